@@ -281,11 +281,14 @@ class TimelineStore {
   }
 
   // Computed: total tracked time in ms
+  // chunks that only contain background events (e.g. music) don't count as tracked time
   get totalTrackedMs(): number {
-    return this.chunks.reduce(
-      (sum, chunk) => sum + (getUnixMs(chunk.to_exclusive) - getUnixMs(chunk.from)),
-      0
-    );
+    return this.chunks
+      .filter((chunk) => chunk.tags.some(([tag]) => tag === "timetrackrs-tracked"))
+      .reduce(
+        (sum, chunk) => sum + (getUnixMs(chunk.to_exclusive) - getUnixMs(chunk.from)),
+        0
+      );
   }
 
   // Computed: breakdown by top-level category
@@ -0,0 +1,7 @@
+CREATE TABLE config.import_cursors (
+    -- name of the import source, e.g. the name of a scheduled import
+    source_id text PRIMARY KEY NOT NULL,
+    -- JSON, see ImportCursor
+    cursor text NOT NULL,
+    updated_timestamp_unix_ms integer NOT NULL
+);
@@ -0,0 +1,5 @@
+CREATE TABLE config.secrets (
+    -- e.g. redaction-hash-key
+    name text PRIMARY KEY NOT NULL,
+    value blob NOT NULL
+);
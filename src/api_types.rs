@@ -36,6 +36,21 @@ pub struct InvalidateRangeRequest {
     pub to: Timestamptz,
 }
 
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct BatteryLevelsRequest {
+    pub after: Timestamptz,
+    pub before: Timestamptz,
+}
+
+/// the battery state stored in a captured window event
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct BatteryLevel {
+    pub timestamp: Timestamptz,
+    pub percent: f64,
+    pub state: Option<String>,
+    pub ac_online: Option<bool>,
+}
+
 macro_rules! make_thingois {
     (pub enum $name:ident {
         $($r:ident { request: $req:ty, response: $resp:ty }),+
@@ -111,6 +126,22 @@ make_thingois! {
         timestamp_search {
             request: TimestampSearchRequest,
             response: Option<Timestamptz>
+        },
+        battery_levels {
+            request: BatteryLevelsRequest,
+            response: Vec<BatteryLevel>
+        },
+        pause_state {
+            request: (),
+            response: PauseState
+        },
+        pause {
+            request: PauseRequest,
+            response: PauseState
+        },
+        resume {
+            request: (),
+            response: PauseState
         }
     }
 }
@@ -1,34 +1,48 @@
+use ::timetrackrs::import::dry_run::dry_run_import;
 use ::timetrackrs::prelude::*;
-use futures::stream::StreamExt;
+
+#[derive(StructOpt)]
+struct Args {
+    /// persist the import progress under this name, so the next import with the same name skips already imported data
+    #[structopt(long)]
+    incremental: Option<String>,
+    /// don't insert anything, only show the time range, counts per data type and the overlap with existing events
+    #[structopt(long)]
+    dry_run: bool,
+    /// insert into the databases in this directory instead of the real ones,
+    /// e.g. to inspect the extraction results with TIMETRACKRS_DATA_DIR=<dir> timetrackrs-server
+    #[structopt(long, parse(from_os_str))]
+    scratch_db: Option<std::path::PathBuf>,
+    #[structopt(subcommand)]
+    import: ImportArgs,
+}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let _guard = util::init_logging()?;
 
-    let opt = ImportArgs::from_args();
-    let mut data = opt.import().await?;
-    log::info!("inserting...");
-    let db = init_db_pool().await?;
+    let opt = Args::from_args();
+    let db = match &opt.scratch_db {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("creating scratch db dir {dir:?}"))?;
+            init_db_pool_in_dir(dir).await?
+        }
+        None => init_db_pool().await?,
+    };
 
-    let mut total_updated: u64 = 0;
-    let mut total_seen: u64 = 0;
-    let mut total_existed: u64 = 0;
-    while let Some(chunk) = data.next().await {
-        let chunk = chunk?;
-        let len = chunk.len() as u64;
-        let updated = db
-            .insert_events_if_needed(chunk)
-            .await
-            .context("inserting events")?;
-        total_updated += updated;
-        total_seen += len;
-        total_existed += len - updated;
-        log::info!(
-            "successfully inserted {}/{} entries ({} already existed)",
-            total_updated,
-            total_seen,
-            total_existed
-        );
+    if opt.dry_run {
+        let report = dry_run_import(&db, &opt.import, opt.incremental.as_deref()).await?;
+        print!("{report}");
+        return Ok(());
     }
+
+    let stats = run_import(&db, &opt.import, opt.incremental.as_deref()).await?;
+    log::info!(
+        "done. inserted {}/{} entries ({} already existed)",
+        stats.inserted,
+        stats.seen,
+        stats.seen - stats.inserted
+    );
     Ok(())
 }
@@ -0,0 +1,96 @@
+// pause / resume the capturers of a running timetrackrs via its HTTP API
+use timetrackrs::prelude::*;
+use timetrackrs::util::init_logging;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    about = "Pause or resume capturing of the running timetrackrs. The pause is not persisted, restarting timetrackrs resumes capturing"
+)]
+struct Args {
+    /// address of the timetrackrs server
+    #[structopt(long, default_value = "127.0.0.1:52714")]
+    server: String,
+    #[structopt(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(StructOpt, Debug)]
+enum Cmd {
+    /// stop capturing until resumed
+    Pause {
+        /// automatically resume after this many minutes
+        #[structopt(long = "for")]
+        minutes: Option<u64>,
+        #[structopt(long)]
+        reason: Option<String>,
+    },
+    Resume,
+    Status,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response<T> {
+    data: T,
+}
+
+/// minimal HTTP/1.1 request, the server only ever runs on localhost
+async fn request(server: &str, method: &str, path: &str, body: &str) -> anyhow::Result<String> {
+    let mut stream = tokio::net::TcpStream::connect(server)
+        .await
+        .with_context(|| format!("Could not connect to timetrackrs at {server}. Is it running?"))?;
+    let req = format!(
+        "{method} {path} HTTP/1.1\r\nHost: {server}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(req.as_bytes()).await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .context("invalid HTTP response")?;
+    if !head.starts_with("HTTP/1.1 200") {
+        anyhow::bail!("{} {} failed: {}\n{}", method, path, head, body);
+    }
+    Ok(body.to_string())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let _guard = init_logging()?;
+    let args = Args::from_args();
+    let (method, path, body) = match args.cmd {
+        Cmd::Pause { minutes, reason } => (
+            "POST",
+            "/api/pause",
+            serde_json::to_string(&PauseRequest {
+                duration_secs: minutes
+                    .map(|m| m.checked_mul(60).context("--for is too long"))
+                    .transpose()?,
+                reason,
+            })?,
+        ),
+        Cmd::Resume => ("POST", "/api/resume", "".to_string()),
+        Cmd::Status => ("GET", "/api/pause-state", "".to_string()),
+    };
+    let response = request(&args.server, method, path, &body).await?;
+    let state: Response<PauseState> =
+        serde_json::from_str(&response).context("Could not parse response")?;
+    let state = state.data;
+    if state.paused {
+        print!("paused");
+        if let Some(since) = state.since {
+            print!(" since {}", since.with_timezone(&Local).format("%H:%M"));
+        }
+        if let Some(until) = state.until {
+            print!(" until {}", until.with_timezone(&Local).format("%H:%M"));
+        }
+        if let Some(reason) = state.reason {
+            print!(" ({reason})");
+        }
+        println!();
+    } else {
+        println!("capturing");
+    }
+    Ok(())
+}
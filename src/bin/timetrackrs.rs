@@ -71,6 +71,9 @@ async fn main() -> anyhow::Result<()> {
     for c in config.capturers {
         features.push(tokio::spawn(capture_loop(db.clone(), c)));
     }
+    for i in config.imports {
+        features.push(tokio::spawn(import_loop(db.clone(), i)));
+    }
     if let Some(server) = config.server {
         features.push(tokio::spawn(timetrackrs::server::server::run_server(
             db.clone(),
@@ -12,11 +12,35 @@ const FS: &[fn() -> std::borrow::Cow<'static, str>] = &[
     // DbEvent::type_script_ify,
     EventData::type_script_ify,
     linux::x11_types::X11EventData::type_script_ify,
+    context::CaptureContext::type_script_ify,
     linux::x11_types::X11WindowData::type_script_ify,
     linux::x11_types::X11WindowGeometry::type_script_ify,
+    linux::x11_types::X11Monitor::type_script_ify,
     crate::process::ProcessData::type_script_ify,
     linux::x11_types::NetworkInfo::type_script_ify,
     linux::x11_types::WifiInterface::type_script_ify,
+    linux::x11_types::NetworkInterface::type_script_ify,
+    linux::x11_types::NetworkInterfaceKind::type_script_ify,
+    linux::x11_types::NetworkGateway::type_script_ify,
+    linux::logind_types::LogindEventData::type_script_ify,
+    linux::input_types::InputActivity::type_script_ify,
+    linux::media_types::MediaUsage::type_script_ify,
+    power::PowerState::type_script_ify,
+    linux::logind_types::SessionState::type_script_ify,
+    linux::mpris_types::MprisEventData::type_script_ify,
+    linux::terminal_types::TerminalContext::type_script_ify,
+    timetrackrs::extract::fetchers::git::GitHead::type_script_ify,
+    linux::container_types::ContainerInfo::type_script_ify,
+    linux::container_types::KubeContext::type_script_ify,
+    linux::mpris_types::MprisPlayer::type_script_ify,
+    linux::mpris_types::MprisMetadata::type_script_ify,
+    command::CommandEventData::type_script_ify,
+    command::CommandTagMapping::type_script_ify,
+    redact::Redaction::type_script_ify,
+    redact::TitleRedactionMethod::type_script_ify,
+    pause::PauseState::type_script_ify,
+    pause::PauseRequest::type_script_ify,
+    pause::PausedEntry::type_script_ify,
     macos::types::MacOSEventData::type_script_ify,
     macos::types::MacOSWindow::type_script_ify,
     macos::types::MacOSProcessData::type_script_ify,
@@ -35,6 +59,8 @@ const FS: &[fn() -> std::borrow::Cow<'static, str>] = &[
     api_types::ApiResponse::<String>::type_script_ify,
     api_types::SingleExtractedChunk::type_script_ify,
     api_types::TimestampSearchRequest::type_script_ify,
+    api_types::BatteryLevelsRequest::type_script_ify,
+    api_types::BatteryLevel::type_script_ify,
     api_types::TimeRangeRequest::type_script_ify,
     api_types::InvalidateRangeRequest::type_script_ify,
     api_types::SingleEventsRequest::type_script_ify,
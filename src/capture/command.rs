@@ -0,0 +1,165 @@
+// run a user supplied program every capture interval and store its json output.
+// which parts of the output become tags is configured with a list of json pointers, e.g.
+// { "Command": { "program": "pomodoro-status", "args": ["--json"], "tags": [{ "tag": "pomodoro-state", "pointer": "/state" }] } }
+use crate::prelude::*;
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandCaptureArgs {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// the program is killed if it runs longer than this
+    #[serde(default = "default_timeout")]
+    pub timeout: Duration,
+    #[serde(default)]
+    pub tags: Vec<CommandTagMapping>,
+    /// if true, the events don't count as tracked time (e.g. for context like the vpn name)
+    #[serde(default)]
+    pub background: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct CommandTagMapping {
+    pub tag: String,
+    /// json pointer (RFC 6901) into the output, e.g. /pane/current_path. arrays add one tag value per element
+    pub pointer: String,
+}
+
+/// the mapping is stored with the event, so changing the config later does not change how old events are extracted
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct CommandEventData {
+    pub program: String,
+    pub output: J,
+    pub tags: Vec<CommandTagMapping>,
+    #[serde(default)]
+    pub background: bool,
+}
+
+fn tag_values(value: &J) -> Vec<String> {
+    match value {
+        J::Null => vec![],
+        J::String(s) => vec![s.clone()],
+        J::Array(a) => a.iter().flat_map(tag_values).collect(),
+        other => vec![other.to_string()],
+    }
+}
+
+impl ExtractInfo for CommandEventData {
+    fn extract_info(&self) -> Option<Tags> {
+        let mut tags = Tags::new();
+        for mapping in &self.tags {
+            if let Some(value) = self.output.pointer(&mapping.pointer) {
+                for v in tag_values(value) {
+                    tags.add(&mapping.tag, v);
+                }
+            }
+        }
+        Some(tags)
+    }
+
+    fn is_background(&self) -> bool {
+        self.background
+    }
+}
+
+impl CapturerCreator for CommandCaptureArgs {
+    fn create_capturer(&self) -> anyhow::Result<Box<dyn Capturer>> {
+        Ok(Box::new(CommandCapturer { args: self.clone() }))
+    }
+}
+
+pub struct CommandCapturer {
+    args: CommandCaptureArgs,
+}
+
+#[async_trait]
+impl Capturer for CommandCapturer {
+    async fn capture(&mut self) -> anyhow::Result<EventData> {
+        let CommandCaptureArgs {
+            program,
+            args,
+            timeout,
+            tags,
+            background,
+        } = &self.args;
+        let output = tokio::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(*timeout, output)
+            .await
+            .with_context(|| format!("{program} did not finish within {timeout:?}"))?
+            .with_context(|| format!("Could not run {program}"))?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "{} failed with {}: {}",
+                program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        let output: J = serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("{program} did not output valid json"))?;
+        Ok(EventData::command_v1(CommandEventData {
+            program: program.clone(),
+            output,
+            tags: tags.clone(),
+            background: *background,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_command_capture() -> anyhow::Result<()> {
+        let args = CommandCaptureArgs {
+            program: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                r#"echo '{"pane": {"path": "/home/user/project"}, "labels": ["a", "b"], "count": 3, "missing": null}'"#
+                    .to_string(),
+            ],
+            timeout: default_timeout(),
+            tags: [
+                ("tmux-pane-path", "/pane/path"),
+                ("label", "/labels"),
+                ("count", "/count"),
+                ("missing", "/missing"),
+                ("not-there", "/foo/bar"),
+            ]
+            .into_iter()
+            .map(|(tag, pointer)| CommandTagMapping {
+                tag: tag.to_string(),
+                pointer: pointer.to_string(),
+            })
+            .collect(),
+            background: false,
+        };
+        let data = args.create_capturer()?.capture().await?;
+        let tags = data.extract_info().unwrap();
+        assert!(tags.has_value("tmux-pane-path", "/home/user/project"));
+        assert!(tags.has_value("label", "a"));
+        assert!(tags.has_value("label", "b"));
+        assert!(tags.has_value("count", "3"));
+        assert!(!tags.has("missing"));
+        assert!(!tags.has("not-there"));
+
+        let slow = CommandCaptureArgs {
+            program: "sleep".to_string(),
+            args: vec!["10".to_string()],
+            timeout: Duration::from_millis(100),
+            tags: vec![],
+            background: false,
+        };
+        assert!(slow.create_capturer()?.capture().await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+// the state of the session and the system that is stored in every window event next to the window data (must be cross-platform).
+// the capturers only fill in the network and terminal, the rest is added to the event in merge_capture_context after capturing,
+// except for the input activity, which is only known once the event has ended (see merge_input_activity)
+use super::linux::{input_types, logind_types, media_types, mpris_types};
+use super::power::{self, PowerState};
+use crate::extract::fetchers::git::{current_git_heads, GitHead};
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, Default)]
+pub struct CaptureContext {
+    pub network: Option<NetworkInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redactions: Vec<Redaction>,
+    /// set if a logind capturer is running
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_state: Option<SessionState>,
+    /// set if input activity is counted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<InputActivity>,
+    /// applications using the microphone, camera or a screen cast
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<MediaUsage>,
+    /// media players playing a video, set if an MPRIS capturer is running
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub playing_video: Vec<String>,
+    /// battery and AC state
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power: Option<PowerState>,
+    /// what runs in the focused window if it is a terminal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<TerminalContext>,
+    /// the branches checked out in the repositories of the focused window's files and working directories
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub git: Vec<GitHead>,
+}
+
+impl CaptureContext {
+    /// a locked session is idle immediately, a call or a video without input is not idle
+    pub fn is_idle(&self, ms_since_user_input: u32) -> bool {
+        self.session_state.is_some_and(|s| s.is_idle())
+            || (super::pc_common::is_idle(Duration::from_millis(ms_since_user_input as u64))
+                && !self.media.as_ref().is_some_and(|m| m.in_call())
+                && self.playing_video.is_empty())
+    }
+
+    pub fn add_tags(&self, tags: &mut Tags) {
+        if let Some(state) = self.session_state {
+            tags.add("session-state", state.as_str());
+        }
+        if let Some(input) = &self.input {
+            input.add_tags(tags);
+        }
+        if let Some(media) = &self.media {
+            media.add_tags(tags);
+        }
+        if !self.playing_video.is_empty() {
+            tags.add("media-watching-video", "true");
+        }
+        if let Some(power) = &self.power {
+            power.add_tags(tags);
+        }
+        if let Some(network) = &self.network {
+            network.add_tags(tags);
+        }
+        if let Some(terminal) = &self.terminal {
+            terminal.add_tags(tags);
+        }
+        for branch in self.git.iter().filter_map(|head| head.branch.as_ref()) {
+            tags.add("git-branch", branch);
+        }
+    }
+}
+
+/// the context of window events, None for all other events
+pub fn capture_context_mut(data: &mut EventData) -> Option<&mut CaptureContext> {
+    match data {
+        EventData::x11_v2(d) => Some(&mut d.context),
+        EventData::sway_v1(d) => Some(&mut d.context),
+        EventData::hyprland_v1(d) => Some(&mut d.context),
+        EventData::gnome_v1(d) => Some(&mut d.context),
+        EventData::kde_v1(d) => Some(&mut d.context),
+        EventData::wlr_toplevel_v1(d) => Some(&mut d.context),
+        _ => None,
+    }
+}
+
+/// store the session state, the media usage (if enabled), the players playing a video, the power state
+/// and the checked out git branches in window events
+pub async fn merge_capture_context(data: &mut EventData, media_usage: bool) {
+    if capture_context_mut(data).is_none() {
+        return;
+    }
+    // the repositories are looked up later by the git fetchers, but their results are cached, so the branch
+    // has to be read now
+    let git = data
+        .extract_info()
+        .map(|tags| current_git_heads(&tags))
+        .unwrap_or_default();
+    let Some(context) = capture_context_mut(data) else {
+        return;
+    };
+    context.git = git;
+    context.session_state = logind_types::session_state();
+    context.playing_video = mpris_types::playing_video();
+    if media_usage {
+        context.media = media_types::get_media_usage()
+            .await
+            .filter(|m| !m.is_empty());
+    }
+    context.power = Some(power::get_power_state()).filter(|p| *p != PowerState::default());
+}
+
+/// store the input activity since the previous call in an event that just ended.
+/// the activity during other events (e.g. while paused) is dropped
+pub fn merge_input_activity(data: &mut EventData) {
+    let input = input_types::take_input_activity();
+    if let Some(context) = capture_context_mut(data) {
+        context.input = input;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_flattened_context() -> anyhow::Result<()> {
+        // the context fields are stored next to the window data, as before they were grouped
+        let json = serde_json::json!({
+            "desktop_names": [],
+            "current_desktop_id": 0,
+            "focused_window": 1,
+            "ms_since_user_input": 0,
+            "ms_until_screensaver": 0,
+            "screensaver_window": 0,
+            "network": null,
+            "windows": [],
+            "session_state": "Locked",
+            "power": {"battery_percent": 50.0, "ac_online": false},
+        });
+        let data: X11EventData = serde_json::from_value(json)?;
+        assert_eq!(data.context.session_state, Some(SessionState::Locked));
+        assert!(data.context.is_idle(data.ms_since_user_input));
+        let mut tags = Tags::new();
+        data.context.add_tags(&mut tags);
+        assert!(tags.has_value("device-power-source", "battery"));
+
+        let json = serde_json::to_value(&data)?;
+        assert_eq!(json["power"]["battery_percent"], 50.0);
+        assert!(json.get("context").is_none());
+
+        // a video keeps a window event without input from being idle
+        let mut context = CaptureContext::default();
+        assert!(context.is_idle(600_000));
+        context.playing_video = vec!["mpv Media Player".to_string()];
+        assert!(!context.is_idle(600_000));
+        Ok(())
+    }
+}
@@ -0,0 +1,514 @@
+// find the container and kubernetes context of the foreground processes of the focused terminal:
+// whether they run inside a container (cgroup path, /.dockerenv, devcontainer env vars) or attach to one
+// (docker exec, podman exec, kubectl exec), and which kubeconfig context kubectl and similar tools use
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::container_types::{ContainerInfo, KubeContext};
+use crate::{capture::process::ProcessData, prelude::*};
+
+lazy_static! {
+    static ref KUBE_TOOLS: regex::Regex =
+        regex::Regex::new(r"^(kubectl|kubectl\.\S+|k9s|helm|stern|kubectx|kubens|kustomize|oc)$")
+            .unwrap();
+}
+
+fn environ(pid: i32) -> HashMap<String, String> {
+    let environ = std::fs::read(format!("/proc/{pid}/environ")).unwrap_or_default();
+    environ
+        .split(|b| *b == 0)
+        .filter_map(|var| {
+            let var = String::from_utf8_lossy(var);
+            let (key, value) = var.split_once('=')?;
+            Some((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// runtime and id of the container from the cgroup path in /proc/pid/cgroup, e.g.
+/// 0::/system.slice/docker-<id>.scope or 0::/kubepods/burstable/pod<uid>/<id>
+fn parse_cgroup(cgroup: &str) -> Option<(&'static str, String)> {
+    let is_id = |s: &str| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit());
+    cgroup.lines().find_map(|line| {
+        let (_, path) = line.rsplit_once(':')?;
+        let kubernetes = path.contains("kubepods");
+        let mut previous = "";
+        for segment in path.split('/') {
+            let segment = segment.trim_end_matches(".scope");
+            let found = [
+                ("docker-", "docker"),
+                ("libpod-", "podman"),
+                ("cri-containerd-", "containerd"),
+                ("crio-", "cri-o"),
+            ]
+            .iter()
+            .find_map(|(prefix, runtime)| {
+                segment
+                    .strip_prefix(prefix)
+                    .filter(|id| is_id(id))
+                    .map(|id| (*runtime, id))
+            })
+            .or_else(|| {
+                (is_id(segment) && (previous == "docker" || kubernetes))
+                    .then_some(("docker", segment))
+            });
+            if let Some((runtime, id)) = found {
+                let runtime = if kubernetes { "kubernetes" } else { runtime };
+                return Some((runtime, id.to_string()));
+            }
+            previous = segment;
+        }
+        None
+    })
+}
+
+/// the container the process runs in, if any
+fn container_of_process(pid: i32) -> Option<ContainerInfo> {
+    let env = environ(pid);
+    let cgroup = std::fs::read_to_string(format!("/proc/{pid}/cgroup"))
+        .ok()
+        .and_then(|c| parse_cgroup(&c));
+    // set by vs code, the devcontainers cli and github codespaces
+    let devcontainer = ["REMOTE_CONTAINERS", "DEVCONTAINER", "CODESPACES"]
+        .iter()
+        .any(|var| env.get(*var).map(|v| v.as_str()) == Some("true"));
+    // podman and systemd-nspawn set $container
+    let env_runtime = env.get("container").filter(|r| !r.is_empty());
+    let dockerenv = Path::new(&format!("/proc/{pid}/root/.dockerenv")).exists();
+    let runtime = match (&cgroup, env_runtime) {
+        (Some((runtime, _)), _) => runtime.to_string(),
+        (None, Some(runtime)) => runtime.clone(),
+        (None, None) if devcontainer => "devcontainer".to_string(),
+        (None, None) if dockerenv => "docker".to_string(),
+        (None, None) => return None,
+    };
+    Some(ContainerInfo {
+        runtime,
+        id: cgroup.map(|(_, id)| id),
+        name: None,
+        image: None,
+    })
+}
+
+/// the positional arguments of a command line, skipping options and their values
+fn positional_args<'a>(args: &'a [String], options_with_value: &[&str]) -> Vec<&'a str> {
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            positional.extend(args.map(|a| a.as_str()));
+            break;
+        }
+        if arg.starts_with('-') && arg.len() > 1 {
+            if !arg.contains('=') && options_with_value.contains(&arg.as_str()) {
+                args.next();
+            }
+            continue;
+        }
+        positional.push(arg.as_str());
+    }
+    positional
+}
+
+/// the value of an option given as `--opt value` or `--opt=value`
+fn option_value<'a>(args: &'a [String], names: &[&str]) -> Option<&'a str> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        names.iter().find_map(|name| {
+            if arg == name {
+                args.get(i + 1).map(|v| v.as_str())
+            } else {
+                arg.strip_prefix(name)?.strip_prefix('=')
+            }
+        })
+    })
+}
+
+const DOCKER_OPTIONS_WITH_VALUE: &[&str] = &[
+    "-c",
+    "--context",
+    "-H",
+    "--host",
+    "-l",
+    "--log-level",
+    "--config",
+    "-e",
+    "--env",
+    "--env-file",
+    "-u",
+    "--user",
+    "-w",
+    "--workdir",
+    "--detach-keys",
+    "--name",
+    "-v",
+    "--volume",
+    "-p",
+    "--publish",
+    "--entrypoint",
+    "--network",
+    "--platform",
+    "-f",
+    "--file",
+    "--project-name",
+    "--profile",
+];
+
+/// the container a docker or podman command line attaches to, e.g. docker exec -it web bash -> web.
+/// for `run`, the image is known but the container usually has no name
+fn parse_container_cmd(cmd: &[String]) -> Option<ContainerInfo> {
+    let runtime = Path::new(cmd.first()?).file_name()?.to_str()?;
+    if runtime != "docker" && runtime != "podman" {
+        return None;
+    }
+    let args = positional_args(&cmd[1..], DOCKER_OPTIONS_WITH_VALUE);
+    let args = match args[..] {
+        ["container", ..] => &args[1..],
+        _ => &args[..],
+    };
+    let (name, image) = match args {
+        ["exec" | "attach", container, ..] => (Some(container.to_string()), None),
+        ["compose", "exec" | "attach", service, ..] => (Some(service.to_string()), None),
+        ["run", image, ..] => (
+            option_value(cmd, &["--name"]).map(|n| n.to_string()),
+            Some(image.to_string()),
+        ),
+        _ => return None,
+    };
+    Some(ContainerInfo {
+        runtime: runtime.to_string(),
+        id: None,
+        name,
+        image,
+    })
+}
+
+const KUBECTL_OPTIONS_WITH_VALUE: &[&str] = &[
+    "-n",
+    "--namespace",
+    "--context",
+    "--kubeconfig",
+    "--cluster",
+    "--user",
+    "-c",
+    "--container",
+    "-f",
+    "--filename",
+    "--image",
+    "--target",
+    "--profile",
+    "--pod-running-timeout",
+];
+
+/// the pod a kubectl command line attaches to, e.g. kubectl exec -it web-0 -- sh -> web-0
+fn parse_kubectl_pod(cmd: &[String]) -> Option<ContainerInfo> {
+    let args = positional_args(cmd.get(1..)?, KUBECTL_OPTIONS_WITH_VALUE);
+    match args[..] {
+        ["exec" | "attach" | "debug" | "port-forward", pod, ..] => Some(ContainerInfo {
+            runtime: "kubernetes".to_string(),
+            id: None,
+            name: Some(pod.trim_start_matches("pod/").to_string()),
+            image: option_value(cmd, &["--image"]).map(|i| i.to_string()),
+        }),
+        _ => None,
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+/// the current context and its namespace. only understands the block style kubectl writes, e.g.
+///
+/// contexts:
+/// - context:
+///     cluster: prod
+///     namespace: web
+///   name: prod
+/// current-context: prod
+fn parse_kubeconfig(config: &str) -> Option<KubeContext> {
+    let current = config
+        .lines()
+        .find_map(|l| l.strip_prefix("current-context:"))
+        .map(unquote)
+        .filter(|c| !c.is_empty())?;
+    let mut contexts: Vec<(Option<&str>, Option<&str>)> = vec![];
+    let mut in_contexts = false;
+    for line in config.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '-']) {
+            in_contexts = line.trim_end() == "contexts:";
+            continue;
+        }
+        if !in_contexts {
+            continue;
+        }
+        let trimmed = line.trim_start();
+        let entry = match trimmed.strip_prefix("- ") {
+            Some(rest) if line.len() - trimmed.len() <= 2 => {
+                contexts.push((None, None));
+                rest
+            }
+            _ => trimmed,
+        };
+        let Some(context) = contexts.last_mut() else {
+            continue;
+        };
+        if let Some((key, value)) = entry.split_once(':') {
+            match key.trim() {
+                "name" => context.0 = Some(unquote(value)),
+                "namespace" => context.1 = Some(unquote(value)),
+                _ => {}
+            }
+        }
+    }
+    let namespace = contexts
+        .iter()
+        .find(|(name, _)| *name == Some(current))
+        .and_then(|(_, namespace)| *namespace);
+    Some(KubeContext {
+        context: current.to_string(),
+        namespace: namespace.filter(|n| !n.is_empty()).map(|n| n.to_string()),
+    })
+}
+
+/// like kubectl: --kubeconfig, the first file in $KUBECONFIG that sets a current context, ~/.kube/config
+fn kubeconfig(process: &ProcessData) -> Option<KubeContext> {
+    let env = environ(process.pid);
+    let paths: Vec<PathBuf> = match option_value(&process.cmd, &["--kubeconfig"]) {
+        Some(path) => vec![PathBuf::from(path)],
+        None => match env.get("KUBECONFIG").filter(|k| !k.is_empty()) {
+            Some(paths) => std::env::split_paths(paths).collect(),
+            None => {
+                let home = env.get("HOME").map(PathBuf::from).or_else(|| {
+                    directories_next::BaseDirs::new().map(|d| d.home_dir().to_path_buf())
+                })?;
+                vec![home.join(".kube/config")]
+            }
+        },
+    };
+    let cwd = process.cwd.as_ref().map(PathBuf::from).unwrap_or_default();
+    paths.iter().find_map(|path| {
+        let config = std::fs::read_to_string(cwd.join(path)).ok()?;
+        parse_kubeconfig(&config)
+    })
+}
+
+/// the kube context of the innermost kubernetes tool in the foreground. the command line flags take precedence
+pub fn get_kube_context(foreground: &[ProcessData]) -> Option<KubeContext> {
+    let process = foreground
+        .iter()
+        .rev()
+        .find(|p| KUBE_TOOLS.is_match(&p.name))?;
+    let from_config = kubeconfig(process);
+    let context = option_value(&process.cmd, &["--context", "--kube-context"])
+        .map(|c| c.to_string())
+        .or_else(|| from_config.as_ref().map(|c| c.context.clone()))?;
+    let namespace = option_value(&process.cmd, &["-n", "--namespace"])
+        .map(|n| n.to_string())
+        .or_else(|| {
+            from_config
+                .filter(|c| c.context == context)
+                .and_then(|c| c.namespace)
+        });
+    Some(KubeContext { context, namespace })
+}
+
+/// name and image of a docker or podman container, by id or name
+async fn inspect(runtime: &str, container: &str) -> anyhow::Result<(String, String)> {
+    let output = tokio::process::Command::new(runtime)
+        .args([
+            "inspect",
+            "--type",
+            "container",
+            "--format",
+            "{{.Name}}\t{{.Config.Image}}",
+            container,
+        ])
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(Duration::from_secs(1), output)
+        .await
+        .with_context(|| format!("{runtime} inspect timed out"))?
+        .with_context(|| format!("Could not run {runtime}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} inspect {} failed: {}",
+            runtime,
+            container,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (name, image) = stdout
+        .trim()
+        .split_once('\t')
+        .context("unexpected inspect output")?;
+    Ok((name.trim_start_matches('/').to_string(), image.to_string()))
+}
+
+/// the container the innermost foreground process runs in or attaches to
+pub async fn get_container(foreground: &[ProcessData]) -> Option<ContainerInfo> {
+    let mut container = foreground.iter().rev().find_map(|process| {
+        let attached = match process.name.as_str() {
+            "docker" | "podman" => parse_container_cmd(&process.cmd),
+            name if KUBE_TOOLS.is_match(name) => parse_kubectl_pod(&process.cmd),
+            _ => None,
+        };
+        attached.or_else(|| container_of_process(process.pid))
+    })?;
+    let runtime = match container.runtime.as_str() {
+        // devcontainers are usually run by docker
+        "docker" | "devcontainer" => "docker",
+        "podman" => "podman",
+        _ => return Some(container),
+    };
+    if let Some(target) = container.id.as_ref().or(container.name.as_ref()) {
+        match inspect(runtime, target).await {
+            Ok((name, image)) => {
+                container.name = Some(name);
+                container.image = Some(image);
+            }
+            Err(e) => log::debug!("could not inspect container: {:?}", e),
+        }
+    }
+    Some(container)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cmd(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_cgroup() {
+        let id = "4f1c2b8a9d3e".repeat(5) + "abcd";
+        assert_eq!(
+            parse_cgroup(&format!("0::/system.slice/docker-{id}.scope\n")),
+            Some(("docker", id.clone()))
+        );
+        assert_eq!(
+            parse_cgroup(&format!(
+                "0::/user.slice/user-1000.slice/user@1000.service/user.slice/libpod-{id}.scope/container\n"
+            )),
+            Some(("podman", id.clone()))
+        );
+        assert_eq!(
+            parse_cgroup(&format!("12:pids:/docker/{id}\n")),
+            Some(("docker", id.clone()))
+        );
+        assert_eq!(
+            parse_cgroup(&format!(
+                "0::/kubepods.slice/kubepods-burstable.slice/cri-containerd-{id}.scope\n"
+            )),
+            Some(("kubernetes", id))
+        );
+        assert_eq!(
+            parse_cgroup("0::/user.slice/user-1000.slice/session-2.scope\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_container_cmd() {
+        let exec = parse_container_cmd(&cmd(&[
+            "docker", "exec", "-it", "-u", "root", "-e", "A=b", "web", "bash",
+        ]))
+        .unwrap();
+        assert_eq!(exec.runtime, "docker");
+        assert_eq!(exec.name.as_deref(), Some("web"));
+
+        let run = parse_container_cmd(&cmd(&[
+            "/usr/bin/podman",
+            "run",
+            "--rm",
+            "-it",
+            "--name=scratch",
+            "-v",
+            "/src:/src",
+            "docker.io/library/rust:1",
+            "bash",
+        ]))
+        .unwrap();
+        assert_eq!(run.runtime, "podman");
+        assert_eq!(run.name.as_deref(), Some("scratch"));
+        assert_eq!(run.image.as_deref(), Some("docker.io/library/rust:1"));
+
+        let compose = parse_container_cmd(&cmd(&[
+            "docker", "compose", "-f", "dev.yml", "exec", "db", "psql",
+        ]))
+        .unwrap();
+        assert_eq!(compose.name.as_deref(), Some("db"));
+
+        assert_eq!(parse_container_cmd(&cmd(&["docker", "ps"])), None);
+
+        let pod = parse_kubectl_pod(&cmd(&[
+            "kubectl",
+            "-n",
+            "shop",
+            "exec",
+            "-it",
+            "pod/web-0",
+            "-c",
+            "app",
+            "--",
+            "sh",
+        ]))
+        .unwrap();
+        assert_eq!(pod.runtime, "kubernetes");
+        assert_eq!(pod.name.as_deref(), Some("web-0"));
+        assert_eq!(parse_kubectl_pod(&cmd(&["kubectl", "get", "pods"])), None);
+    }
+
+    #[test]
+    fn test_parse_kubeconfig() {
+        let config = r#"apiVersion: v1
+clusters:
+- cluster:
+    server: https://prod.example.com
+  name: prod
+contexts:
+- context:
+    cluster: prod
+    user: admin
+  name: dev
+- context:
+    cluster: prod
+    namespace: "shop"
+    user: admin
+  name: prod-admin
+current-context: prod-admin
+kind: Config
+users:
+- name: admin
+  user:
+    token: secret
+"#;
+        assert_eq!(
+            parse_kubeconfig(config),
+            Some(KubeContext {
+                context: "prod-admin".to_string(),
+                namespace: Some("shop".to_string()),
+            })
+        );
+        let config = config.replace("current-context: prod-admin", "current-context: dev");
+        assert_eq!(parse_kubeconfig(&config).unwrap().namespace, None);
+        assert_eq!(
+            parse_kubeconfig("apiVersion: v1\ncurrent-context: \"\"\n"),
+            None
+        );
+    }
+}
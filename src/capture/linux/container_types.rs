@@ -0,0 +1,46 @@
+// container and kubernetes context types (must be cross-platform)
+use crate::prelude::*;
+
+/// a container the foreground process of the focused terminal runs in or is attached to
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, PartialEq)]
+pub struct ContainerInfo {
+    /// docker, podman, containerd, cri-o, kubernetes or devcontainer
+    pub runtime: String,
+    pub id: Option<String>,
+    /// the container name, or the pod name for kubernetes
+    pub name: Option<String>,
+    pub image: Option<String>,
+}
+
+/// the kubeconfig context used by kubectl and similar tools in the focused terminal
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, PartialEq)]
+pub struct KubeContext {
+    pub context: String,
+    /// None if the context does not set a namespace
+    pub namespace: Option<String>,
+}
+
+impl ContainerInfo {
+    pub fn add_tags(&self, tags: &mut Tags) {
+        tags.add("container-runtime", &self.runtime);
+        if let Some(name) = &self.name {
+            tags.add("container-name", name);
+        } else if let Some(id) = &self.id {
+            tags.add("container-id", id.chars().take(12).collect::<String>());
+        }
+        if let Some(image) = &self.image {
+            tags.add("container-image", image);
+        }
+    }
+}
+
+impl KubeContext {
+    pub fn add_tags(&self, tags: &mut Tags) {
+        tags.add("k8s-context", &self.context);
+        // same as kubectl
+        tags.add(
+            "k8s-namespace",
+            self.namespace.as_deref().unwrap_or("default"),
+        );
+    }
+}
@@ -0,0 +1,166 @@
+// GNOME Shell does not implement any protocol to get the focused window, so this uses
+// the "Window Calls" extension (https://github.com/ickyicky/window-calls) if it is installed,
+// and otherwise org.gnome.Shell.Introspect (only allowed with unsafe mode or for allowlisted apps, and without pids).
+// idle time comes from mutter's idle monitor
+use std::collections::HashMap;
+
+use super::terminal::get_terminal_context;
+use crate::{capture::process::get_process_data, prelude::*, util::OsInfo};
+
+use zbus::zvariant::OwnedValue;
+
+#[zbus::proxy(
+    default_service = "org.gnome.Shell",
+    interface = "org.gnome.Shell.Extensions.Windows",
+    default_path = "/org/gnome/Shell/Extensions/Windows"
+)]
+trait WindowCalls {
+    /// json array of windows
+    fn list(&self) -> zbus::Result<String>;
+    /// newer versions of the extension don't include the title in List
+    fn get_title(&self, winid: u32) -> zbus::Result<String>;
+}
+
+#[zbus::proxy(
+    default_service = "org.gnome.Shell",
+    interface = "org.gnome.Shell.Introspect",
+    default_path = "/org/gnome/Shell/Introspect"
+)]
+trait Introspect {
+    fn get_windows(&self) -> zbus::Result<HashMap<u64, HashMap<String, OwnedValue>>>;
+}
+
+#[zbus::proxy(
+    default_service = "org.gnome.Mutter.IdleMonitor",
+    interface = "org.gnome.Mutter.IdleMonitor",
+    default_path = "/org/gnome/Mutter/IdleMonitor/Core"
+)]
+trait IdleMonitor {
+    fn get_idletime(&self) -> zbus::Result<u64>;
+}
+
+pub struct GnomeCapturer {
+    conn: Option<zbus::Connection>,
+    os_info: OsInfo,
+    system: sysinfo::System,
+}
+
+#[derive(Debug, Deserialize)]
+struct WindowCallsWindow {
+    id: u32,
+    #[serde(default)]
+    title: Option<String>,
+    wm_class: Option<String>,
+    wm_class_instance: Option<String>,
+    pid: Option<i32>,
+    #[serde(default)]
+    focus: bool,
+}
+
+impl GnomeCapturer {
+    pub(crate) fn new() -> GnomeCapturer {
+        GnomeCapturer {
+            conn: None,
+            os_info: util::get_os_info(),
+            system: sysinfo::System::new(),
+        }
+    }
+
+    async fn focused_from_extension(
+        conn: &zbus::Connection,
+    ) -> anyhow::Result<Option<DesktopWindow>> {
+        let proxy = WindowCallsProxy::new(conn).await?;
+        let windows: Vec<WindowCallsWindow> = serde_json::from_str(&proxy.list().await?)
+            .context("parsing window-calls List response")?;
+        let Some(window) = windows.into_iter().find(|w| w.focus) else {
+            return Ok(None);
+        };
+        let title = match window.title {
+            Some(title) => title,
+            None => proxy.get_title(window.id).await.unwrap_or_default(),
+        };
+        Ok(Some(DesktopWindow {
+            title,
+            app_id: window.wm_class_instance,
+            wm_class: window.wm_class,
+            pid: window.pid,
+        }))
+    }
+
+    async fn focused_from_introspect(
+        conn: &zbus::Connection,
+    ) -> anyhow::Result<Option<DesktopWindow>> {
+        let windows = IntrospectProxy::new(conn).await?.get_windows().await?;
+        let get_string = |props: &HashMap<String, OwnedValue>, key: &str| {
+            props
+                .get(key)
+                .and_then(|v| <&str>::try_from(&**v).ok())
+                .map(|s| s.to_string())
+        };
+        Ok(windows
+            .values()
+            .find(|props| {
+                props
+                    .get("has-focus")
+                    .and_then(|v| bool::try_from(&**v).ok())
+                    .unwrap_or(false)
+            })
+            .map(|props| DesktopWindow {
+                title: get_string(props, "title").unwrap_or_default(),
+                app_id: get_string(props, "app-id")
+                    .map(|id| id.trim_end_matches(".desktop").to_string()),
+                wm_class: get_string(props, "wm-class"),
+                pid: None,
+            }))
+    }
+}
+
+#[async_trait]
+impl Capturer for GnomeCapturer {
+    async fn capture(&mut self) -> anyhow::Result<EventData> {
+        if self.conn.is_none() {
+            self.conn = Some(
+                zbus::Connection::session()
+                    .await
+                    .context("Could not connect to the session bus")?,
+            );
+        }
+        let conn = self.conn.as_ref().unwrap();
+        let focused_window = match GnomeCapturer::focused_from_extension(conn).await {
+            Ok(w) => w,
+            Err(e) => {
+                log::debug!("window-calls extension not available: {:?}", e);
+                GnomeCapturer::focused_from_introspect(conn).await.context(
+                    "Could not get focused window. Install the Window Calls GNOME Shell extension",
+                )?
+            }
+        };
+        let ms_since_user_input = IdleMonitorProxy::new(conn)
+            .await?
+            .get_idletime()
+            .await
+            .map_err(|e| log::info!("could not get idle time: {}", e))
+            .unwrap_or(0);
+        let process = focused_window
+            .as_ref()
+            .and_then(|w| w.pid)
+            .and_then(|pid| get_process_data(&mut self.system, pid as usize));
+        let terminal = match &process {
+            Some(process) => get_terminal_context(&mut self.system, process).await,
+            None => None,
+        };
+        Ok(EventData::gnome_v1(GnomeEventData {
+            os_info: self.os_info.clone(),
+            ms_since_user_input: ms_since_user_input.min(u32::MAX as u64) as u32,
+            focused_window,
+            process,
+            context: CaptureContext {
+                network: linux::network::get_network_info()
+                    .map_err(|e| log::info!("could not get net info: {}", e))
+                    .ok(),
+                terminal,
+                ..Default::default()
+            },
+        }))
+    }
+}
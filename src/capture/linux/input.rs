@@ -0,0 +1,243 @@
+// count keypresses, clicks, scroll steps and mouse movement in the background.
+// on X11, raw events come from the XInput2 extension. on Wayland, clients can't see global input,
+// so the evdev devices in /dev/input are read directly, which needs read permission (e.g. the input group).
+// only the event types are looked at, never which key was pressed
+use std::{fs::File, io::Read, path::Path};
+
+use super::input_types::{count_input, start_counting_input, stop_counting_input, InputCounts};
+use crate::prelude::*;
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xinput::{self, ConnectionExt as _},
+        Event,
+    },
+};
+
+/// starts the watchers in background threads. uses XInput2 on X11 and evdev otherwise
+pub fn watch_input() -> anyhow::Result<()> {
+    let wayland = std::env::var("XDG_SESSION_TYPE").ok().as_deref() == Some("wayland")
+        || std::env::var_os("DISPLAY").is_none();
+    if !wayland {
+        match x11rb::connect(None) {
+            Ok((conn, screen_num)) => {
+                start_counting_input();
+                std::thread::spawn(move || {
+                    if let Err(e) = watch_xinput(conn, screen_num) {
+                        log::warn!("Stopped counting X11 input: {:?}", e);
+                        stop_counting_input();
+                    }
+                });
+                return Ok(());
+            }
+            Err(e) => log::info!("could not connect to X11, trying evdev: {}", e),
+        }
+    }
+    watch_evdev()
+}
+
+const BUTTON_SCROLL: std::ops::RangeInclusive<u32> = 4..=7;
+
+fn watch_xinput(conn: impl Connection, screen_num: usize) -> anyhow::Result<()> {
+    let root = conn.setup().roots[screen_num].root;
+    // as an XI 2.0 client, smooth scrolling is sent as emulated button 4-7 presses
+    conn.xinput_xi_query_version(2, 0)?
+        .reply()
+        .context("XInput2 is not supported")?;
+    conn.xinput_xi_select_events(
+        root,
+        &[xinput::EventMask {
+            deviceid: xinput::Device::ALL_MASTER.into(),
+            mask: vec![(xinput::XIEventMask::RAW_KEY_PRESS
+                | xinput::XIEventMask::RAW_BUTTON_PRESS
+                | xinput::XIEventMask::RAW_MOTION)
+                .into()],
+        }],
+    )?;
+    conn.flush()?;
+    loop {
+        match conn.wait_for_event()? {
+            Event::XinputRawKeyPress(e) => {
+                if u32::from(e.flags) & u32::from(xinput::KeyEventFlags::KEY_REPEAT) == 0 {
+                    count_input(|c| c.keypresses += 1);
+                }
+            }
+            Event::XinputRawButtonPress(e) => {
+                if BUTTON_SCROLL.contains(&e.detail) {
+                    count_input(|c| c.scroll += 1);
+                } else {
+                    count_input(|c| c.clicks += 1);
+                }
+            }
+            Event::XinputRawMotion(e) => {
+                // valuators 0 and 1 are x and y. axisvalues only contains the valuators set in the mask
+                let mut values = e.axisvalues.iter();
+                let (mut dx, mut dy) = (0.0, 0.0);
+                for valuator in 0..2 {
+                    let is_set = e
+                        .valuator_mask
+                        .first()
+                        .is_some_and(|mask| mask & (1 << valuator) != 0);
+                    if is_set {
+                        let v = values.next().context("missing axis value")?;
+                        let v = v.integral as f64 + v.frac as f64 / (1u64 << 32) as f64;
+                        if valuator == 0 {
+                            dx = v;
+                        } else {
+                            dy = v;
+                        }
+                    }
+                }
+                count_input(|c| c.mouse_distance_px += (dx * dx + dy * dy).sqrt());
+            }
+            _ => {}
+        }
+    }
+}
+
+// see linux/input-event-codes.h
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const REL_X: u16 = 0x00;
+const REL_Y: u16 = 0x01;
+const REL_HWHEEL: u16 = 0x06;
+const REL_WHEEL: u16 = 0x08;
+const KEY_ESC: u16 = 1;
+const KEY_MAX_KEYBOARD: u16 = 0xff;
+const BTN_MOUSE: u16 = 0x110;
+const BTN_TASK: u16 = 0x117;
+
+/// struct input_event: struct timeval, u16 type, u16 code, i32 value
+const TIMEVAL_SIZE: usize = 2 * std::mem::size_of::<std::os::raw::c_long>();
+const INPUT_EVENT_SIZE: usize = TIMEVAL_SIZE + 8;
+
+/// type, code and value of the events in a buffer read from an evdev device
+fn parse_input_events(buf: &[u8]) -> impl Iterator<Item = (u16, u16, i32)> + '_ {
+    buf.chunks_exact(INPUT_EVENT_SIZE).map(|event| {
+        let data = &event[TIMEVAL_SIZE..];
+        (
+            u16::from_ne_bytes([data[0], data[1]]),
+            u16::from_ne_bytes([data[2], data[3]]),
+            i32::from_ne_bytes([data[4], data[5], data[6], data[7]]),
+        )
+    })
+}
+
+/// relative movement of one evdev device since the last SYN_REPORT
+#[derive(Default)]
+struct Motion {
+    dx: i32,
+    dy: i32,
+}
+
+fn count_evdev_event(
+    counts: &mut InputCounts,
+    motion: &mut Motion,
+    (kind, code, value): (u16, u16, i32),
+) {
+    match (kind, code) {
+        // value 1 is a press, 2 an auto-repeat
+        (EV_KEY, KEY_ESC..=KEY_MAX_KEYBOARD) if value == 1 => counts.keypresses += 1,
+        (EV_KEY, BTN_MOUSE..=BTN_TASK) if value == 1 => counts.clicks += 1,
+        (EV_REL, REL_X) => motion.dx += value,
+        (EV_REL, REL_Y) => motion.dy += value,
+        (EV_REL, REL_WHEEL | REL_HWHEEL) => counts.scroll += value.unsigned_abs(),
+        (EV_SYN, _) => {
+            let Motion { dx, dy } = std::mem::take(motion);
+            counts.mouse_distance_px += ((dx as f64).powi(2) + (dy as f64).powi(2)).sqrt();
+        }
+        _ => {}
+    }
+}
+
+fn watch_evdev_device(path: &Path, mut device: File) -> anyhow::Result<()> {
+    let mut buf = [0u8; INPUT_EVENT_SIZE * 64];
+    let mut motion = Motion::default();
+    loop {
+        let len = device
+            .read(&mut buf)
+            .with_context(|| format!("reading {path:?}"))?;
+        if len == 0 {
+            return Ok(());
+        }
+        count_input(|counts| {
+            for event in parse_input_events(&buf[..len]) {
+                count_evdev_event(counts, &mut motion, event);
+            }
+        });
+    }
+}
+
+/// devices plugged in later are not picked up
+fn watch_evdev() -> anyhow::Result<()> {
+    let mut watching = 0;
+    for entry in std::fs::read_dir("/dev/input").context("reading /dev/input")? {
+        let path = entry?.path();
+        if !path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with("event"))
+        {
+            continue;
+        }
+        let device = match File::open(&path) {
+            Ok(device) => device,
+            Err(e) => {
+                log::debug!("could not open {:?}: {}", path, e);
+                continue;
+            }
+        };
+        watching += 1;
+        std::thread::spawn(move || {
+            if let Err(e) = watch_evdev_device(&path, device) {
+                log::info!("Stopped counting input of {:?}: {:?}", path, e);
+            }
+        });
+    }
+    if watching == 0 {
+        anyhow::bail!("No readable input devices in /dev/input. Add your user to the input group to count input activity");
+    }
+    start_counting_input();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn event(kind: u16, code: u16, value: i32) -> Vec<u8> {
+        let mut e = vec![0u8; TIMEVAL_SIZE];
+        e.extend(kind.to_ne_bytes());
+        e.extend(code.to_ne_bytes());
+        e.extend(value.to_ne_bytes());
+        e
+    }
+
+    #[test]
+    fn test_count_evdev_events() {
+        let buf: Vec<u8> = [
+            // a, a (repeat), release
+            event(EV_KEY, 30, 1),
+            event(EV_KEY, 30, 2),
+            event(EV_KEY, 30, 0),
+            event(EV_SYN, 0, 0),
+            event(EV_KEY, BTN_MOUSE, 1),
+            event(EV_KEY, BTN_MOUSE, 0),
+            event(EV_REL, REL_X, 3),
+            event(EV_REL, REL_Y, -4),
+            event(EV_SYN, 0, 0),
+            event(EV_REL, REL_WHEEL, -2),
+            event(EV_SYN, 0, 0),
+        ]
+        .concat();
+        let mut counts = InputCounts::default();
+        let mut motion = Motion::default();
+        for e in parse_input_events(&buf) {
+            count_evdev_event(&mut counts, &mut motion, e);
+        }
+        assert_eq!(counts.keypresses, 1);
+        assert_eq!(counts.clicks, 1);
+        assert_eq!(counts.scroll, 2);
+        assert_eq!(counts.mouse_distance_px, 5.0);
+    }
+}
@@ -0,0 +1,171 @@
+// keyboard and mouse activity types (must be cross-platform)
+use std::{sync::Mutex, time::Instant};
+
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use typescript_definitions::TypeScriptify;
+
+/// how much input happened since the previous capture. only counts, never which keys were pressed
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, Default, PartialEq)]
+pub struct InputActivity {
+    pub interval_ms: u32,
+    /// key presses without auto-repeat
+    pub keypresses: u32,
+    pub clicks: u32,
+    /// scroll wheel steps
+    pub scroll: u32,
+    pub mouse_distance_px: u32,
+}
+
+/// at least this many keypresses per minute count as typing
+const TYPING_KEYS_PER_MINUTE: u32 = 20;
+/// at least this many clicks or pixels moved per minute count as using the mouse
+const POINTING_CLICKS_PER_MINUTE: u32 = 5;
+const POINTING_PX_PER_MINUTE: u32 = 2000;
+
+/// keep two significant digits, so the rates don't create a new tag value for every event
+fn round_significant(value: u32) -> u32 {
+    let mut magnitude = 1;
+    while value / magnitude >= 100 {
+        magnitude *= 10;
+    }
+    (value + magnitude / 2) / magnitude * magnitude
+}
+
+impl InputActivity {
+    fn per_minute(&self, count: u32) -> u32 {
+        (count as u64 * 60_000 / self.interval_ms.max(1) as u64) as u32
+    }
+
+    pub fn add_tags(&self, tags: &mut Tags) {
+        if self.interval_ms == 0 {
+            return;
+        }
+        let keys = self.per_minute(self.keypresses);
+        let clicks = self.per_minute(self.clicks);
+        let scroll = self.per_minute(self.scroll);
+        let distance = self.per_minute(self.mouse_distance_px);
+        tags.add("input-keys-per-minute", round_significant(keys).to_string());
+        tags.add(
+            "input-clicks-per-minute",
+            round_significant(clicks).to_string(),
+        );
+        tags.add(
+            "input-scroll-per-minute",
+            round_significant(scroll).to_string(),
+        );
+        tags.add(
+            "input-mouse-px-per-minute",
+            round_significant(distance).to_string(),
+        );
+        let activity = if keys >= TYPING_KEYS_PER_MINUTE {
+            "typing"
+        } else if clicks >= POINTING_CLICKS_PER_MINUTE || distance >= POINTING_PX_PER_MINUTE {
+            "pointing"
+        } else {
+            // scrolling, or looking at the screen without much input
+            "reading"
+        };
+        tags.add("input-activity", activity);
+    }
+}
+
+/// counts updated by the input watchers
+#[derive(Default)]
+pub struct InputCounts {
+    pub keypresses: u32,
+    pub clicks: u32,
+    pub scroll: u32,
+    pub mouse_distance_px: f64,
+}
+
+lazy_static! {
+    /// None if no input watcher is running
+    static ref INPUT_COUNTS: Mutex<Option<(Instant, InputCounts)>> = Mutex::new(None);
+}
+
+pub fn start_counting_input() {
+    let mut counts = INPUT_COUNTS.lock().unwrap();
+    if counts.is_none() {
+        *counts = Some((Instant::now(), InputCounts::default()));
+    }
+}
+
+pub fn stop_counting_input() {
+    *INPUT_COUNTS.lock().unwrap() = None;
+}
+
+pub fn count_input(f: impl FnOnce(&mut InputCounts)) {
+    if let Some((_, counts)) = INPUT_COUNTS.lock().unwrap().as_mut() {
+        f(counts);
+    }
+}
+
+/// the activity since the previous call
+pub fn take_input_activity() -> Option<InputActivity> {
+    let mut guard = INPUT_COUNTS.lock().unwrap();
+    let (since, counts) = guard.as_mut()?;
+    let activity = InputActivity {
+        interval_ms: since.elapsed().as_millis() as u32,
+        keypresses: counts.keypresses,
+        clicks: counts.clicks,
+        scroll: counts.scroll,
+        mouse_distance_px: counts.mouse_distance_px.round() as u32,
+    };
+    *since = Instant::now();
+    *counts = InputCounts::default();
+    Some(activity)
+}
+
+/// start counting input in the background
+#[cfg(target_os = "linux")]
+pub fn watch_input() -> anyhow::Result<()> {
+    if INPUT_COUNTS.lock().unwrap().is_some() {
+        // already started by another capture loop
+        return Ok(());
+    }
+    super::input::watch_input()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn watch_input() -> anyhow::Result<()> {
+    anyhow::bail!("Not on Linux!")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_input_activity_tags() {
+        assert_eq!(round_significant(7), 7);
+        assert_eq!(round_significant(99), 99);
+        assert_eq!(round_significant(1234), 1200);
+        assert_eq!(round_significant(45678), 46000);
+
+        let typing = InputActivity {
+            interval_ms: 30_000,
+            keypresses: 80,
+            clicks: 1,
+            scroll: 0,
+            mouse_distance_px: 300,
+        };
+        let mut tags = Tags::new();
+        typing.add_tags(&mut tags);
+        assert!(tags.has_value("input-keys-per-minute", "160"));
+        assert!(tags.has_value("input-clicks-per-minute", "2"));
+        assert!(tags.has_value("input-activity", "typing"));
+
+        let reading = InputActivity {
+            interval_ms: 60_000,
+            keypresses: 2,
+            clicks: 0,
+            scroll: 30,
+            mouse_distance_px: 150,
+        };
+        let mut tags = Tags::new();
+        reading.add_tags(&mut tags);
+        assert!(tags.has_value("input-scroll-per-minute", "30"));
+        assert!(tags.has_value("input-activity", "reading"));
+    }
+}
@@ -0,0 +1,221 @@
+// KWin has no D-Bus method to get the active window, but it can run scripts that call back over D-Bus.
+// every capture loads a small script that sends the active window to an object we export on our own connection
+// (same approach as kdotool and aw-watcher-kwin).
+// idle time comes from the freedesktop ScreenSaver interface which KDE implements
+use std::{io::Write, os::unix::fs::OpenOptionsExt, path::PathBuf, time::Duration};
+
+use super::terminal::get_terminal_context;
+use crate::{capture::process::get_process_data, prelude::*, util::OsInfo};
+
+const RECEIVER_PATH: &str = "/net/phiresky/timetrackrs/KWin";
+const RECEIVER_INTERFACE: &str = "net.phiresky.timetrackrs.KWin";
+const SCRIPT_NAME: &str = "timetrackrs-active-window";
+
+#[zbus::proxy(
+    default_service = "org.kde.KWin",
+    interface = "org.kde.kwin.Scripting",
+    default_path = "/Scripting"
+)]
+trait KWinScripting {
+    fn load_script(&self, file_path: &str, plugin_name: &str) -> zbus::Result<i32>;
+    fn unload_script(&self, plugin_name: &str) -> zbus::Result<bool>;
+}
+
+#[zbus::proxy(
+    default_service = "org.freedesktop.ScreenSaver",
+    interface = "org.freedesktop.ScreenSaver",
+    default_path = "/ScreenSaver"
+)]
+trait ScreenSaver {
+    fn get_session_idle_time(&self) -> zbus::Result<u32>;
+}
+
+struct ActiveWindowReceiver {
+    tx: tokio::sync::mpsc::UnboundedSender<String>,
+}
+
+#[zbus::interface(name = "net.phiresky.timetrackrs.KWin")]
+impl ActiveWindowReceiver {
+    fn update(&self, window: String) {
+        // the capturer might have stopped waiting already
+        let _ = self.tx.send(window);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KWinWindow {
+    title: String,
+    resource_class: Option<String>,
+    desktop_file: Option<String>,
+    pid: Option<i32>,
+}
+
+/// KWin 6 calls the active window activeWindow, KWin 5 activeClient
+fn kwin_script(service: &str) -> String {
+    format!(
+        r#"const w = workspace.activeWindow || workspace.activeClient;
+callDBus("{service}", "{RECEIVER_PATH}", "{RECEIVER_INTERFACE}", "Update", w ? JSON.stringify({{
+    title: w.caption,
+    resource_class: w.resourceClass,
+    desktop_file: w.desktopFileName,
+    pid: w.pid,
+}}) : "null");
+"#
+    )
+}
+
+/// the script file KWin loads. KWin runs it in our session, so it is only writable by us and removed on drop
+struct ScriptFile {
+    path: String,
+}
+
+impl ScriptFile {
+    /// in $XDG_RUNTIME_DIR, which only we can write to, unlike a shared /tmp
+    fn create(content: &str) -> anyhow::Result<ScriptFile> {
+        let dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .context("XDG_RUNTIME_DIR is not set")?;
+        let path = dir.join(format!("{SCRIPT_NAME}-{}.js", std::process::id()));
+        // left over from a previous process with the same pid
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).with_context(|| format!("removing {path:?}"))
+            }
+            _ => {}
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)
+            .with_context(|| format!("creating {path:?}"))?;
+        let path = path.to_string_lossy().to_string();
+        // removes the file again if writing fails
+        let script = ScriptFile { path };
+        file.write_all(content.as_bytes())
+            .with_context(|| format!("writing {:?}", script.path))?;
+        Ok(script)
+    }
+}
+
+impl Drop for ScriptFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            log::debug!("could not remove {:?}: {}", self.path, e);
+        }
+    }
+}
+
+pub struct KdeCapturer {
+    state: Option<KdeConnection>,
+    os_info: OsInfo,
+    system: sysinfo::System,
+}
+
+struct KdeConnection {
+    conn: zbus::Connection,
+    rx: tokio::sync::mpsc::UnboundedReceiver<String>,
+    script: ScriptFile,
+}
+
+impl KdeCapturer {
+    pub(crate) fn new() -> KdeCapturer {
+        KdeCapturer {
+            state: None,
+            os_info: util::get_os_info(),
+            system: sysinfo::System::new(),
+        }
+    }
+
+    async fn connect() -> anyhow::Result<KdeConnection> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let conn = zbus::connection::Builder::session()?
+            .serve_at(RECEIVER_PATH, ActiveWindowReceiver { tx })?
+            .build()
+            .await
+            .context("Could not connect to the session bus")?;
+        let service = conn
+            .unique_name()
+            .context("D-Bus connection has no unique name")?
+            .to_string();
+        let script = ScriptFile::create(&kwin_script(&service))?;
+        Ok(KdeConnection { conn, rx, script })
+    }
+
+    async fn get_active_window(state: &mut KdeConnection) -> anyhow::Result<Option<KWinWindow>> {
+        let scripting = KWinScriptingProxy::new(&state.conn).await?;
+        // in case the last run did not finish
+        scripting.unload_script(SCRIPT_NAME).await?;
+        let id = scripting
+            .load_script(&state.script.path, SCRIPT_NAME)
+            .await
+            .context("Could not load KWin script")?;
+        // KWin 6 exports scripts at /Scripting/Script<id>, KWin 5 at /<id>
+        let mut ran = false;
+        for path in [format!("/Scripting/Script{id}"), format!("/{id}")] {
+            let script = zbus::Proxy::new(
+                &state.conn,
+                "org.kde.KWin",
+                path.as_str(),
+                "org.kde.kwin.Script",
+            )
+            .await?;
+            if script.call_method("run", &()).await.is_ok() {
+                ran = true;
+                break;
+            }
+        }
+        if !ran {
+            anyhow::bail!("Could not run KWin script {}", id);
+        }
+        let response = tokio::time::timeout(Duration::from_secs(2), state.rx.recv()).await;
+        scripting.unload_script(SCRIPT_NAME).await?;
+        let window = response
+            .context("KWin script did not respond")?
+            .context("receiver closed")?;
+        serde_json::from_str(&window).context("parsing KWin script response")
+    }
+}
+
+#[async_trait]
+impl Capturer for KdeCapturer {
+    async fn capture(&mut self) -> anyhow::Result<EventData> {
+        if self.state.is_none() {
+            self.state = Some(KdeCapturer::connect().await?);
+        }
+        let state = self.state.as_mut().unwrap();
+        let window = KdeCapturer::get_active_window(state).await?;
+        let ms_since_user_input = ScreenSaverProxy::new(&state.conn)
+            .await?
+            .get_session_idle_time()
+            .await
+            .map_err(|e| log::info!("could not get idle time: {}", e))
+            .unwrap_or(0);
+        let process = window
+            .as_ref()
+            .and_then(|w| w.pid)
+            .and_then(|pid| get_process_data(&mut self.system, pid as usize));
+        let terminal = match &process {
+            Some(process) => get_terminal_context(&mut self.system, process).await,
+            None => None,
+        };
+        Ok(EventData::kde_v1(KdeEventData {
+            os_info: self.os_info.clone(),
+            ms_since_user_input,
+            focused_window: window.map(|w| DesktopWindow {
+                title: w.title,
+                app_id: w.desktop_file,
+                wm_class: w.resource_class,
+                pid: w.pid,
+            }),
+            process,
+            context: CaptureContext {
+                network: linux::network::get_network_info()
+                    .map_err(|e| log::info!("could not get net info: {}", e))
+                    .ok(),
+                terminal,
+                ..Default::default()
+            },
+        }))
+    }
+}
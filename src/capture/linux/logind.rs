@@ -0,0 +1,289 @@
+// watch the session state via systemd-logind
+// https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html
+// the state is kept up to date from signals in the background and merged into the events of the window capturers
+use futures::StreamExt;
+use zbus::zvariant::OwnedObjectPath;
+
+use super::logind_types::*;
+use crate::prelude::*;
+
+#[zbus::proxy(
+    default_service = "org.freedesktop.login1",
+    interface = "org.freedesktop.login1.Manager",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Manager {
+    fn get_session(&self, session_id: &str) -> zbus::Result<OwnedObjectPath>;
+    #[zbus(name = "GetSessionByPID")]
+    fn get_session_by_pid(&self, pid: u32) -> zbus::Result<OwnedObjectPath>;
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    default_service = "org.freedesktop.login1",
+    interface = "org.freedesktop.login1.Session"
+)]
+trait Session {
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn locked_hint(&self) -> zbus::Result<bool>;
+    #[zbus(signal)]
+    fn lock(&self) -> zbus::Result<()>;
+    #[zbus(signal)]
+    fn unlock(&self) -> zbus::Result<()>;
+}
+
+fn locked_state(locked_hint: bool) -> SessionState {
+    if locked_hint {
+        SessionState::Locked
+    } else {
+        SessionState::Active
+    }
+}
+
+pub struct LogindCapturer {
+    options: LogindCaptureArgs,
+    os_info: util::OsInfo,
+    /// session id and the task listening for logind signals
+    watcher: Option<(String, tokio::task::JoinHandle<()>)>,
+}
+
+impl LogindCapturer {
+    pub fn new(options: LogindCaptureArgs) -> LogindCapturer {
+        LogindCapturer {
+            options,
+            os_info: util::get_os_info(),
+            watcher: None,
+        }
+    }
+
+    async fn start_watcher(&self) -> anyhow::Result<(String, tokio::task::JoinHandle<()>)> {
+        let conn = match &self.options.bus_address {
+            Some(address) => {
+                zbus::connection::Builder::address(address.as_str())?
+                    .build()
+                    .await
+            }
+            None => zbus::Connection::system().await,
+        }
+        .context("Could not connect to D-Bus")?;
+        let manager = ManagerProxy::new(&conn).await?;
+        let session_id = self
+            .options
+            .session_id
+            .clone()
+            .or_else(|| std::env::var("XDG_SESSION_ID").ok());
+        let path = match &session_id {
+            Some(id) => manager
+                .get_session(id)
+                .await
+                .with_context(|| format!("Could not get logind session {id}"))?,
+            None => manager
+                .get_session_by_pid(std::process::id())
+                .await
+                .context("Could not get the logind session of this process")?,
+        };
+        let session = SessionProxy::builder(&conn).path(path)?.build().await?;
+        let id = session.id().await.context("getting session id")?;
+
+        // subscribe before reading the current state so no change gets lost
+        let mut lock = session.receive_lock().await?;
+        let mut unlock = session.receive_unlock().await?;
+        let mut locked_hint = session.receive_locked_hint_changed().await;
+        let mut sleep = manager.receive_prepare_for_sleep().await?;
+        set_session_state(Some(locked_state(session.locked_hint().await?)));
+
+        let watcher = tokio::spawn(async move {
+            let res: anyhow::Result<()> = async {
+                loop {
+                    let state = tokio::select! {
+                        Some(_) = lock.next() => SessionState::Locked,
+                        Some(_) = unlock.next() => SessionState::Active,
+                        Some(change) = locked_hint.next() => locked_state(change.get().await?),
+                        Some(signal) = sleep.next() => {
+                            if *signal.args()?.start() {
+                                SessionState::Sleeping
+                            } else {
+                                locked_state(session.locked_hint().await?)
+                            }
+                        }
+                        else => anyhow::bail!("logind signal streams ended"),
+                    };
+                    set_session_state(Some(state));
+                }
+            }
+            .await;
+            if let Err(e) = res {
+                log::warn!("Stopped watching logind session: {:?}", e);
+            }
+            set_session_state(None);
+        });
+        Ok((id, watcher))
+    }
+}
+
+impl Drop for LogindCapturer {
+    fn drop(&mut self) {
+        if let Some((_, watcher)) = self.watcher.take() {
+            watcher.abort();
+            set_session_state(None);
+        }
+    }
+}
+
+#[async_trait]
+impl Capturer for LogindCapturer {
+    async fn capture(&mut self) -> anyhow::Result<EventData> {
+        let running = matches!(&self.watcher, Some((_, watcher)) if !watcher.is_finished());
+        if !running {
+            self.watcher = Some(self.start_watcher().await?);
+        }
+        let (session_id, _) = self.watcher.as_ref().unwrap();
+        Ok(EventData::logind_v1(LogindEventData {
+            os_info: self.os_info.clone(),
+            session_id: session_id.clone(),
+            state: session_state().context("logind watcher stopped")?,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::capture::context::merge_capture_context;
+    use zbus::object_server::SignalEmitter;
+
+    const SESSION_PATH: &str = "/org/freedesktop/login1/session/_31";
+
+    struct FakeManager;
+
+    #[zbus::interface(name = "org.freedesktop.login1.Manager")]
+    impl FakeManager {
+        fn get_session(&self, session_id: &str) -> zbus::fdo::Result<OwnedObjectPath> {
+            if session_id != "1" {
+                return Err(zbus::fdo::Error::Failed(format!(
+                    "No session '{session_id}' known"
+                )));
+            }
+            Ok(OwnedObjectPath::try_from(SESSION_PATH).unwrap())
+        }
+        #[zbus(signal)]
+        async fn prepare_for_sleep(emitter: &SignalEmitter<'_>, start: bool) -> zbus::Result<()>;
+    }
+
+    struct FakeSession;
+
+    #[zbus::interface(name = "org.freedesktop.login1.Session")]
+    impl FakeSession {
+        #[zbus(property)]
+        fn id(&self) -> String {
+            "1".to_string()
+        }
+        #[zbus(property)]
+        fn locked_hint(&self) -> bool {
+            false
+        }
+        #[zbus(signal)]
+        async fn lock(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+        #[zbus(signal)]
+        async fn unlock(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+    }
+
+    async fn wait_for_state(state: SessionState) -> anyhow::Result<()> {
+        for _ in 0..100 {
+            if session_state() == Some(state) {
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        anyhow::bail!("session state is {:?}, not {:?}", session_state(), state)
+    }
+
+    /// runs a private bus with a fake logind
+    #[tokio::test]
+    async fn test_logind_capture() -> anyhow::Result<()> {
+        let mut daemon = match tokio::process::Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(daemon) => daemon,
+            Err(e) => {
+                log::warn!("skipping logind test, could not start dbus-daemon: {}", e);
+                return Ok(());
+            }
+        };
+        let mut address = String::new();
+        {
+            use tokio::io::AsyncBufReadExt;
+            let stdout = daemon.stdout.take().unwrap();
+            tokio::io::BufReader::new(stdout)
+                .read_line(&mut address)
+                .await?;
+        }
+        let address = address.trim().to_string();
+
+        let service = zbus::connection::Builder::address(address.as_str())?
+            .name("org.freedesktop.login1")?
+            .serve_at("/org/freedesktop/login1", FakeManager)?
+            .serve_at(SESSION_PATH, FakeSession)?
+            .build()
+            .await?;
+        let manager = service
+            .object_server()
+            .interface::<_, FakeManager>("/org/freedesktop/login1")
+            .await?;
+        let session = service
+            .object_server()
+            .interface::<_, FakeSession>(SESSION_PATH)
+            .await?;
+
+        let mut capturer = LogindCapturer::new(LogindCaptureArgs {
+            bus_address: Some(address),
+            session_id: Some("1".to_string()),
+        });
+        let EventData::logind_v1(data) = capturer.capture().await? else {
+            anyhow::bail!("wrong type");
+        };
+        assert_eq!(data.session_id, "1");
+        assert_eq!(data.state, SessionState::Active);
+
+        FakeSession::lock(session.signal_emitter()).await?;
+        wait_for_state(SessionState::Locked).await?;
+        let tags = capturer.capture().await?.extract_info().unwrap();
+        assert!(tags.has_value("session-state", "locked"));
+
+        // window events captured while locked are idle even with recent input
+        let mut window_event = EventData::wlr_toplevel_v1(WlrToplevelEventData {
+            os_info: util::get_os_info(),
+            ms_since_user_input: 0,
+            toplevels: vec![WlrToplevel {
+                title: "Document".to_string(),
+                app_id: "org.gnome.Evince".to_string(),
+                activated: true,
+                ..Default::default()
+            }],
+            context: Default::default(),
+        });
+        merge_capture_context(&mut window_event, false).await;
+        assert!(window_event.extract_info().is_none());
+
+        FakeSession::unlock(session.signal_emitter()).await?;
+        wait_for_state(SessionState::Active).await?;
+        merge_capture_context(&mut window_event, false).await;
+        let tags = window_event.extract_info().unwrap();
+        assert!(tags.has_value("session-state", "active"));
+
+        FakeManager::prepare_for_sleep(manager.signal_emitter(), true).await?;
+        wait_for_state(SessionState::Sleeping).await?;
+        FakeManager::prepare_for_sleep(manager.signal_emitter(), false).await?;
+        wait_for_state(SessionState::Active).await?;
+
+        drop(capturer);
+        assert_eq!(session_state(), None);
+        Ok(())
+    }
+}
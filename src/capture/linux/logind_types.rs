@@ -0,0 +1,91 @@
+// logind session state capture types (must be cross-platform)
+use std::sync::RwLock;
+
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use typescript_definitions::TypeScriptify;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LogindCaptureArgs {
+    /// D-Bus address to connect to instead of the system bus
+    #[serde(default)]
+    pub bus_address: Option<String>,
+    /// logind session id to watch. defaults to $XDG_SESSION_ID, then the session of this process
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+#[cfg(target_os = "linux")]
+impl CapturerCreator for LogindCaptureArgs {
+    fn create_capturer(&self) -> anyhow::Result<Box<dyn Capturer>> {
+        Ok(Box::new(super::logind::LogindCapturer::new(self.clone())))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl CapturerCreator for LogindCaptureArgs {
+    fn create_capturer(&self) -> anyhow::Result<Box<dyn Capturer>> {
+        anyhow::bail!("Not on Linux!")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    Active,
+    Locked,
+    /// the system is about to suspend
+    Sleeping,
+}
+
+impl SessionState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionState::Active => "active",
+            SessionState::Locked => "locked",
+            SessionState::Sleeping => "sleeping",
+        }
+    }
+
+    /// a locked session is idle, no matter how recent the last input was
+    pub fn is_idle(&self) -> bool {
+        *self != SessionState::Active
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct LogindEventData {
+    #[serde(default)]
+    pub os_info: util::OsInfo,
+    pub session_id: String,
+    pub state: SessionState,
+}
+
+impl ExtractInfo for LogindEventData {
+    fn extract_info(&self) -> Option<Tags> {
+        let mut tags = Tags::new();
+        tags.add("session-state", self.state.as_str());
+        Some(tags)
+    }
+
+    /// the window capturers record the actual activity
+    fn is_background(&self) -> bool {
+        true
+    }
+}
+
+lazy_static! {
+    /// updated by the logind watcher, None if no logind capturer is running
+    static ref SESSION_STATE: RwLock<Option<SessionState>> = RwLock::new(None);
+}
+
+pub fn set_session_state(state: Option<SessionState>) {
+    let mut current = SESSION_STATE.write().unwrap();
+    if *current != state {
+        log::info!("session state changed to {:?}", state);
+        *current = state;
+    }
+}
+
+pub fn session_state() -> Option<SessionState> {
+    *SESSION_STATE.read().unwrap()
+}
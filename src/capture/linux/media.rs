@@ -0,0 +1,299 @@
+// which applications use the microphone, camera or a screen cast, to detect calls and meetings.
+// the microphone streams come from pactl (PulseAudio or pipewire-pulse), cameras from open /dev/video* files,
+// cameras and screen casts through the portal from the PipeWire graph in pw-dump.
+// screen capture on X11 doesn't go through any of these, so it is not detected
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use super::media_types::MediaUsage;
+use crate::prelude::*;
+use serde_json::Value as JsonValue;
+
+/// audio and video daemons and mixers that hold devices or record without being a call
+fn is_ignored_app(app: &str) -> bool {
+    matches!(
+        app,
+        "pipewire"
+            | "pipewire-pulse"
+            | "wireplumber"
+            | "pipewire-media-session"
+            | "pulseaudio"
+            | "pavucontrol"
+            | "pwvucontrol"
+            | "easyeffects"
+    ) || app.starts_with("xdg-desktop-portal")
+}
+
+/// the application of a PulseAudio or PipeWire client from its properties
+fn app_name(props: &HashMap<String, String>) -> Option<String> {
+    let app = props
+        .get("application.process.binary")
+        .or_else(|| props.get("application.name"))?;
+    (!is_ignored_app(app)).then(|| app.clone())
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct SourceOutput {
+    source: Option<u32>,
+    corked: bool,
+    props: HashMap<String, String>,
+}
+
+/// output of LC_ALL=C pactl list source-outputs
+fn parse_source_outputs(text: &str) -> Vec<SourceOutput> {
+    let mut outputs: Vec<SourceOutput> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with("Source Output #") {
+            outputs.push(SourceOutput::default());
+            continue;
+        }
+        let Some(output) = outputs.last_mut() else {
+            continue;
+        };
+        let line = line.trim();
+        if let Some(source) = line.strip_prefix("Source: ") {
+            output.source = source.parse().ok();
+        } else if let Some(corked) = line.strip_prefix("Corked: ") {
+            output.corked = corked == "yes";
+        } else if let Some((key, value)) = line.split_once(" = ") {
+            output
+                .props
+                .insert(key.to_string(), value.trim_matches('"').to_string());
+        }
+    }
+    outputs
+}
+
+/// indices of the monitor sources in the output of pactl list short sources
+fn parse_monitor_sources(text: &str) -> HashSet<u32> {
+    text.lines()
+        .filter_map(|line| {
+            let mut columns = line.split('\t');
+            let index = columns.next()?.parse().ok()?;
+            columns.next()?.ends_with(".monitor").then_some(index)
+        })
+        .collect()
+}
+
+fn microphone_users(source_outputs: &str, sources: &str) -> Vec<String> {
+    let monitors = parse_monitor_sources(sources);
+    parse_source_outputs(source_outputs)
+        .into_iter()
+        .filter(|o| !o.corked && !o.source.is_some_and(|s| monitors.contains(&s)))
+        // level meters of volume controls
+        .filter(|o| o.props.get("media.name").map(|n| n.as_str()) != Some("Peak detect"))
+        .filter_map(|o| app_name(&o.props))
+        .collect()
+}
+
+/// (camera users, screen cast users) from the output of pw-dump
+fn pipewire_video_users(dump: &JsonValue) -> (Vec<String>, Vec<String>) {
+    let objects = dump.as_array().map(|a| &a[..]).unwrap_or_default();
+    let nodes: HashMap<u64, &JsonValue> = objects
+        .iter()
+        .filter(|o| o.get("type").and_then(|t| t.as_str()) == Some("PipeWire:Interface:Node"))
+        .filter_map(|o| Some((o.get("id")?.as_u64()?, o.get("info")?)))
+        .collect();
+    let props = |info: &JsonValue| -> HashMap<String, String> {
+        info.get("props")
+            .and_then(|p| p.as_object())
+            .into_iter()
+            .flatten()
+            .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+            .collect()
+    };
+    let (mut camera, mut screen_share) = (Vec::new(), Vec::new());
+    for link in objects
+        .iter()
+        .filter(|o| o.get("type").and_then(|t| t.as_str()) == Some("PipeWire:Interface:Link"))
+        .filter_map(|o| o.get("info"))
+    {
+        let node = |key: &str| nodes.get(&link.get(key)?.as_u64()?).copied();
+        let (Some(source), Some(sink)) = (node("output-node-id"), node("input-node-id")) else {
+            continue;
+        };
+        if source.get("state").and_then(|s| s.as_str()) != Some("running") {
+            continue;
+        }
+        let (source, sink) = (props(source), props(sink));
+        let class = source.get("media.class").map(|c| c.as_str());
+        if !matches!(class, Some("Video/Source" | "Stream/Output/Video")) {
+            continue;
+        }
+        let Some(app) = app_name(&sink) else {
+            continue;
+        };
+        // cameras are devices, screen casts are streams of the compositor
+        if source.contains_key("device.api") || source.contains_key("api.v4l2.path") {
+            camera.push(app);
+        } else {
+            screen_share.push(app);
+        }
+    }
+    (camera, screen_share)
+}
+
+/// executable names of the processes that have a /dev/video* device open
+fn video_device_users() -> anyhow::Result<Vec<String>> {
+    let mut users = Vec::new();
+    for entry in std::fs::read_dir("/proc").context("reading /proc")? {
+        let path = entry?.path();
+        // other users' processes and processes that exited in the meantime can't be read
+        let Ok(fds) = std::fs::read_dir(path.join("fd")) else {
+            continue;
+        };
+        let uses_camera = fds
+            .filter_map(|fd| std::fs::read_link(fd.ok()?.path()).ok())
+            .any(|target| target.to_str().is_some_and(|t| t.starts_with("/dev/video")));
+        if !uses_camera {
+            continue;
+        }
+        let exe = std::fs::read_link(path.join("exe")).ok();
+        let name = match exe.as_ref().and_then(|e| e.file_name()) {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => std::fs::read_to_string(path.join("comm"))
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        };
+        if !name.is_empty() && !is_ignored_app(&name) {
+            users.push(name);
+        }
+    }
+    Ok(users)
+}
+
+async fn run(program: &str, args: &[&str]) -> anyhow::Result<String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .env("LC_ALL", "C")
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(Duration::from_secs(1), output)
+        .await
+        .with_context(|| format!("{program} timed out"))?
+        .with_context(|| format!("Could not run {program}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {:?} failed: {}",
+            program,
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+async fn get_microphone_users() -> anyhow::Result<Vec<String>> {
+    let source_outputs = run("pactl", &["list", "source-outputs"]).await?;
+    let sources = run("pactl", &["list", "short", "sources"]).await?;
+    Ok(microphone_users(&source_outputs, &sources))
+}
+
+async fn get_pipewire_video_users() -> anyhow::Result<(Vec<String>, Vec<String>)> {
+    let dump = run("pw-dump", &[]).await?;
+    let dump: JsonValue = serde_json::from_str(&dump).context("parsing pw-dump output")?;
+    Ok(pipewire_video_users(&dump))
+}
+
+fn sorted(mut apps: Vec<String>) -> Vec<String> {
+    apps.sort();
+    apps.dedup();
+    apps
+}
+
+/// missing tools are ignored, so this works with PulseAudio, PipeWire or neither
+pub async fn get_media_usage() -> MediaUsage {
+    let microphone = get_microphone_users()
+        .await
+        .map_err(|e| log::debug!("could not get microphone users: {:?}", e))
+        .unwrap_or_default();
+    let (mut camera, screen_share) = get_pipewire_video_users()
+        .await
+        .map_err(|e| log::debug!("could not get pipewire video users: {:?}", e))
+        .unwrap_or_default();
+    camera.extend(
+        video_device_users()
+            .map_err(|e| log::debug!("could not get camera users: {:?}", e))
+            .unwrap_or_default(),
+    );
+    MediaUsage {
+        microphone: sorted(microphone),
+        camera: sorted(camera),
+        screen_share: sorted(screen_share),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_microphone_users() {
+        let source_outputs = r#"Source Output #51
+	Driver: PipeWire
+	Owner Module: n/a
+	Client: 49
+	Source: 56
+	Corked: no
+	Mute: no
+	Properties:
+		media.name = "AudioCallbackDriver"
+		application.name = "Firefox"
+		application.process.binary = "firefox"
+Source Output #60
+	Driver: PipeWire
+	Client: 58
+	Source: 55
+	Corked: no
+	Properties:
+		media.name = "Peak detect"
+		application.name = "PulseAudio Volume Control"
+		application.process.binary = "pavucontrol"
+Source Output #71
+	Client: 70
+	Source: 55
+	Corked: no
+	Properties:
+		application.name = "OBS"
+		application.process.binary = "obs"
+Source Output #80
+	Client: 79
+	Source: 56
+	Corked: yes
+	Properties:
+		application.process.binary = "Discord"
+"#;
+        let sources = "55\talsa_output.pci-0000_00_1f.3.analog-stereo.monitor\tPipeWire\ts32le 2ch 48000Hz\tRUNNING\n\
+                       56\talsa_input.pci-0000_00_1f.3.analog-stereo\tPipeWire\ts32le 2ch 48000Hz\tRUNNING\n";
+        assert_eq!(
+            microphone_users(source_outputs, sources),
+            vec!["firefox".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_pipewire_video_users() {
+        let dump = serde_json::json!([
+            {"id": 40, "type": "PipeWire:Interface:Node", "info": {"state": "running", "props": {
+                "media.class": "Video/Source", "device.api": "v4l2", "node.name": "v4l2_input.pci-0000_00_14.0-usb-0_1.5_1.0"}}},
+            {"id": 41, "type": "PipeWire:Interface:Node", "info": {"state": "running", "props": {
+                "media.class": "Stream/Input/Video", "application.name": "Chromium", "application.process.binary": "chromium"}}},
+            {"id": 50, "type": "PipeWire:Interface:Node", "info": {"state": "running", "props": {
+                "media.class": "Video/Source", "node.name": "xdpw_stream"}}},
+            {"id": 51, "type": "PipeWire:Interface:Node", "info": {"state": "running", "props": {
+                "media.class": "Stream/Input/Video", "application.name": "Firefox", "application.process.binary": "firefox"}}},
+            {"id": 60, "type": "PipeWire:Interface:Node", "info": {"state": "suspended", "props": {
+                "media.class": "Video/Source", "device.api": "libcamera"}}},
+            {"id": 61, "type": "PipeWire:Interface:Link", "info": {"output-node-id": 40, "input-node-id": 41}},
+            {"id": 62, "type": "PipeWire:Interface:Link", "info": {"output-node-id": 50, "input-node-id": 51}},
+            {"id": 63, "type": "PipeWire:Interface:Link", "info": {"output-node-id": 60, "input-node-id": 51}},
+        ]);
+        let (camera, screen_share) = pipewire_video_users(&dump);
+        assert_eq!(camera, vec!["chromium".to_string()]);
+        assert_eq!(screen_share, vec!["firefox".to_string()]);
+    }
+}
@@ -0,0 +1,54 @@
+// microphone, camera and screen sharing types (must be cross-platform)
+use crate::prelude::*;
+
+/// applications currently using the microphone, camera or a screen cast
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, Default, PartialEq)]
+pub struct MediaUsage {
+    /// applications recording from a microphone (not from a monitor of an output)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub microphone: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub camera: Vec<String>,
+    /// applications receiving a screen cast
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub screen_share: Vec<String>,
+}
+
+impl MediaUsage {
+    pub fn is_empty(&self) -> bool {
+        self.microphone.is_empty() && self.camera.is_empty() && self.screen_share.is_empty()
+    }
+
+    /// a call doesn't need keyboard or mouse input, so it is never idle
+    pub fn in_call(&self) -> bool {
+        !self.is_empty()
+    }
+
+    pub fn add_tags(&self, tags: &mut Tags) {
+        if !self.in_call() {
+            return;
+        }
+        tags.add("in-call", "true");
+        for (key, apps) in [
+            ("microphone-in-use-by", &self.microphone),
+            ("camera-in-use-by", &self.camera),
+            ("screen-shared-with", &self.screen_share),
+        ] {
+            for app in apps {
+                tags.add(key, app);
+                tags.add("call-software", app);
+            }
+        }
+    }
+}
+
+/// probe the microphone, camera and screen casts
+#[cfg(target_os = "linux")]
+pub async fn get_media_usage() -> Option<MediaUsage> {
+    Some(super::media::get_media_usage().await)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub async fn get_media_usage() -> Option<MediaUsage> {
+    None
+}
@@ -1,10 +1,32 @@
 #[cfg(target_os = "linux")]
+pub mod container;
+#[cfg(target_os = "linux")]
+pub mod gnome;
+#[cfg(target_os = "linux")]
+pub mod input;
+#[cfg(target_os = "linux")]
+pub mod kde;
+#[cfg(target_os = "linux")]
+pub mod logind;
+#[cfg(target_os = "linux")]
+pub mod media;
+#[cfg(target_os = "linux")]
+pub mod mpris;
+#[cfg(target_os = "linux")]
 pub mod network;
 #[cfg(target_os = "linux")]
+pub mod terminal;
+#[cfg(target_os = "linux")]
 pub mod wayland;
 #[cfg(target_os = "linux")]
 pub mod x11;
 
 // these types are cross platform
+pub mod container_types;
+pub mod input_types;
+pub mod logind_types;
+pub mod media_types;
+pub mod mpris_types;
+pub mod terminal_types;
 pub mod wayland_types;
 pub mod x11_types;
@@ -0,0 +1,284 @@
+// capture what media players are playing via MPRIS
+// https://specifications.freedesktop.org/mpris-spec/latest/
+use std::collections::HashMap;
+
+use super::mpris_types::*;
+use crate::prelude::*;
+
+use zbus::zvariant::{OwnedValue, Value};
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+#[zbus::proxy(
+    interface = "org.mpris.MediaPlayer2",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait MediaPlayer2 {
+    #[zbus(property)]
+    fn identity(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn desktop_entry(&self) -> zbus::Result<String>;
+}
+
+#[zbus::proxy(
+    interface = "org.mpris.MediaPlayer2.Player",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait Player {
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+    #[zbus(property)]
+    fn position(&self) -> zbus::Result<i64>;
+}
+
+pub struct MprisCapturer {
+    options: MprisCaptureArgs,
+    /// connected lazily, so the capturer survives the session bus not being there yet
+    conn: Option<zbus::Connection>,
+    os_info: util::OsInfo,
+}
+
+fn string_value(v: &Value) -> Option<String> {
+    match v {
+        Value::Str(s) => Some(s.to_string()),
+        Value::ObjectPath(p) => Some(p.to_string()),
+        Value::Value(v) => string_value(v),
+        _ => None,
+    }
+}
+
+fn strings_value(v: &Value) -> Vec<String> {
+    match v {
+        Value::Array(a) => a.iter().filter_map(string_value).collect(),
+        Value::Value(v) => strings_value(v),
+        v => string_value(v).into_iter().collect(),
+    }
+}
+
+fn int_value(v: &Value) -> Option<i64> {
+    match v {
+        Value::I64(i) => Some(*i),
+        Value::U64(i) => i64::try_from(*i).ok(),
+        Value::I32(i) => Some(*i as i64),
+        Value::U32(i) => Some(*i as i64),
+        Value::Value(v) => int_value(v),
+        _ => None,
+    }
+}
+
+fn parse_metadata(metadata: &HashMap<String, OwnedValue>) -> MprisMetadata {
+    let get = |key: &str| metadata.get(key).map(|v| &**v);
+    MprisMetadata {
+        track_id: get("mpris:trackid").and_then(string_value),
+        title: get("xesam:title").and_then(string_value),
+        artists: get("xesam:artist").map(strings_value).unwrap_or_default(),
+        album: get("xesam:album").and_then(string_value),
+        url: get("xesam:url").and_then(string_value),
+        length_us: get("mpris:length").and_then(int_value),
+    }
+}
+
+impl MprisCapturer {
+    pub fn new(options: MprisCaptureArgs) -> MprisCapturer {
+        MprisCapturer {
+            options,
+            conn: None,
+            os_info: util::get_os_info(),
+        }
+    }
+
+    async fn connection(&mut self) -> anyhow::Result<&zbus::Connection> {
+        if self.conn.is_none() {
+            let conn = match &self.options.bus_address {
+                Some(address) => {
+                    zbus::connection::Builder::address(address.as_str())?
+                        .build()
+                        .await
+                }
+                None => zbus::Connection::session().await,
+            }
+            .context("Could not connect to D-Bus")?;
+            self.conn = Some(conn);
+        }
+        Ok(self.conn.as_ref().unwrap())
+    }
+
+    async fn get_player(conn: &zbus::Connection, bus_name: &str) -> anyhow::Result<MprisPlayer> {
+        let root = MediaPlayer2Proxy::builder(conn)
+            .destination(bus_name.to_string())?
+            .build()
+            .await?;
+        let player = PlayerProxy::builder(conn)
+            .destination(bus_name.to_string())?
+            .build()
+            .await?;
+        Ok(MprisPlayer {
+            bus_name: bus_name.to_string(),
+            identity: root.identity().await.ok(),
+            desktop_entry: root.desktop_entry().await.ok(),
+            playback_status: player
+                .playback_status()
+                .await
+                .context("getting PlaybackStatus")?,
+            // not all players support the position
+            position_us: player.position().await.ok(),
+            metadata: player
+                .metadata()
+                .await
+                .map(|m| parse_metadata(&m))
+                .unwrap_or_default(),
+        })
+    }
+
+    async fn get_players(&mut self) -> anyhow::Result<Vec<MprisPlayer>> {
+        let conn = self.connection().await?.clone();
+        let names = zbus::fdo::DBusProxy::new(&conn)
+            .await?
+            .list_names()
+            .await
+            .context("listing D-Bus names")?;
+        let mut bus_names: Vec<String> = names
+            .into_iter()
+            .map(|n| n.to_string())
+            .filter(|n| n.starts_with(MPRIS_PREFIX))
+            .collect();
+        bus_names.sort();
+        let mut players = vec![];
+        for bus_name in bus_names {
+            match MprisCapturer::get_player(&conn, &bus_name).await {
+                Ok(player) => players.push(player),
+                // the player might have exited in between
+                Err(e) => log::debug!("Could not get MPRIS player {}: {:?}", bus_name, e),
+            }
+        }
+        Ok(players)
+    }
+}
+
+#[async_trait]
+impl Capturer for MprisCapturer {
+    async fn capture(&mut self) -> anyhow::Result<EventData> {
+        let data = self.get_players().await.map(|players| MprisEventData {
+            os_info: self.os_info.clone(),
+            players,
+        });
+        // window events are not idle while a video plays
+        set_playing_video(data.as_ref().map(|d| d.playing_video()).unwrap_or_default());
+        Ok(EventData::mpris_v1(data?))
+    }
+}
+
+impl Drop for MprisCapturer {
+    fn drop(&mut self) {
+        set_playing_video(vec![]);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct FakeRoot;
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2")]
+    impl FakeRoot {
+        #[zbus(property)]
+        fn identity(&self) -> String {
+            "mpv Media Player".to_string()
+        }
+        #[zbus(property)]
+        fn desktop_entry(&self) -> String {
+            "mpv".to_string()
+        }
+    }
+
+    struct FakePlayer;
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl FakePlayer {
+        #[zbus(property)]
+        fn playback_status(&self) -> String {
+            "Playing".to_string()
+        }
+        #[zbus(property)]
+        fn position(&self) -> i64 {
+            42_000_000
+        }
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            let mut m = HashMap::new();
+            let mut put = |k: &str, v: Value| {
+                m.insert(k.to_string(), OwnedValue::try_from(v).unwrap());
+            };
+            put("xesam:title", Value::from("Big Buck Bunny"));
+            put("xesam:artist", Value::from(vec!["Blender Foundation"]));
+            put(
+                "xesam:url",
+                Value::from("file:///videos/big_buck_bunny.mkv"),
+            );
+            put("mpris:length", Value::from(596_000_000i64));
+            m
+        }
+    }
+
+    /// runs a private session bus with a fake mpv MPRIS service
+    #[tokio::test]
+    async fn test_mpris_capture() -> anyhow::Result<()> {
+        let mut daemon = match tokio::process::Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(daemon) => daemon,
+            Err(e) => {
+                log::warn!("skipping MPRIS test, could not start dbus-daemon: {}", e);
+                return Ok(());
+            }
+        };
+        let mut address = String::new();
+        {
+            use tokio::io::AsyncBufReadExt;
+            let stdout = daemon.stdout.take().unwrap();
+            tokio::io::BufReader::new(stdout)
+                .read_line(&mut address)
+                .await?;
+        }
+        let address = address.trim().to_string();
+
+        let _service = zbus::connection::Builder::address(address.as_str())?
+            .name("org.mpris.MediaPlayer2.mpv")?
+            .serve_at("/org/mpris/MediaPlayer2", FakeRoot)?
+            .serve_at("/org/mpris/MediaPlayer2", FakePlayer)?
+            .build()
+            .await?;
+
+        let mut capturer = MprisCapturer::new(MprisCaptureArgs {
+            bus_address: Some(address),
+        });
+        let data = match capturer.capture().await? {
+            EventData::mpris_v1(data) => data,
+            other => anyhow::bail!("unexpected event {:?}", other),
+        };
+        assert_eq!(data.players.len(), 1);
+        let player = &data.players[0];
+        assert_eq!(player.identity.as_deref(), Some("mpv Media Player"));
+        assert_eq!(player.position_us, Some(42_000_000));
+        assert_eq!(player.metadata.length_us, Some(596_000_000));
+
+        assert!(data.is_watching_video());
+        assert!(!data.is_background());
+        let tags = data.extract_info().unwrap();
+        assert!(tags.has_value("media-track", "Big Buck Bunny"));
+        assert!(tags.has_value("media-artist", "Blender Foundation"));
+        assert!(tags.has_value("media-type", "video"));
+        assert!(tags.has_value("media-watching-video", "true"));
+        assert_eq!(playing_video(), vec!["mpv Media Player".to_string()]);
+
+        drop(capturer);
+        assert!(playing_video().is_empty());
+        Ok(())
+    }
+}
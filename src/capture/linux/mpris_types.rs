@@ -0,0 +1,164 @@
+// mpris capture types (must be cross-platform)
+use std::sync::RwLock;
+
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use typescript_definitions::TypeScriptify;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MprisCaptureArgs {
+    /// D-Bus address to connect to instead of the session bus
+    #[serde(default)]
+    pub bus_address: Option<String>,
+}
+
+#[cfg(target_os = "linux")]
+impl CapturerCreator for MprisCaptureArgs {
+    fn create_capturer(&self) -> anyhow::Result<Box<dyn Capturer>> {
+        Ok(Box::new(super::mpris::MprisCapturer::new(self.clone())))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl CapturerCreator for MprisCaptureArgs {
+    fn create_capturer(&self) -> anyhow::Result<Box<dyn Capturer>> {
+        anyhow::bail!("Not on Linux!")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct MprisEventData {
+    #[serde(default)]
+    pub os_info: util::OsInfo,
+    pub players: Vec<MprisPlayer>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct MprisPlayer {
+    /// e.g. org.mpris.MediaPlayer2.mpv
+    pub bus_name: String,
+    /// human readable name of the player, e.g. "mpv Media Player"
+    pub identity: Option<String>,
+    pub desktop_entry: Option<String>,
+    /// Playing, Paused or Stopped
+    pub playback_status: String,
+    pub position_us: Option<i64>,
+    pub metadata: MprisMetadata,
+}
+
+/// https://www.freedesktop.org/wiki/Specifications/mpris-spec/metadata/
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, Default)]
+pub struct MprisMetadata {
+    pub track_id: Option<String>,
+    pub title: Option<String>,
+    pub artists: Vec<String>,
+    pub album: Option<String>,
+    pub url: Option<String>,
+    pub length_us: Option<i64>,
+}
+
+lazy_static! {
+    static ref VIDEO_PLAYERS: regex::Regex =
+        regex::Regex::new(r"(?i)^(mpv|vlc|celluloid|totem|haruna|smplayer|kodi|dragon|io\.github\.celluloid_player\.celluloid|org\.gnome\.totem|org\.kde\.haruna)")
+            .unwrap();
+    static ref VIDEO_URL: regex::Regex = regex::Regex::new(
+        r"(?i)(\.(mkv|mp4|webm|avi|mov|m4v|wmv|flv|ts|m2ts|mpg|mpeg|ogv)$|^https?://([^/]*\.)?(youtube\.com|youtu\.be|netflix\.com|twitch\.tv|vimeo\.com|primevideo\.com|disneyplus\.com)/)"
+    )
+    .unwrap();
+    /// updated by the MPRIS capturer, empty if no MPRIS capturer is running
+    static ref PLAYING_VIDEO: RwLock<Vec<String>> = RwLock::new(vec![]);
+}
+
+pub fn set_playing_video(players: Vec<String>) {
+    *PLAYING_VIDEO.write().unwrap() = players;
+}
+
+/// the players that played a video at the last capture of the MPRIS capturer
+pub fn playing_video() -> Vec<String> {
+    PLAYING_VIDEO.read().unwrap().clone()
+}
+
+impl MprisPlayer {
+    pub fn name(&self) -> &str {
+        self.identity.as_deref().unwrap_or(&self.bus_name)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback_status == "Playing"
+    }
+
+    /// MPRIS has no media type, so guess it from the url and the player
+    pub fn is_video(&self) -> bool {
+        if let Some(url) = &self.metadata.url {
+            if VIDEO_URL.is_match(url) {
+                return true;
+            }
+            if url.starts_with("file://") {
+                // a local file that is not a video file
+                return false;
+            }
+        }
+        let player_name = self.bus_name.trim_start_matches("org.mpris.MediaPlayer2.");
+        [Some(player_name), self.desktop_entry.as_deref()]
+            .into_iter()
+            .flatten()
+            .any(|name| VIDEO_PLAYERS.is_match(name))
+    }
+}
+
+impl MprisEventData {
+    /// names of the players that are playing a video
+    pub fn playing_video(&self) -> Vec<String> {
+        self.players
+            .iter()
+            .filter(|p| p.is_playing() && p.is_video())
+            .map(|p| p.name().to_string())
+            .collect()
+    }
+
+    pub fn is_watching_video(&self) -> bool {
+        self.players.iter().any(|p| p.is_playing() && p.is_video())
+    }
+}
+
+impl ExtractInfo for MprisEventData {
+    fn extract_info(&self) -> Option<Tags> {
+        let playing: Vec<_> = self.players.iter().filter(|p| p.is_playing()).collect();
+        if playing.is_empty() {
+            return None;
+        }
+        let mut tags = Tags::new();
+        tags.add("device-hostname", &self.os_info.hostname);
+        tags.add("media-source", "MPRIS");
+        for player in playing {
+            tags.add("media-player", player.name());
+            let m = &player.metadata;
+            for artist in &m.artists {
+                tags.add("media-artist", artist);
+            }
+            if let Some(title) = &m.title {
+                tags.add("media-track", title);
+            }
+            if let Some(album) = &m.album {
+                tags.add("media-album", album);
+            }
+            if let Some(url) = &m.url {
+                tags.add("media-url", url);
+            }
+            tags.add(
+                "media-type",
+                if player.is_video() { "video" } else { "music" },
+            );
+        }
+        if self.is_watching_video() {
+            // the playing players are also stored in window events, so those are not idle while a movie plays
+            tags.add("media-watching-video", "true");
+        }
+        Some(tags)
+    }
+
+    /// listening to music is a background activity, watching a video is not
+    fn is_background(&self) -> bool {
+        !self.is_watching_video()
+    }
+}
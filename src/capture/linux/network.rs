@@ -1,9 +1,30 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
 use crate::prelude::*;
 use nl80211::{parse_hex, parse_i8, parse_string, parse_u32, Socket};
 
-// currently wifi only
-// todo?:  get mac address of gateway (in ethernet this should be same as bssid of wifi)
+// wifi comes from nl80211. the default route, gateway and interfaces are read from /proc/net and /sys/class/net,
+// which the kernel fills from the same rtnetlink tables. the ipv4 gateway is preferred, the ipv6 one is used on
+// ipv6-only networks. its MAC is not in /proc, so it is asked from `ip -j neigh`
 pub fn get_network_info() -> anyhow::Result<NetworkInfo> {
+    let wifi = get_wifi_info()
+        .map_err(|e| log::debug!("could not get wifi info: {:?}", e))
+        .ok()
+        .flatten();
+    let gateway = get_gateway()
+        .map_err(|e| log::debug!("could not get default gateway: {:?}", e))
+        .ok()
+        .flatten();
+    let interfaces = get_interfaces().context("listing network interfaces")?;
+    Ok(NetworkInfo {
+        wifi,
+        gateway,
+        interfaces,
+    })
+}
+
+fn get_wifi_info() -> anyhow::Result<Option<WifiInterface>> {
     let interfaces = Socket::connect()
         .context("connect to nl80211 socket")?
         .get_interfaces_info()
@@ -26,19 +47,257 @@ pub fn get_network_info() -> anyhow::Result<NetworkInfo> {
                 ..
             }) = interface.get_station_info()
             {
-                return Ok(NetworkInfo {
-                    wifi: Some(WifiInterface {
-                        ssid: parse_string(ssid),
-                        mac: parse_hex(mac),
-                        name: parse_string(name).trim_end_matches('\0').to_string(),
-                        power: parse_u32(power),
-                        average_signal: parse_i8(&average_signal),
-                        bssid: parse_hex(&bssid),
-                        connected_time: parse_u32(&connected_time),
-                    }),
-                });
+                return Ok(Some(WifiInterface {
+                    ssid: parse_string(ssid),
+                    mac: parse_hex(mac),
+                    name: parse_string(name).trim_end_matches('\0').to_string(),
+                    power: parse_u32(power),
+                    average_signal: parse_i8(&average_signal),
+                    bssid: parse_hex(&bssid),
+                    connected_time: parse_u32(&connected_time),
+                }));
             };
         }
     }
-    Ok(NetworkInfo { wifi: None })
+    Ok(None)
+}
+
+const RTF_GATEWAY: u32 = 0x2;
+
+/// the ipv4 default route with the lowest metric from /proc/net/route
+fn parse_default_route(route: &str) -> Option<(String, Ipv4Addr)> {
+    route
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() < 8 {
+                return None;
+            }
+            let hex = |i: usize| u32::from_str_radix(cols[i], 16).ok();
+            let (destination, gateway, flags, metric, mask) = (
+                hex(1)?,
+                hex(2)?,
+                hex(3)?,
+                cols[6].parse::<u32>().ok()?,
+                hex(7)?,
+            );
+            if destination != 0 || mask != 0 || flags & RTF_GATEWAY == 0 {
+                return None;
+            }
+            // the kernel prints the network order address as a host order integer
+            Some((
+                metric,
+                cols[0].to_string(),
+                Ipv4Addr::from(gateway.to_ne_bytes()),
+            ))
+        })
+        .min_by_key(|(metric, _, _)| *metric)
+        .map(|(_, interface, ip)| (interface, ip))
+}
+
+/// the default route with the lowest metric from /proc/net/ipv6_route
+fn parse_default_route_v6(route: &str) -> Option<(String, Ipv6Addr)> {
+    route
+        .lines()
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            let [destination, destination_prefix, _, _, next_hop, metric, _, _, flags, interface] =
+                cols[..]
+            else {
+                return None;
+            };
+            let hex = |s: &str| u32::from_str_radix(s, 16).ok();
+            let next_hop = u128::from_str_radix(next_hop, 16).ok()?;
+            if u128::from_str_radix(destination, 16).ok()? != 0
+                || hex(destination_prefix)? != 0
+                || hex(flags)? & RTF_GATEWAY == 0
+                || next_hop == 0
+            {
+                return None;
+            }
+            Some((
+                hex(metric)?,
+                interface.to_string(),
+                Ipv6Addr::from(next_hop),
+            ))
+        })
+        .min_by_key(|(metric, _, _)| *metric)
+        .map(|(_, interface, ip)| (interface, ip))
+}
+
+/// MAC address from the output of `ip -j neigh show <ip> dev <interface>`
+fn parse_neigh_mac(neigh: &str) -> Option<String> {
+    let entries: Vec<serde_json::Value> = serde_json::from_str(neigh).ok()?;
+    entries
+        .iter()
+        .find_map(|e| e.get("lladdr")?.as_str())
+        .map(|mac| mac.to_string())
+}
+
+/// MAC address of the given IP from the neighbour table in /proc/net/arp
+fn parse_arp_mac(arp: &str, interface: &str, ip: Ipv4Addr) -> Option<String> {
+    arp.lines().skip(1).find_map(|line| {
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        match cols[..] {
+            [entry_ip, _, flags, mac, _, device]
+                if device == interface
+                    && entry_ip.parse::<Ipv4Addr>().ok() == Some(ip)
+                    && flags != "0x0"
+                    && mac != "00:00:00:00:00:00" =>
+            {
+                Some(mac.to_string())
+            }
+            _ => None,
+        }
+    })
+}
+
+fn get_gateway() -> anyhow::Result<Option<NetworkGateway>> {
+    let route = std::fs::read_to_string("/proc/net/route").context("reading /proc/net/route")?;
+    if let Some((interface, ip)) = parse_default_route(&route) {
+        let arp = std::fs::read_to_string("/proc/net/arp").context("reading /proc/net/arp")?;
+        return Ok(Some(NetworkGateway {
+            mac: parse_arp_mac(&arp, &interface, ip),
+            interface,
+            ip: ip.to_string(),
+        }));
+    }
+    // missing if ipv6 is disabled
+    let route = std::fs::read_to_string("/proc/net/ipv6_route").unwrap_or_default();
+    let Some((interface, ip)) = parse_default_route_v6(&route) else {
+        return Ok(None);
+    };
+    let mac = std::process::Command::new("ip")
+        .args(["-j", "neigh", "show", &ip.to_string(), "dev", &interface])
+        .output()
+        .map_err(|e| log::debug!("could not run ip neigh: {:?}", e))
+        .ok()
+        .and_then(|output| parse_neigh_mac(&String::from_utf8_lossy(&output.stdout)));
+    Ok(Some(NetworkGateway {
+        mac,
+        interface,
+        ip: ip.to_string(),
+    }))
+}
+
+fn interface_kind(dir: &Path) -> Option<NetworkInterfaceKind> {
+    let uevent = std::fs::read_to_string(dir.join("uevent")).unwrap_or_default();
+    match uevent.lines().find_map(|l| l.strip_prefix("DEVTYPE=")) {
+        Some("wireguard") => return Some(NetworkInterfaceKind::WireGuard),
+        Some("wlan") => return Some(NetworkInterfaceKind::Wifi),
+        // bridge, vlan, bond, ppp, ...
+        Some(_) => return None,
+        None => {}
+    }
+    if dir.join("tun_flags").exists() {
+        Some(NetworkInterfaceKind::Tunnel)
+    } else if dir.join("wireless").exists() || dir.join("phy80211").exists() {
+        Some(NetworkInterfaceKind::Wifi)
+    } else if dir.join("device").exists() {
+        Some(NetworkInterfaceKind::Wired)
+    } else {
+        // loopback, veth, dummy
+        None
+    }
+}
+
+fn get_interfaces() -> anyhow::Result<Vec<NetworkInterface>> {
+    let mut interfaces = vec![];
+    for entry in std::fs::read_dir("/sys/class/net").context("reading /sys/class/net")? {
+        let dir = entry?.path();
+        let Some(kind) = interface_kind(&dir) else {
+            continue;
+        };
+        let read = |file: &str| {
+            std::fs::read_to_string(dir.join(file))
+                .map(|s| s.trim().to_string())
+                .ok()
+        };
+        let operstate = read("operstate").unwrap_or_default();
+        interfaces.push(NetworkInterface {
+            name: dir.file_name().unwrap().to_string_lossy().to_string(),
+            kind,
+            // tunnels don't report a carrier, their state stays unknown while up
+            up: operstate == "up" || (kind.is_vpn() && operstate == "unknown"),
+            mac: read("address").filter(|a| !a.is_empty()),
+        });
+    }
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(interfaces)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_gateway() {
+        let route =
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+enp3s0\t00000000\t0100000A\t0003\t0\t0\t100\t00000000\t0\t0\t0
+enp3s0\t0000000A\t00000000\t0001\t0\t0\t100\t0000FFFF\t0\t0\t0
+";
+        let (interface, ip) = parse_default_route(route).unwrap();
+        assert_eq!(interface, "enp3s0");
+        assert_eq!(ip, Ipv4Addr::new(10, 0, 0, 1));
+
+        let arp = "IP address       HW type     Flags       HW address            Mask     Device
+10.0.0.7         0x1         0x2         11:22:33:44:55:66     *        enp3s0
+10.0.0.1         0x1         0x2         aa:bb:cc:dd:ee:ff     *        enp3s0
+192.168.1.1      0x1         0x0         00:00:00:00:00:00     *        wlan0
+";
+        assert_eq!(
+            parse_arp_mac(arp, "enp3s0", ip).as_deref(),
+            Some("aa:bb:cc:dd:ee:ff")
+        );
+        assert_eq!(
+            parse_arp_mac(arp, "wlan0", Ipv4Addr::new(192, 168, 1, 1)),
+            None
+        );
+
+        let route_v6 = "00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000400 00000001 00000000 00000003  wlan0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000002 00000064 00000001 00000000 00000003   enp3s0
+fd000000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001   enp3s0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo
+";
+        let (interface_v6, ip_v6) = parse_default_route_v6(route_v6).unwrap();
+        assert_eq!(interface_v6, "enp3s0");
+        assert_eq!(ip_v6, "fe80::2".parse::<Ipv6Addr>().unwrap());
+        assert_eq!(
+            parse_neigh_mac(r#"[{"dst":"fe80::2","dev":"enp3s0","lladdr":"aa:bb:cc:dd:ee:ff","router":null,"state":["REACHABLE"]}]"#).as_deref(),
+            Some("aa:bb:cc:dd:ee:ff")
+        );
+        assert_eq!(parse_neigh_mac("[]"), None);
+
+        let network = NetworkInfo {
+            wifi: None,
+            gateway: Some(NetworkGateway {
+                interface,
+                ip: ip.to_string(),
+                mac: Some("aa:bb:cc:dd:ee:ff".to_string()),
+            }),
+            interfaces: vec![
+                NetworkInterface {
+                    name: "enp3s0".to_string(),
+                    kind: NetworkInterfaceKind::Wired,
+                    up: true,
+                    mac: None,
+                },
+                NetworkInterface {
+                    name: "wg-office".to_string(),
+                    kind: NetworkInterfaceKind::WireGuard,
+                    up: true,
+                    mac: None,
+                },
+            ],
+        };
+        let mut tags = Tags::new();
+        network.add_tags(&mut tags);
+        assert!(tags.has_value("network-location", "gateway:aa:bb:cc:dd:ee:ff"));
+        assert!(tags.has_value("network-location", "wired"));
+        assert!(tags.has_value("network-location", "vpn:wg-office"));
+        assert!(!tags.has("connected-wifi"));
+    }
 }
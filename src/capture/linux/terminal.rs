@@ -0,0 +1,181 @@
+// find what runs in the focused terminal: the terminal emulator's shell, the foreground process group
+// of its tty and, for tmux clients, the foreground process of the active pane
+use std::time::Duration;
+
+use super::{
+    container::{get_container, get_kube_context},
+    terminal_types::TerminalContext,
+};
+use crate::{
+    capture::process::{get_process_data, ProcessData},
+    prelude::*,
+};
+
+lazy_static! {
+    static ref TERMINALS: regex::Regex = regex::Regex::new(
+        r"^(alacritty|kitty|foot|footclient|wezterm-gui|ghostty|gnome-terminal-(server)?|kgx|konsole|xterm|urxvt|st|terminator|tilix|xfce4-terminal|lxterminal|qterminal|terminology)$"
+    )
+    .unwrap();
+}
+
+struct ProcStat {
+    tty_nr: i32,
+    /// foreground process group of the controlling terminal
+    tpgid: i32,
+}
+
+/// see proc(5). the command name in parentheses can contain spaces
+fn parse_stat(stat: &str) -> Option<ProcStat> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    Some(ProcStat {
+        tty_nr: fields.get(4)?.parse().ok()?,
+        tpgid: fields.get(5)?.parse().ok()?,
+    })
+}
+
+fn proc_stat(pid: i32) -> Option<ProcStat> {
+    parse_stat(&std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?)
+}
+
+/// children of all threads of the process
+fn children(pid: i32) -> Vec<i32> {
+    let Ok(tasks) = std::fs::read_dir(format!("/proc/{pid}/task")) else {
+        return vec![];
+    };
+    tasks
+        .filter_map(|t| std::fs::read_to_string(t.ok()?.path().join("children")).ok())
+        .flat_map(|c| {
+            c.split_whitespace()
+                .filter_map(|p| p.parse().ok())
+                .collect::<Vec<i32>>()
+        })
+        .collect()
+}
+
+fn foreground_process(shell: i32) -> i32 {
+    match proc_stat(shell) {
+        Some(ProcStat { tpgid, .. }) if tpgid > 0 => tpgid,
+        _ => shell,
+    }
+}
+
+async fn tmux(args: &[&str]) -> anyhow::Result<String> {
+    let output = tokio::process::Command::new("tmux")
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(Duration::from_secs(1), output)
+        .await
+        .context("tmux timed out")?
+        .context("Could not run tmux")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "tmux {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn parse_client_session(clients: &str, client_pid: i32) -> Option<String> {
+    clients.lines().find_map(|line| {
+        let (pid, session) = line.split_once('\t')?;
+        (pid.parse::<i32>().ok() == Some(client_pid)).then(|| session.to_string())
+    })
+}
+
+fn parse_active_pane(panes: &str) -> Option<i32> {
+    panes
+        .lines()
+        .find_map(|line| match line.split('\t').collect::<Vec<_>>()[..] {
+            ["1", "1", pid] => pid.parse().ok(),
+            _ => None,
+        })
+}
+
+/// session name and shell pid of the active pane of the session the given tmux client shows
+async fn tmux_active_pane(client_pid: i32) -> anyhow::Result<Option<(String, i32)>> {
+    let clients = tmux(&["list-clients", "-F", "#{client_pid}\t#{session_name}"]).await?;
+    let Some(session) = parse_client_session(&clients, client_pid) else {
+        return Ok(None);
+    };
+    let target = format!("={session}");
+    let panes = tmux(&[
+        "list-panes",
+        "-s",
+        "-t",
+        &target,
+        "-F",
+        "#{window_active}\t#{pane_active}\t#{pane_pid}",
+    ])
+    .await?;
+    Ok(parse_active_pane(&panes).map(|pane| (session, pane)))
+}
+
+/// None if the process is not a known terminal emulator
+pub async fn get_terminal_context(
+    system: &mut sysinfo::System,
+    terminal: &ProcessData,
+) -> Option<TerminalContext> {
+    if !TERMINALS.is_match(&terminal.name) {
+        return None;
+    }
+    // terminals with multiple windows per process (kitty, gnome-terminal) have multiple shells,
+    // there is no way to know which one is focused so use the newest one
+    let shell = children(terminal.pid)
+        .into_iter()
+        .filter(|pid| proc_stat(*pid).is_some_and(|s| s.tty_nr != 0))
+        .max()?;
+    let mut current = foreground_process(shell);
+    let mut foreground = vec![];
+    let mut tmux_session = None;
+    while let Some(process) = get_process_data(system, current as usize) {
+        let is_tmux_client = process.name.starts_with("tmux");
+        foreground.push(process);
+        if !is_tmux_client || tmux_session.is_some() {
+            break;
+        }
+        match tmux_active_pane(current).await {
+            Ok(Some((session, pane))) => {
+                tmux_session = Some(session);
+                current = foreground_process(pane);
+            }
+            Ok(None) => break,
+            Err(e) => {
+                log::debug!("could not get active tmux pane: {:?}", e);
+                break;
+            }
+        }
+    }
+    Some(TerminalContext {
+        container: get_container(&foreground).await,
+        kube: get_kube_context(&foreground),
+        foreground,
+        tmux_session,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_tmux() {
+        assert_eq!(
+            parse_client_session("1234\tmain\n5678\twork space\n", 5678).as_deref(),
+            Some("work space")
+        );
+        assert_eq!(parse_client_session("1234\tmain\n", 5678), None);
+        assert_eq!(
+            parse_active_pane("0\t1\t100\n1\t0\t101\n1\t1\t102\n"),
+            Some(102)
+        );
+
+        let stat = parse_stat("4321 (my (weird) cmd) S 1 4321 4321 34817 4400 4194304 0").unwrap();
+        assert_eq!(stat.tty_nr, 34817);
+        assert_eq!(stat.tpgid, 4400);
+    }
+}
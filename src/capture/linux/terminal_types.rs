@@ -0,0 +1,225 @@
+// terminal context types (must be cross-platform)
+use crate::{capture::process::ProcessData, prelude::*};
+use std::path::Path;
+
+lazy_static! {
+    static ref EDITORS: regex::Regex =
+        regex::Regex::new(r"^(n?vim?|vi|nano|micro|hx|helix|kak|emacs|emacsclient)$").unwrap();
+}
+
+/// what runs in the focused terminal window. without this, all time in terminals is just "Alacritty" or "kitty"
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct TerminalContext {
+    /// the foreground process of the terminal and, if that is a tmux client,
+    /// the foreground process of the active tmux pane. outermost first
+    pub foreground: Vec<ProcessData>,
+    pub tmux_session: Option<String>,
+    /// the container the innermost foreground process runs in or attaches to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<ContainerInfo>,
+    /// set if a kubernetes tool like kubectl or k9s is in the foreground
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kube: Option<KubeContext>,
+}
+
+/// ssh options that take a value, see ssh(1)
+const SSH_OPTIONS_WITH_VALUE: &str = "BbcDEeFIiJLlmOopQRSWw";
+
+/// the host of an ssh command line, e.g. ssh -p 2222 user@example.com -> example.com
+pub fn ssh_host(cmd: &[String]) -> Option<String> {
+    let mut args = cmd.iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            continue;
+        }
+        if let Some(flags) = arg.strip_prefix('-') {
+            // -p 22, -p22 and -vp 22 are all valid
+            if let Some(i) = flags.find(|c| SSH_OPTIONS_WITH_VALUE.contains(c)) {
+                if i + 1 == flags.len() {
+                    args.next();
+                }
+            }
+            continue;
+        }
+        let (destination, is_uri) = match arg.strip_prefix("ssh://") {
+            Some(uri) => (uri, true),
+            None => (arg.as_str(), false),
+        };
+        let host = destination
+            .rsplit_once('@')
+            .map(|(_, host)| host)
+            .unwrap_or(destination);
+        let host = if is_uri {
+            host.split(':').next().unwrap_or(host)
+        } else {
+            host
+        };
+        return Some(host.to_string());
+    }
+    None
+}
+
+/// the file an editor was started with
+fn editor_file(process: &ProcessData) -> Option<String> {
+    let file = process
+        .cmd
+        .iter()
+        .skip(1)
+        .find(|a| !a.starts_with('-') && !a.starts_with('+'))?;
+    if file.starts_with('/') {
+        return Some(file.clone());
+    }
+    Some(
+        Path::new(process.cwd.as_ref()?)
+            .join(file)
+            .to_string_lossy()
+            .to_string(),
+    )
+}
+
+fn screen_session(cmd: &[String]) -> Option<String> {
+    cmd.windows(2)
+        .find(|w| matches!(w[0].as_str(), "-S" | "-r" | "-x"))
+        .map(|w| w[1].clone())
+}
+
+impl TerminalContext {
+    pub fn add_tags(&self, tags: &mut Tags) {
+        if let Some(innermost) = self.foreground.last() {
+            tags.add("terminal-foreground-command", &innermost.name);
+            if let Some(cwd) = &innermost.cwd {
+                tags.add("terminal-cwd", cwd);
+            }
+            if EDITORS.is_match(&innermost.name) {
+                if let Some(file) = editor_file(innermost) {
+                    tags.add("software-opened-file", file);
+                }
+            }
+        }
+        if let Some(session) = &self.tmux_session {
+            tags.add("terminal-multiplexer", "tmux");
+            tags.add("tmux-session", session);
+        }
+        if let Some(container) = &self.container {
+            container.add_tags(tags);
+        }
+        if let Some(kube) = &self.kube {
+            kube.add_tags(tags);
+        }
+        for process in &self.foreground {
+            match process.name.as_str() {
+                "ssh" => {
+                    if let Some(host) = ssh_host(&process.cmd) {
+                        tags.add("ssh-host", host);
+                    }
+                }
+                "screen" => {
+                    tags.add("terminal-multiplexer", "screen");
+                    if let Some(session) = screen_session(&process.cmd) {
+                        tags.add("screen-session", session);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn process(name: &str, cmd: &[&str], cwd: &str) -> ProcessData {
+        ProcessData {
+            pid: 1,
+            name: name.to_string(),
+            cmd: cmd.iter().map(|s| s.to_string()).collect(),
+            exe: None,
+            cwd: Some(cwd.to_string()),
+            memory_kB: 0,
+            parent: None,
+            status: "Run".to_string(),
+            start_time: Utc::now(),
+            cpu_usage: None,
+        }
+    }
+
+    fn cmd(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_ssh_host() {
+        assert_eq!(
+            ssh_host(&cmd(&["ssh", "server"])).as_deref(),
+            Some("server")
+        );
+        assert_eq!(
+            ssh_host(&cmd(&[
+                "ssh",
+                "-p",
+                "2222",
+                "-A",
+                "me@example.com",
+                "uptime"
+            ]))
+            .as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            ssh_host(&cmd(&["ssh", "-vp2222", "ssh://me@example.com:2222"])).as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            ssh_host(&cmd(&[
+                "ssh",
+                "-J",
+                "jump",
+                "-o",
+                "Compression=yes",
+                "target"
+            ]))
+            .as_deref(),
+            Some("target")
+        );
+        assert_eq!(ssh_host(&cmd(&["ssh", "-V"])), None);
+    }
+
+    #[test]
+    fn test_terminal_tags() {
+        let context = TerminalContext {
+            foreground: vec![
+                process("tmux", &["tmux", "attach"], "/home/user"),
+                process(
+                    "nvim",
+                    &["nvim", "+12", "src/main.rs"],
+                    "/home/user/project",
+                ),
+            ],
+            tmux_session: Some("work".to_string()),
+            container: None,
+            kube: None,
+        };
+        let mut tags = Tags::new();
+        context.add_tags(&mut tags);
+        assert!(tags.has_value("terminal-foreground-command", "nvim"));
+        assert!(tags.has_value("software-opened-file", "/home/user/project/src/main.rs"));
+        assert!(tags.has_value("tmux-session", "work"));
+        assert!(!tags.has("ssh-host"));
+
+        let context = TerminalContext {
+            foreground: vec![
+                process("screen", &["screen", "-r", "build"], "/home/user"),
+                process("ssh", &["ssh", "build@ci.example.com"], "/home/user"),
+            ],
+            tmux_session: None,
+            container: None,
+            kube: None,
+        };
+        let mut tags = Tags::new();
+        context.add_tags(&mut tags);
+        assert!(tags.has_value("terminal-foreground-command", "ssh"));
+        assert!(tags.has_value("ssh-host", "ci.example.com"));
+        assert!(tags.has_value("screen-session", "build"));
+    }
+}
@@ -1,10 +1,20 @@
+use std::collections::HashMap;
+
+use futures::stream::BoxStream;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
 use tokio::process::Command;
 
-use super::wayland_types::WaylandCaptureArgs;
-use crate::{capture::process::get_process_data, prelude::*, util::OsInfo};
+use super::terminal::get_terminal_context;
+use super::wayland_types::{find_focused_sway_window, WaylandCaptureArgs};
+use crate::{
+    capture::process::{get_process_data, ProcessData},
+    prelude::*,
+    util::OsInfo,
+};
 
 use async_trait::async_trait;
 use wayland_client::{
+    backend::ObjectId,
     event_created_child,
     globals::{registry_queue_init, GlobalListContents},
     protocol::{wl_registry, wl_seat::WlSeat},
@@ -14,10 +24,8 @@ use wayland_protocols::ext::idle_notify::v1::client::ext_idle_notification_v1::E
 use wayland_protocols::ext::idle_notify::v1::client::ext_idle_notification_v1::ExtIdleNotificationV1;
 use wayland_protocols::ext::idle_notify::v1::client::ext_idle_notifier_v1::ExtIdleNotifierV1;
 use wayland_protocols_wlr::foreign_toplevel::v1::client::{
-    zwlr_foreign_toplevel_handle_v1::ZwlrForeignToplevelHandleV1,
-    zwlr_foreign_toplevel_manager_v1::{
-        Event, ZwlrForeignToplevelManagerV1, EVT_FINISHED_OPCODE, EVT_TOPLEVEL_OPCODE,
-    },
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{Event, ZwlrForeignToplevelManagerV1, EVT_TOPLEVEL_OPCODE},
 };
 // refs:
 // https://github.com/ActivityWatch/aw-watcher-window-wayland
@@ -29,6 +37,7 @@ use wayland_protocols_wlr::foreign_toplevel::v1::client::{
 // hyprctl clients -j
 
 // update 2025: wlr_foreign_toplevel_management_v1 is implemented, but since it does not allow getting pids i'll keep using wm-specific methods
+// where they exist. the foreign toplevel capturer is the fallback for all other wlroots compositors (river, labwc, Wayfire, ...)
 
 pub fn init_sway(_options: WaylandCaptureArgs) -> anyhow::Result<SwayCapturer> {
     SwayCapturer::new()
@@ -43,12 +52,10 @@ pub struct SwayCapturer {
     system: sysinfo::System,
 }
 
-#[allow(unused)]
 pub struct WaylandForeignTopLevelManagerCapturer {
     event_queue: EventQueue<WaylandListener>,
     listener: WaylandListener,
     os_info: OsInfo,
-    system: sysinfo::System,
 }
 
 pub struct HyprlandCapturer {
@@ -63,6 +70,15 @@ struct WaylandListener {
     idle_notification: ExtIdleNotificationV1,
     last_input: Instant,
     is_idle: bool,
+    /// only filled if the foreign toplevel manager is bound
+    toplevels: HashMap<ObjectId, ToplevelState>,
+}
+
+/// state of a toplevel handle. the events are double buffered, they only apply on the done event
+#[derive(Default)]
+struct ToplevelState {
+    current: WlrToplevel,
+    pending: WlrToplevel,
 }
 fn deep_collect_pids_sway(obj: &serde_json::Value) -> Vec<usize> {
     match obj {
@@ -126,6 +142,7 @@ impl SwayCapturer {
             idle_notification,
             last_input: Instant::now(),
             is_idle: false,
+            toplevels: HashMap::new(),
         };
         event_queue.roundtrip(&mut listener)?;
         let s = SwayCapturer {
@@ -166,6 +183,7 @@ impl HyprlandCapturer {
             idle_notification,
             last_input: Instant::now(),
             is_idle: false,
+            toplevels: HashMap::new(),
         };
         event_queue.roundtrip(&mut listener)?;
         let s = HyprlandCapturer {
@@ -182,6 +200,12 @@ impl WaylandForeignTopLevelManagerCapturer {
     pub fn new() -> anyhow::Result<WaylandForeignTopLevelManagerCapturer> {
         let connection = Connection::connect_to_env()
             .with_context(|| "Unable to connect to Wayland compositor")?;
+        WaylandForeignTopLevelManagerCapturer::from_connection(connection)
+    }
+
+    fn from_connection(
+        connection: Connection,
+    ) -> anyhow::Result<WaylandForeignTopLevelManagerCapturer> {
         let (globals, mut event_queue) =
             registry_queue_init::<WaylandListener>(&connection).unwrap();
         let queue_handle = event_queue.handle();
@@ -192,11 +216,13 @@ impl WaylandForeignTopLevelManagerCapturer {
             1..=ExtIdleNotifierV1::interface().version,
             (),
         )?;
-        let _windows: ZwlrForeignToplevelManagerV1 = globals.bind(
-            &queue_handle,
-            1..=ZwlrForeignToplevelManagerV1::interface().version,
-            (),
-        )?;
+        let _windows: ZwlrForeignToplevelManagerV1 = globals
+            .bind(
+                &queue_handle,
+                1..=ZwlrForeignToplevelManagerV1::interface().version,
+                (),
+            )
+            .context("compositor does not support wlr-foreign-toplevel-management")?;
 
         let inner_timeout = std::time::Duration::from_secs(30);
         let idle_notification =
@@ -206,20 +232,97 @@ impl WaylandForeignTopLevelManagerCapturer {
             idle_notification,
             last_input: Instant::now(),
             is_idle: false,
+            toplevels: HashMap::new(),
         };
+        // first roundtrip announces the toplevels, the second one their initial state
         event_queue.roundtrip(&mut listener)?;
-        panic!("not implemented fully");
-        /*let s = SwayCapturer {
-            system: sysinfo::System::new(),
+        event_queue.roundtrip(&mut listener)?;
+        Ok(WaylandForeignTopLevelManagerCapturer {
             event_queue,
             listener,
             os_info: util::get_os_info(),
-        };Ok(s)*/
+        })
+    }
+}
+
+/// turns a line based event stream from the compositor into change notifications.
+/// keep_alive is dropped (e.g. killing the child process) when the capture loop stops listening
+fn line_changes(
+    reader: impl AsyncBufRead + Unpin + Send + 'static,
+    is_change: fn(&str) -> bool,
+    keep_alive: impl Send + 'static,
+) -> BoxStream<'static, ()> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let _keep_alive = keep_alive;
+        let mut lines = reader.lines();
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    if is_change(&line) && tx.send(()).is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    log::warn!("Could not read compositor events: {:?}", e);
+                    break;
+                }
+            }
+        }
+    });
+    Box::pin(tokio_stream::wrappers::UnboundedReceiverStream::new(rx))
+}
+
+fn is_sway_change(line: &str) -> bool {
+    let change = serde_json::from_str::<serde_json::Value>(line)
+        .ok()
+        .and_then(|e| e.get("change")?.as_str().map(|s| s.to_string()));
+    matches!(change.as_deref(), Some("focus" | "title" | "close"))
+}
+
+fn is_hyprland_change(line: &str) -> bool {
+    matches!(
+        line.split_once(">>").map(|(event, _)| event),
+        Some("activewindow" | "activewindowv2" | "windowtitle" | "windowtitlev2" | "closewindow")
+    )
+}
+
+fn hyprland_event_socket() -> anyhow::Result<std::path::PathBuf> {
+    let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE")
+        .context("HYPRLAND_INSTANCE_SIGNATURE is not set")?;
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").context("XDG_RUNTIME_DIR is not set")?;
+    let path = std::path::Path::new(&runtime_dir)
+        .join("hypr")
+        .join(&signature)
+        .join(".socket2.sock");
+    if path.exists() {
+        Ok(path)
+    } else {
+        // hyprland < 0.40
+        Ok(std::path::Path::new("/tmp/hypr")
+            .join(&signature)
+            .join(".socket2.sock"))
     }
 }
 
 #[async_trait]
 impl Capturer for SwayCapturer {
+    fn changes(&mut self) -> anyhow::Result<Option<BoxStream<'static, ()>>> {
+        let mut child = Command::new("swaymsg")
+            .args(["-t", "subscribe", "-m", r#"["window"]"#])
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Could not run swaymsg")?;
+        let stdout = child.stdout.take().context("swaymsg has no stdout")?;
+        Ok(Some(line_changes(
+            tokio::io::BufReader::new(stdout),
+            is_sway_change,
+            child,
+        )))
+    }
+
     async fn capture(&mut self) -> anyhow::Result<EventData> {
         let res = Command::new("swaymsg")
             .arg("-t")
@@ -238,27 +341,50 @@ impl Capturer for SwayCapturer {
         // we don't really need to pump the event queue because we
         self.event_queue.roundtrip(&mut self.listener)?;
         let pids = deep_collect_pids_sway(&parsed);
+        let processes: Vec<ProcessData> = pids
+            .into_iter()
+            .filter_map(|pid| get_process_data(&mut self.system, pid))
+            .collect();
+        let focused_pid = find_focused_sway_window(&parsed).and_then(|w| w.get("pid")?.as_i64());
+        let terminal = match processes.iter().find(|p| Some(p.pid as i64) == focused_pid) {
+            Some(process) => get_terminal_context(&mut self.system, process).await,
+            None => None,
+        };
         Ok(EventData::sway_v1(SwayEventData {
             window_tree: parsed,
-            processes: pids
-                .into_iter()
-                .filter_map(|pid| get_process_data(&mut self.system, pid))
-                .collect(),
+            processes,
             os_info: self.os_info.clone(),
             ms_since_user_input: if self.listener.is_idle {
                 (Instant::now() - self.listener.last_input).as_millis() as u32
             } else {
                 0
             },
-            network: linux::network::get_network_info()
-                .map_err(|e| log::info!("could not get net info: {}", e))
-                .ok(),
+            context: CaptureContext {
+                network: linux::network::get_network_info()
+                    .map_err(|e| log::info!("could not get net info: {}", e))
+                    .ok(),
+                terminal,
+                ..Default::default()
+            },
         }))
     }
 }
 
 #[async_trait]
 impl Capturer for HyprlandCapturer {
+    fn changes(&mut self) -> anyhow::Result<Option<BoxStream<'static, ()>>> {
+        let path = hyprland_event_socket()?;
+        let socket = std::os::unix::net::UnixStream::connect(&path)
+            .with_context(|| format!("Could not connect to {path:?}"))?;
+        socket.set_nonblocking(true)?;
+        let socket = tokio::net::UnixStream::from_std(socket)?;
+        Ok(Some(line_changes(
+            tokio::io::BufReader::new(socket),
+            is_hyprland_change,
+            (),
+        )))
+    }
+
     async fn capture(&mut self) -> anyhow::Result<EventData> {
         let res = Command::new("hyprctl")
             .arg("clients")
@@ -273,25 +399,55 @@ impl Capturer for HyprlandCapturer {
             )
         }
         let parsed: serde_json::Value = serde_json::from_slice(&res.stdout)?;
+        let monitors = match Command::new("hyprctl")
+            .args(["monitors", "-j"])
+            .output()
+            .await
+        {
+            Ok(res) if res.status.success() => serde_json::from_slice(&res.stdout)
+                .map_err(|e| log::info!("could not parse hyprland monitors: {}", e))
+                .unwrap_or_default(),
+            res => {
+                log::info!("could not get hyprland monitors: {:?}", res);
+                serde_json::Value::Null
+            }
+        };
         // self.idle_watcher.run_iteration()?;
         // we don't really need to pump the event queue because we
         self.event_queue.roundtrip(&mut self.listener)?;
         let pids = deep_collect_pids_hyprland(&parsed);
+        let processes: Vec<ProcessData> = pids
+            .into_iter()
+            .filter_map(|pid| get_process_data(&mut self.system, pid))
+            .collect();
+        let focused_pid = parsed.as_array().and_then(|windows| {
+            windows
+                .iter()
+                .find(|w| w.get("focusHistoryID").and_then(|id| id.as_u64()) == Some(0))?
+                .get("pid")?
+                .as_i64()
+        });
+        let terminal = match processes.iter().find(|p| Some(p.pid as i64) == focused_pid) {
+            Some(process) => get_terminal_context(&mut self.system, process).await,
+            None => None,
+        };
         Ok(EventData::hyprland_v1(HyprlandEventData {
             window_tree: parsed,
-            processes: pids
-                .into_iter()
-                .filter_map(|pid| get_process_data(&mut self.system, pid))
-                .collect(),
+            monitors,
+            processes,
             os_info: self.os_info.clone(),
             ms_since_user_input: if self.listener.is_idle {
                 (Instant::now() - self.listener.last_input).as_millis() as u32
             } else {
                 0
             },
-            network: linux::network::get_network_info()
-                .map_err(|e| log::info!("could not get net info: {}", e))
-                .ok(),
+            context: CaptureContext {
+                network: linux::network::get_network_info()
+                    .map_err(|e| log::info!("could not get net info: {}", e))
+                    .ok(),
+                terminal,
+                ..Default::default()
+            },
         }))
     }
 }
@@ -299,7 +455,27 @@ impl Capturer for HyprlandCapturer {
 #[async_trait]
 impl Capturer for WaylandForeignTopLevelManagerCapturer {
     async fn capture(&mut self) -> anyhow::Result<EventData> {
-        anyhow::bail!("not implemented");
+        self.event_queue.roundtrip(&mut self.listener)?;
+        Ok(EventData::wlr_toplevel_v1(WlrToplevelEventData {
+            os_info: self.os_info.clone(),
+            ms_since_user_input: if self.listener.is_idle {
+                (Instant::now() - self.listener.last_input).as_millis() as u32
+            } else {
+                0
+            },
+            toplevels: self
+                .listener
+                .toplevels
+                .values()
+                .map(|t| t.current.clone())
+                .collect(),
+            context: CaptureContext {
+                network: linux::network::get_network_info()
+                    .map_err(|e| log::info!("could not get net info: {}", e))
+                    .ok(),
+                ..Default::default()
+            },
+        }))
     }
 }
 
@@ -390,18 +566,18 @@ impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandListener {
 
 impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for WaylandListener {
     fn event(
-        _state: &mut Self,
+        state: &mut Self,
         _x: &ZwlrForeignToplevelManagerV1,
         event: <ZwlrForeignToplevelManagerV1 as Proxy>::Event,
         _: &(),
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
-        println!("got foreign toplevel manager event: {:?}", event);
         match event {
             Event::Toplevel { toplevel } => {
-                println!("New toplevel window: {:?}", toplevel);
-                // toplevel.send_request(req)
+                state
+                    .toplevels
+                    .insert(toplevel.id(), ToplevelState::default());
             }
             Event::Finished => {
                 log::info!("Foreign toplevel manager finished");
@@ -410,28 +586,123 @@ impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for WaylandListener {
         }
     }
     event_created_child!(Self, ZwlrForeignToplevelManagerV1, [
-        EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ()),
-        EVT_FINISHED_OPCODE => (ZwlrForeignToplevelHandleV1, ())
+        EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ())
     ]);
-    /*fn event_created_child(
-        opcode: u16,
-        _qhandle: &QueueHandle<Self>,
-    ) -> std::sync::Arc<dyn wayland_client::backend::ObjectData> {
-        println!("debug: {:?}", opcode);
-        _qhandle.make_data::<ZwlrForeignToplevelHandleV1, _>(())
-    }*/
 }
 
+/// zwlr_foreign_toplevel_handle_v1 state enum values
+const TOPLEVEL_STATE_MAXIMIZED: u32 = 0;
+const TOPLEVEL_STATE_MINIMIZED: u32 = 1;
+const TOPLEVEL_STATE_ACTIVATED: u32 = 2;
+const TOPLEVEL_STATE_FULLSCREEN: u32 = 3;
+
 impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for WaylandListener {
     fn event(
-        _state: &mut Self,
-        _: &ZwlrForeignToplevelHandleV1,
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
         event: <ZwlrForeignToplevelHandleV1 as Proxy>::Event,
         _: &(),
         _: &Connection,
         _: &QueueHandle<Self>,
     ) {
-        println!("got foreign toplevel handle event: {:?}", event);
-        {}
+        use zwlr_foreign_toplevel_handle_v1::Event as HandleEvent;
+        if let HandleEvent::Closed = event {
+            state.toplevels.remove(&handle.id());
+            handle.destroy();
+            return;
+        }
+        let Some(toplevel) = state.toplevels.get_mut(&handle.id()) else {
+            return;
+        };
+        match event {
+            HandleEvent::Title { title } => toplevel.pending.title = title,
+            HandleEvent::AppId { app_id } => toplevel.pending.app_id = app_id,
+            HandleEvent::State { state } => {
+                // array of native endian u32
+                let states: Vec<u32> = state
+                    .chunks_exact(4)
+                    .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+                    .collect();
+                toplevel.pending.maximized = states.contains(&TOPLEVEL_STATE_MAXIMIZED);
+                toplevel.pending.minimized = states.contains(&TOPLEVEL_STATE_MINIMIZED);
+                toplevel.pending.activated = states.contains(&TOPLEVEL_STATE_ACTIVATED);
+                toplevel.pending.fullscreen = states.contains(&TOPLEVEL_STATE_FULLSCREEN);
+            }
+            HandleEvent::Done => toplevel.current = toplevel.pending.clone(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn on_path(program: &str) -> bool {
+        std::env::var_os("PATH")
+            .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+    }
+
+    /// starts a headless sway with a terminal and checks that its toplevel is captured.
+    /// skipped if sway or foot is not installed
+    #[tokio::test]
+    async fn test_foreign_toplevel_headless() -> anyhow::Result<()> {
+        if let Some(missing) = ["sway", "foot"].into_iter().find(|p| !on_path(p)) {
+            log::warn!("skipping wayland test, {} is not installed", missing);
+            return Ok(());
+        }
+        let runtime_dir =
+            std::env::temp_dir().join(format!("timetrackrs-wlr-{}", std::process::id()));
+        std::fs::create_dir_all(&runtime_dir)?;
+        let config = runtime_dir.join("sway.conf");
+        std::fs::write(
+            &config,
+            "exec foot --app-id timetrackrs-test --title timetrackrs-test-title\n",
+        )?;
+        let _compositor = Command::new("sway")
+            .arg("--config")
+            .arg(&config)
+            .env("XDG_RUNTIME_DIR", &runtime_dir)
+            .env("WLR_BACKENDS", "headless")
+            .env("WLR_LIBINPUT_NO_DEVICES", "1")
+            .env("WLR_RENDERER", "pixman")
+            .env_remove("WAYLAND_DISPLAY")
+            .env_remove("DISPLAY")
+            .kill_on_drop(true)
+            .spawn()
+            .context("could not start sway")?;
+
+        let mut toplevels = vec![];
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            let socket = std::fs::read_dir(&runtime_dir)?
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .find(|p| {
+                    let name = p.file_name().unwrap_or_default().to_string_lossy();
+                    name.starts_with("wayland-") && !name.ends_with(".lock")
+                });
+            let Some(socket) = socket else {
+                continue;
+            };
+            let stream = std::os::unix::net::UnixStream::connect(&socket)?;
+            let mut capturer = WaylandForeignTopLevelManagerCapturer::from_connection(
+                Connection::from_socket(stream)?,
+            )?;
+            if let EventData::wlr_toplevel_v1(data) = capturer.capture().await? {
+                toplevels = data.toplevels;
+            }
+            if !toplevels.is_empty() {
+                break;
+            }
+        }
+        std::fs::remove_dir_all(&runtime_dir).ok();
+        let toplevel = toplevels
+            .iter()
+            .find(|t| t.app_id == "timetrackrs-test")
+            .context("test window not found")?;
+        assert_eq!(toplevel.title, "timetrackrs-test-title");
+        assert!(toplevel.activated);
+        Ok(())
     }
 }
@@ -1,4 +1,7 @@
-use crate::{capture::process::ProcessData, prelude::*};
+use crate::{
+    capture::{process::ProcessData, visibility::*},
+    prelude::*,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
@@ -20,9 +23,14 @@ impl CapturerCreator for WaylandCaptureArgs {
             }
             Ok("Hyprland") => super::wayland::init_hyprland(self.clone())
                 .map(|e| Box::new(e) as Box<dyn Capturer>),
+            Ok(desktop) if desktop.split(':').any(|d| d == "GNOME") => {
+                Ok(Box::new(super::gnome::GnomeCapturer::new()) as Box<dyn Capturer>)
+            }
+            Ok(desktop) if desktop.split(':').any(|d| d == "KDE") => {
+                Ok(Box::new(super::kde::KdeCapturer::new()) as Box<dyn Capturer>)
+            }
             _ => {
-                log::warn!("Unknown or unsupported desktop environment");
-                // anyhow::bail!("Unsupported desktop environment")
+                log::info!("Unknown desktop environment, using wlr-foreign-toplevel-management");
                 super::wayland::WaylandForeignTopLevelManagerCapturer::new()
                     .map(|e| Box::new(e) as Box<dyn Capturer>)
             }
@@ -41,27 +49,24 @@ impl CapturerCreator for WaylandCaptureArgs {
 pub struct SwayEventData {
     pub os_info: util::OsInfo,
     pub ms_since_user_input: u32,
-    pub network: Option<NetworkInfo>,
     /// response of swaymsg -t get_tree command
     pub window_tree: serde_json::Value,
     pub processes: Vec<ProcessData>,
+    /// network, session, input, media, power and terminal state
+    #[serde(flatten)]
+    pub context: CaptureContext,
 }
 
 impl ExtractInfo for SwayEventData {
     fn extract_info(&self) -> Option<Tags> {
-        use std::time::Duration;
         let mut tags = Tags::new();
 
-        if super::super::pc_common::is_idle(Duration::from_millis(self.ms_since_user_input as u64))
-        {
+        if self.context.is_idle(self.ms_since_user_input) {
             return None;
         }
-
+        self.context.add_tags(&mut tags);
         self.os_info.to_partial_general_software(&mut tags);
-
-        if let Some(NetworkInfo { wifi: Some(wifi) }) = &self.network {
-            tags.add("connected-wifi", &wifi.ssid);
-        }
+        add_visibility_tags(&mut tags, &sway_visibility(&self.window_tree));
 
         // Find the focused window in the Sway tree
         if let Some(focused_window) = find_focused_sway_window(&self.window_tree) {
@@ -110,26 +115,29 @@ impl ExtractInfo for SwayEventData {
 pub struct HyprlandEventData {
     pub os_info: util::OsInfo,
     pub ms_since_user_input: u32,
-    pub network: Option<NetworkInfo>,
     /// response of swaymsg -t get_tree command
     pub window_tree: serde_json::Value,
     pub processes: Vec<ProcessData>,
+    /// network, session, input, media, power and terminal state
+    #[serde(flatten)]
+    pub context: CaptureContext,
+    /// response of hyprctl monitors -j
+    #[serde(default, skip_serializing_if = "JsonValue::is_null")]
+    pub monitors: JsonValue,
 }
 impl ExtractInfo for HyprlandEventData {
     fn extract_info(&self) -> Option<Tags> {
-        use std::time::Duration;
         let mut tags = Tags::new();
 
-        if super::super::pc_common::is_idle(Duration::from_millis(self.ms_since_user_input as u64))
-        {
+        if self.context.is_idle(self.ms_since_user_input) {
             return None;
         }
-
+        self.context.add_tags(&mut tags);
         self.os_info.to_partial_general_software(&mut tags);
-
-        if let Some(NetworkInfo { wifi: Some(wifi) }) = &self.network {
-            tags.add("connected-wifi", &wifi.ssid);
-        }
+        add_visibility_tags(
+            &mut tags,
+            &hyprland_visibility(&self.window_tree, &self.monitors),
+        );
 
         // Find the focused window in Hyprland (the one with focusHistoryID: 0)
         if let Some(windows) = self.window_tree.as_array() {
@@ -185,7 +193,144 @@ impl ExtractInfo for HyprlandEventData {
     }
 }
 
-fn find_focused_sway_window(node: &JsonValue) -> Option<&JsonValue> {
+/// a window as announced by wlr-foreign-toplevel-management. the protocol does not expose pids
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WlrToplevel {
+    pub title: String,
+    pub app_id: String,
+    pub activated: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WlrToplevelEventData {
+    pub os_info: util::OsInfo,
+    pub ms_since_user_input: u32,
+    pub toplevels: Vec<WlrToplevel>,
+    /// network, session, input, media, power and terminal state
+    #[serde(flatten)]
+    pub context: CaptureContext,
+}
+
+impl ExtractInfo for WlrToplevelEventData {
+    fn extract_info(&self) -> Option<Tags> {
+        if self.context.is_idle(self.ms_since_user_input) {
+            return None;
+        }
+        let mut tags = Tags::new();
+        self.context.add_tags(&mut tags);
+        self.os_info.to_partial_general_software(&mut tags);
+
+        if let Some(focused) = self.toplevels.iter().find(|t| t.activated) {
+            let window_class = if focused.app_id.is_empty() {
+                None
+            } else {
+                Some((focused.app_id.clone(), "".to_string()))
+            };
+            tags.extend(super::super::pc_common::match_software(
+                &focused.title,
+                &window_class,
+                None,
+                None,
+                None,
+            ));
+        }
+        Some(tags)
+    }
+}
+
+/// the focused window as reported by the desktop shell over D-Bus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DesktopWindow {
+    pub title: String,
+    /// wayland app id or desktop file name
+    pub app_id: Option<String>,
+    /// X11 WM_CLASS (also set by GNOME and KWin for wayland windows)
+    pub wm_class: Option<String>,
+    pub pid: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GnomeEventData {
+    pub os_info: util::OsInfo,
+    pub ms_since_user_input: u32,
+    pub focused_window: Option<DesktopWindow>,
+    pub process: Option<ProcessData>,
+    /// network, session, input, media, power and terminal state
+    #[serde(flatten)]
+    pub context: CaptureContext,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdeEventData {
+    pub os_info: util::OsInfo,
+    pub ms_since_user_input: u32,
+    pub focused_window: Option<DesktopWindow>,
+    pub process: Option<ProcessData>,
+    /// network, session, input, media, power and terminal state
+    #[serde(flatten)]
+    pub context: CaptureContext,
+}
+
+fn desktop_window_tags(
+    os_info: &util::OsInfo,
+    ms_since_user_input: u32,
+    focused_window: &Option<DesktopWindow>,
+    process: &Option<ProcessData>,
+    context: &CaptureContext,
+) -> Option<Tags> {
+    if context.is_idle(ms_since_user_input) {
+        return None;
+    }
+    let mut tags = Tags::new();
+    context.add_tags(&mut tags);
+    os_info.to_partial_general_software(&mut tags);
+
+    if let Some(window) = focused_window {
+        let window_class = match (&window.wm_class, &window.app_id) {
+            (Some(class), app_id) => Some((class.clone(), app_id.clone().unwrap_or_default())),
+            (None, Some(app_id)) => Some((app_id.clone(), "".to_string())),
+            (None, None) => None,
+        };
+        let process = process.as_ref().filter(|p| Some(p.pid) == window.pid);
+        tags.extend(super::super::pc_common::match_software(
+            &window.title,
+            &window_class,
+            process.and_then(|p| p.exe.as_deref()),
+            process.and_then(|p| p.cwd.as_deref()),
+            process.map(|p| p.cmd.as_ref()),
+        ));
+    }
+    Some(tags)
+}
+
+impl ExtractInfo for GnomeEventData {
+    fn extract_info(&self) -> Option<Tags> {
+        desktop_window_tags(
+            &self.os_info,
+            self.ms_since_user_input,
+            &self.focused_window,
+            &self.process,
+            &self.context,
+        )
+    }
+}
+
+impl ExtractInfo for KdeEventData {
+    fn extract_info(&self) -> Option<Tags> {
+        desktop_window_tags(
+            &self.os_info,
+            self.ms_since_user_input,
+            &self.focused_window,
+            &self.process,
+            &self.context,
+        )
+    }
+}
+
+pub(crate) fn find_focused_sway_window(node: &JsonValue) -> Option<&JsonValue> {
     // Check if this node is focused
     if let Some(focused) = node.get("focused") {
         if focused.as_bool() == Some(true) {
@@ -213,3 +358,206 @@ fn find_focused_sway_window(node: &JsonValue) -> Option<&JsonValue> {
 
     None
 }
+
+fn json_i32(value: Option<&JsonValue>) -> Option<i32> {
+    value?.as_f64().map(|v| v as i32)
+}
+
+fn sway_rect(rect: &JsonValue) -> Option<Rect> {
+    Some(Rect {
+        x: json_i32(rect.get("x"))?,
+        y: json_i32(rect.get("y"))?,
+        width: json_i32(rect.get("width"))?,
+        height: json_i32(rect.get("height"))?,
+    })
+}
+
+/// visible views of a sway tree node with whether they are floating
+fn collect_sway_windows(
+    node: &JsonValue,
+    floating: bool,
+    windows: &mut Vec<(bool, StackedWindow)>,
+) {
+    let software = node
+        .get("app_id")
+        .and_then(|a| a.as_str())
+        .or_else(|| node.get("window_properties")?.get("class")?.as_str());
+    if let (Some(software), Some(true), Some(rect)) = (
+        software,
+        node.get("visible").and_then(|v| v.as_bool()),
+        node.get("rect").and_then(sway_rect),
+    ) {
+        windows.push((
+            floating,
+            StackedWindow {
+                software: software.to_string(),
+                rect,
+                fullscreen: node
+                    .get("fullscreen_mode")
+                    .and_then(|f| f.as_u64())
+                    .unwrap_or(0)
+                    > 0,
+                focused: node.get("focused").and_then(|f| f.as_bool()) == Some(true),
+            },
+        ));
+    }
+    for child in node
+        .get("nodes")
+        .and_then(|n| n.as_array())
+        .into_iter()
+        .flatten()
+    {
+        collect_sway_windows(child, floating, windows);
+    }
+    for child in node
+        .get("floating_nodes")
+        .and_then(|n| n.as_array())
+        .into_iter()
+        .flatten()
+    {
+        collect_sway_windows(child, true, windows);
+    }
+}
+
+fn sway_visibility(tree: &JsonValue) -> Vec<WindowVisibility> {
+    let mut outputs = Vec::new();
+    let mut windows = Vec::new();
+    for output in tree
+        .get("nodes")
+        .and_then(|n| n.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let name = output
+            .get("name")
+            .and_then(|n| n.as_str())
+            .unwrap_or_default();
+        // the scratchpad
+        if name == "__i3" {
+            continue;
+        }
+        if let Some(rect) = output.get("rect").and_then(sway_rect) {
+            outputs.push(Output {
+                name: name.to_string(),
+                rect,
+            });
+        }
+        collect_sway_windows(output, false, &mut windows);
+    }
+    // floating windows are above tiled ones, fullscreen windows above everything
+    windows.sort_by_key(|(floating, w)| (w.fullscreen, *floating));
+    let windows: Vec<StackedWindow> = windows.into_iter().map(|(_, w)| w).collect();
+    compute_visibility(&outputs, &windows)
+}
+
+/// hyprland positions windows in logical pixels, the monitor size is in physical pixels
+fn hyprland_monitor_rect(monitor: &JsonValue) -> Option<Rect> {
+    let scale = monitor.get("scale").and_then(|s| s.as_f64()).unwrap_or(1.0);
+    let mut width = monitor.get("width")?.as_f64()? / scale;
+    let mut height = monitor.get("height")?.as_f64()? / scale;
+    // rotated by 90 or 270 degrees
+    if monitor
+        .get("transform")
+        .and_then(|t| t.as_u64())
+        .unwrap_or(0)
+        % 2
+        == 1
+    {
+        std::mem::swap(&mut width, &mut height);
+    }
+    Some(Rect {
+        x: json_i32(monitor.get("x"))?,
+        y: json_i32(monitor.get("y"))?,
+        width: width.round() as i32,
+        height: height.round() as i32,
+    })
+}
+
+fn hyprland_visibility(clients: &JsonValue, monitors: &JsonValue) -> Vec<WindowVisibility> {
+    let mut outputs = Vec::new();
+    let mut workspaces = Vec::new();
+    for monitor in monitors.as_array().into_iter().flatten() {
+        if monitor.get("disabled").and_then(|d| d.as_bool()) == Some(true) {
+            continue;
+        }
+        let (Some(name), Some(rect)) = (
+            monitor.get("name").and_then(|n| n.as_str()),
+            hyprland_monitor_rect(monitor),
+        ) else {
+            continue;
+        };
+        outputs.push(Output {
+            name: name.to_string(),
+            rect,
+        });
+        for (workspace, special) in [("activeWorkspace", false), ("specialWorkspace", true)] {
+            if let Some(id) = monitor
+                .get(workspace)
+                .and_then(|w| w.get("id"))
+                .and_then(|id| id.as_i64())
+                .filter(|id| *id != 0)
+            {
+                workspaces.push((id, special));
+            }
+        }
+    }
+    let mut windows = Vec::new();
+    for client in clients.as_array().into_iter().flatten() {
+        let Some(&(_, special)) = workspaces.iter().find(|(id, _)| {
+            client
+                .get("workspace")
+                .and_then(|w| w.get("id"))
+                .and_then(|id| id.as_i64())
+                == Some(*id)
+        }) else {
+            continue;
+        };
+        if client.get("mapped").and_then(|m| m.as_bool()) == Some(false)
+            || client.get("hidden").and_then(|h| h.as_bool()) == Some(true)
+        {
+            continue;
+        }
+        let (Some(software), Some(at), Some(size)) = (
+            client.get("class").and_then(|c| c.as_str()),
+            client.get("at").and_then(|a| a.as_array()),
+            client.get("size").and_then(|s| s.as_array()),
+        ) else {
+            continue;
+        };
+        let (Some(x), Some(y), Some(width), Some(height)) = (
+            json_i32(at.first()),
+            json_i32(at.get(1)),
+            json_i32(size.first()),
+            json_i32(size.get(1)),
+        ) else {
+            continue;
+        };
+        // a bool in older versions, 2 is fullscreen in newer ones (1 is maximized)
+        let fullscreen = client
+            .get("fullscreen")
+            .is_some_and(|f| f.as_bool().unwrap_or_else(|| f.as_u64().unwrap_or(0) >= 2));
+        let floating = client.get("floating").and_then(|f| f.as_bool()) == Some(true);
+        let history = client
+            .get("focusHistoryID")
+            .and_then(|h| h.as_u64())
+            .unwrap_or(u64::MAX);
+        windows.push((
+            (special, fullscreen, floating, std::cmp::Reverse(history)),
+            StackedWindow {
+                software: software.to_string(),
+                rect: Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                },
+                fullscreen,
+                focused: history == 0,
+            },
+        ));
+    }
+    // there is no stacking order, so recently focused floating windows are assumed to be on top
+    windows.sort_by_key(|(order, _)| *order);
+    let windows: Vec<StackedWindow> = windows.into_iter().map(|(_, w)| w).collect();
+    compute_visibility(&outputs, &windows)
+}
@@ -5,19 +5,26 @@
 #![allow(non_snake_case)]
 
 use super::x11_types::*;
+use crate::capture::linux::terminal::get_terminal_context;
 use crate::capture::process::get_process_data;
 use crate::prelude::*;
 
+use futures::stream::BoxStream;
 use serde_json::{json, Value as J};
 use std::collections::{BTreeMap, HashMap};
 use x11rb::connection::Connection;
 use x11rb::connection::RequestConnection;
+use x11rb::protocol::randr::ConnectionExt as _;
 use x11rb::protocol::xproto::get_property;
 use x11rb::protocol::xproto::intern_atom;
 use x11rb::protocol::xproto::Atom;
 use x11rb::protocol::xproto::AtomEnum;
+use x11rb::protocol::xproto::ChangeWindowAttributesAux;
 use x11rb::protocol::xproto::ConnectionExt;
+use x11rb::protocol::xproto::EventMask;
+use x11rb::protocol::xproto::MapState;
 use x11rb::protocol::xproto::Window;
+use x11rb::protocol::Event;
 
 fn get_property32<Conn: ?Sized + RequestConnection>(
     conn: &Conn,
@@ -86,6 +93,47 @@ impl<C: Connection> X11Capturer<C> {
             Ok(ok) => Ok(ok.clone()),
         }
     }
+    fn randr_monitors(&mut self) -> anyhow::Result<Vec<X11Monitor>> {
+        let monitors = self
+            .conn
+            .randr_get_monitors(self.root_window, true)?
+            .reply()?
+            .monitors;
+        monitors
+            .into_iter()
+            .map(|m| {
+                Ok(X11Monitor {
+                    name: self.atom_name(m.name)?,
+                    primary: m.primary,
+                    geometry: X11WindowGeometry {
+                        x: m.x as i32,
+                        y: m.y as i32,
+                        width: m.width as i32,
+                        height: m.height as i32,
+                    },
+                })
+            })
+            .collect()
+    }
+    /// falls back to the whole screen without RandR 1.5
+    fn monitors(&mut self) -> anyhow::Result<Vec<X11Monitor>> {
+        match self.randr_monitors() {
+            Ok(monitors) if !monitors.is_empty() => return Ok(monitors),
+            Ok(_) => {}
+            Err(e) => log::debug!("could not get randr monitors: {:?}", e),
+        }
+        let root = self.conn.get_geometry(self.root_window)?.reply()?;
+        Ok(vec![X11Monitor {
+            name: "screen".to_string(),
+            primary: true,
+            geometry: X11WindowGeometry {
+                x: 0,
+                y: 0,
+                width: root.width as i32,
+                height: root.height as i32,
+            },
+        }])
+    }
 }
 pub fn init(options: X11CaptureArgs) -> anyhow::Result<X11Capturer<impl Connection>> {
     let (conn, screen_num) = x11rb::connect(None)?;
@@ -100,13 +148,68 @@ pub fn init(options: X11CaptureArgs) -> anyhow::Result<X11Capturer<impl Connecti
     })
 }
 
+/// blocks and sends a message whenever the active window (_NET_ACTIVE_WINDOW on the root window)
+/// or the title of the active window changes. uses its own connection since the capturer's one is not Sync
+fn watch_focus_changes(tx: tokio::sync::mpsc::UnboundedSender<()>) -> anyhow::Result<()> {
+    let (conn, screen_num) = x11rb::connect(None)?;
+    let root = conn.setup().roots[screen_num].root;
+    let NET_ACTIVE_WINDOW = conn
+        .intern_atom(false, b"_NET_ACTIVE_WINDOW")?
+        .reply()?
+        .atom;
+    let NET_WM_NAME = conn.intern_atom(false, b"_NET_WM_NAME")?.reply()?.atom;
+    let watch = ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE);
+    let unwatch = ChangeWindowAttributesAux::new().event_mask(EventMask::NO_EVENT);
+    conn.change_window_attributes(root, &watch)?;
+    conn.flush()?;
+    let mut active: Option<Window> = None;
+    loop {
+        let current = conn
+            .get_property(false, root, NET_ACTIVE_WINDOW, AtomEnum::WINDOW, 0, 1)?
+            .reply()?
+            .value32()
+            .and_then(|mut v| v.next())
+            .filter(|w| *w != 0);
+        if current != active {
+            // errors (e.g. the window is already gone) arrive as error events, which are ignored below
+            if let Some(w) = active {
+                conn.change_window_attributes(w, &unwatch)?;
+            }
+            if let Some(w) = current {
+                conn.change_window_attributes(w, &watch)?;
+            }
+            conn.flush()?;
+            active = current;
+        }
+        if let Event::PropertyNotify(e) = conn.wait_for_event()? {
+            if (e.atom == NET_ACTIVE_WINDOW || e.atom == NET_WM_NAME) && tx.send(()).is_err() {
+                // capture loop is gone
+                return Ok(());
+            }
+        }
+    }
+}
+
 #[async_trait]
 impl<C: Connection + Send> Capturer for X11Capturer<C> {
+    fn changes(&mut self) -> anyhow::Result<Option<BoxStream<'static, ()>>> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        std::thread::spawn(move || {
+            if let Err(e) = watch_focus_changes(tx) {
+                log::warn!("Stopped watching X11 focus changes: {:?}", e);
+            }
+        });
+        Ok(Some(Box::pin(
+            tokio_stream::wrappers::UnboundedReceiverStream::new(rx),
+        )))
+    }
+
     async fn capture(&mut self) -> anyhow::Result<EventData> {
         let mut system = sysinfo::System::new();
         let NET_CLIENT_LIST = self.atom("_NET_CLIENT_LIST")?;
         let NET_CURRENT_DESKTOP = self.atom("_NET_CURRENT_DESKTOP")?;
         let NET_DESKTOP_NAMES = self.atom("_NET_DESKTOP_NAMES")?;
+        let NET_CLIENT_LIST_STACKING = self.atom("_NET_CLIENT_LIST_STACKING")?;
 
         let blacklist = vec![
             self.atom("_NET_WM_ICON")?, // HUUGE
@@ -126,6 +229,9 @@ impl<C: Connection + Send> Capturer for X11Capturer<C> {
         )?);
         let focus = self.conn.get_input_focus()?.reply()?.focus;
         let mut windows = get_property32(&self.conn, self.root_window, NET_CLIENT_LIST)?;
+        let stacking = get_property32(&self.conn, self.root_window, NET_CLIENT_LIST_STACKING)
+            .map_err(|e| log::debug!("could not get stacking order: {:?}", e))
+            .unwrap_or_default();
         windows.sort_unstable();
         if self.options.only_focused_window {
             windows.retain(|i| i == &focus);
@@ -214,6 +320,7 @@ impl<C: Connection + Send> Capturer for X11Capturer<C> {
                 .conn
                 .translate_coordinates(window, self.root_window, 0, 0)?
                 .reply()?;
+            let attributes = self.conn.get_window_attributes(window)?.reply()?;
 
             windowsdata.push(X11WindowData {
                 window_id: window,
@@ -225,12 +332,21 @@ impl<C: Connection + Send> Capturer for X11Capturer<C> {
                 },
                 process,
                 window_properties: propmap,
+                viewable: Some(attributes.map_state == MapState::VIEWABLE),
             });
         }
         let xscreensaver =
             x11rb::protocol::screensaver::query_info(&self.conn, self.root_window)?.reply()?;
         // see XScreenSaverQueryInfo at https://linux.die.net/man/3/xscreensaverunsetattributes
 
+        let terminal = match windowsdata
+            .iter()
+            .find(|w| w.window_id == focus)
+            .and_then(|w| w.process.as_ref())
+        {
+            Some(process) => get_terminal_context(&mut system, process).await,
+            None => None,
+        };
         let data = X11EventData {
             desktop_names,
             os_info: self.os_info.clone(),
@@ -240,9 +356,15 @@ impl<C: Connection + Send> Capturer for X11Capturer<C> {
             ms_until_screensaver: xscreensaver.ms_until_server,
             screensaver_window: xscreensaver.saver_window,
             windows: windowsdata,
-            network: linux::network::get_network_info()
-                .map_err(|e| log::info!("could not get net info: {}", e))
-                .ok(),
+            context: CaptureContext {
+                network: linux::network::get_network_info()
+                    .map_err(|e| log::info!("could not get net info: {}", e))
+                    .ok(),
+                terminal,
+                ..Default::default()
+            },
+            stacking,
+            monitors: self.monitors()?,
         };
         Ok(EventData::x11_v2(data))
     }
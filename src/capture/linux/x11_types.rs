@@ -1,5 +1,8 @@
 // x11 capture types (must be cross-platform)
-use crate::{capture::process::ProcessData, prelude::*};
+use crate::{
+    capture::{process::ProcessData, visibility::*},
+    prelude::*,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as J;
 use std::collections::BTreeMap;
@@ -37,8 +40,15 @@ pub struct X11EventData {
     pub ms_since_user_input: u32,
     pub ms_until_screensaver: u32,
     pub screensaver_window: u32,
-    pub network: Option<NetworkInfo>,
     pub windows: Vec<X11WindowData>,
+    /// network, session, input, media, power and terminal state
+    #[serde(flatten)]
+    pub context: CaptureContext,
+    /// window ids from _NET_CLIENT_LIST_STACKING, bottom to top
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stacking: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub monitors: Vec<X11Monitor>,
 }
 #[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
 pub struct X11WindowData {
@@ -46,6 +56,16 @@ pub struct X11WindowData {
     pub geometry: X11WindowGeometry,
     pub process: Option<ProcessData>,
     pub window_properties: BTreeMap<String, J>,
+    /// false if the window or one of its parents is unmapped (e.g. on another workspace)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub viewable: Option<bool>,
+}
+/// a RandR monitor, or the whole screen if RandR 1.5 is not available
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct X11Monitor {
+    pub name: String,
+    pub primary: bool,
+    pub geometry: X11WindowGeometry,
 }
 #[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
 pub struct X11WindowGeometry {
@@ -73,12 +93,142 @@ pub struct WifiInterface {
     pub connected_time: u32,
 }
 
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkInterfaceKind {
+    Wired,
+    Wifi,
+    WireGuard,
+    /// tun/tap devices, e.g. OpenVPN
+    Tunnel,
+}
+
+impl NetworkInterfaceKind {
+    pub fn is_vpn(&self) -> bool {
+        matches!(
+            self,
+            NetworkInterfaceKind::WireGuard | NetworkInterfaceKind::Tunnel
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub kind: NetworkInterfaceKind,
+    /// link is up (for wired interfaces: a cable is connected)
+    pub up: bool,
+    pub mac: Option<String>,
+}
+
+/// the gateway of the default route. its MAC identifies the network even if it has no name (e.g. ethernet)
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct NetworkGateway {
+    pub interface: String,
+    pub ip: String,
+    pub mac: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
 pub struct NetworkInfo {
     pub wifi: Option<WifiInterface>,
+    #[serde(default)]
+    pub gateway: Option<NetworkGateway>,
+    /// physical and vpn interfaces (no loopback, bridges, containers)
+    #[serde(default)]
+    pub interfaces: Vec<NetworkInterface>,
 }
 
+impl NetworkInfo {
+    /// network-location values can be mapped to places (home, office, ...) with tag rules,
+    /// e.g. gateway:aa:bb:cc:dd:ee:ff for an office ethernet where there is no SSID
+    pub fn add_tags(&self, tags: &mut Tags) {
+        if let Some(wifi) = &self.wifi {
+            tags.add("connected-wifi", &wifi.ssid);
+            tags.add("network-location", format!("wifi:{}", wifi.ssid));
+        }
+        if let Some(gateway) = &self.gateway {
+            tags.add(
+                "network-location",
+                format!("gateway:{}", gateway.mac.as_ref().unwrap_or(&gateway.ip)),
+            );
+        }
+        for interface in self.interfaces.iter().filter(|i| i.up) {
+            match interface.kind {
+                NetworkInterfaceKind::Wired => tags.add("network-location", "wired"),
+                kind if kind.is_vpn() => {
+                    tags.add("network-location", format!("vpn:{}", interface.name))
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl X11WindowGeometry {
+    fn rect(&self) -> Rect {
+        Rect {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+impl X11EventData {
+    /// windows in stacking order. events captured before the stacking order was recorded have none
+    fn visibility(&self) -> Vec<WindowVisibility> {
+        let outputs: Vec<Output> = self
+            .monitors
+            .iter()
+            .map(|m| Output {
+                name: m.name.clone(),
+                rect: m.geometry.rect(),
+            })
+            .collect();
+        let windows: Vec<StackedWindow> = self
+            .stacking
+            .iter()
+            .filter_map(|id| self.windows.iter().find(|w| w.window_id == *id))
+            .filter(|w| w.is_on_screen(self.current_desktop_id))
+            .filter_map(|w| {
+                Some(StackedWindow {
+                    software: w.get_class()?.1,
+                    rect: w.geometry.rect(),
+                    fullscreen: w.has_state("_NET_WM_STATE_FULLSCREEN"),
+                    focused: w.window_id == self.focused_window,
+                })
+            })
+            .collect();
+        compute_visibility(&outputs, &windows)
+    }
+}
+
+/// _NET_WM_DESKTOP of windows shown on all desktops
+const ALL_DESKTOPS: u64 = 0xFFFFFFFF;
+
 impl X11WindowData {
+    fn property_values(&self, name: &str) -> &[J] {
+        self.window_properties
+            .get(name)
+            .and_then(|p| p.get("value"))
+            .and_then(|v| v.as_array())
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+    }
+    fn has_state(&self, state: &str) -> bool {
+        self.property_values("_NET_WM_STATE")
+            .iter()
+            .any(|s| s.as_str() == Some(state))
+    }
+    fn is_on_screen(&self, current_desktop: usize) -> bool {
+        let desktop = self.property_values("_NET_WM_DESKTOP").first();
+        self.viewable != Some(false)
+            && !self.has_state("_NET_WM_STATE_HIDDEN")
+            && desktop
+                .and_then(|d| d.as_u64())
+                .is_none_or(|d| d == current_desktop as u64 || d == ALL_DESKTOPS)
+    }
     fn get_title(&self) -> Option<String> {
         if let Some(J::String(title)) = &self.window_properties.get("_NET_WM_NAME") {
             Some(title.to_string())
@@ -116,13 +266,12 @@ impl ExtractInfo for X11EventData {
     fn extract_info(&self) -> Option<Tags> {
         let mut tags = Tags::new();
         let x = &self;
-        if super::super::pc_common::is_idle(Duration::from_millis(x.ms_since_user_input as u64)) {
+        if x.context.is_idle(x.ms_since_user_input) {
             return None;
         }
+        x.context.add_tags(&mut tags);
         x.os_info.to_partial_general_software(&mut tags);
-        if let Some(NetworkInfo { wifi: Some(wifi) }) = &x.network {
-            tags.add("connected-wifi", &wifi.ssid);
-        }
+        add_visibility_tags(&mut tags, &x.visibility());
         let window = x.windows.iter().find(|e| e.window_id == x.focused_window);
         match window {
             None => (),
@@ -57,6 +57,7 @@ impl Capturer for MacOSCapturer {
                     log::warn!("{}", e);
                     Duration::ZERO
                 }),
+            redactions: vec![],
         }))
     }
 }
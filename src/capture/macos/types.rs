@@ -28,6 +28,8 @@ pub struct MacOSEventData {
     pub duration_since_user_input: Duration,
     pub focused_window: Option<i32>,
     pub windows: Vec<MacOSWindow>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redactions: Vec<Redaction>,
 }
 
 impl ExtractInfo for MacOSEventData {
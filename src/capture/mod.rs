@@ -1,11 +1,17 @@
+pub mod command;
+pub mod context;
 pub mod linux;
 pub mod macos;
+pub mod pause;
 pub mod pc_common;
+pub mod power;
 pub mod process;
+pub mod redact;
+pub mod visibility;
 pub mod windows;
 use std::time::Duration;
 
-use futures::never::Never;
+use futures::{never::Never, stream::BoxStream, FutureExt, StreamExt};
 
 use crate::{capture::linux::wayland_types::WaylandCaptureArgs, prelude::*};
 
@@ -17,6 +23,12 @@ pub enum CaptureArgs {
     Wayland(WaylandCaptureArgs),
     Windows(WindowsCaptureArgs),
     MacOS(MacOSCaptureArgs),
+    /// Capture what is playing in media players via MPRIS (linux)
+    Mpris(MprisCaptureArgs),
+    /// Watch whether the session is locked via systemd-logind (linux)
+    Logind(LogindCaptureArgs),
+    /// Run a program each interval and store its json output
+    Command(CommandCaptureArgs),
     /// Capture window information using the default for the current system
     NativeDefault(NativeDefaultArgs),
 }
@@ -57,6 +69,21 @@ impl CapturerCreator for NativeDefaultArgs {
 pub struct CaptureConfig {
     pub interval: Duration,
     pub args: CaptureArgs,
+    /// private data that is removed before events are stored
+    #[serde(default)]
+    pub redact: RedactionConfig,
+    /// capture whenever the focused window or its title changes instead of only every interval.
+    /// the interval is still used as a heartbeat. ignored for capturers that can't detect changes
+    #[serde(default)]
+    pub event_driven: bool,
+    /// count keypresses, clicks, scroll steps and mouse movement (never which keys) and store them in window events.
+    /// the counts are shared, so only enable this for one capturer
+    #[serde(default)]
+    pub input_activity: bool,
+    /// store which applications use the microphone, camera or a screen cast in window events.
+    /// this runs pactl and pw-dump and reads the open files of all processes on every capture
+    #[serde(default)]
+    pub media_usage: bool,
 }
 
 #[enum_dispatch(CaptureArgs)]
@@ -67,39 +94,148 @@ pub trait CapturerCreator {
 #[async_trait]
 pub trait Capturer: Send {
     async fn capture(&mut self) -> anyhow::Result<EventData>;
+
+    /// a stream that yields whenever the captured data probably changed (e.g. focus or title change).
+    /// capturers that can't detect changes return None and are only polled
+    fn changes(&mut self) -> anyhow::Result<Option<BoxStream<'static, ()>>> {
+        Ok(None)
+    }
+}
+
+/// after a change, wait this long for more changes (e.g. a title that changes while typing) before capturing
+const CHANGE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+async fn capture_once(
+    c: &mut Box<dyn Capturer>,
+    config: &CaptureConfig,
+    hash_key: &[u8],
+) -> anyhow::Result<EventData> {
+    let pause_state = pause::pause_state();
+    let mut data = if pause_state.paused {
+        EventData::paused_v1(PausedEntry {
+            reason: pause_state.reason,
+            until: pause_state.until,
+        })
+    } else {
+        c.capture().await?
+    };
+    context::merge_capture_context(&mut data, config.media_usage).await;
+    redact::redact_event(&mut data, &config.redact, hash_key);
+    Ok(data)
+}
+
+async fn insert_captured(
+    db: &DatyBasy,
+    idgen: &crate::libxid::Generator,
+    timestamp: DateTime<Utc>,
+    duration_ms: i64,
+    data: EventData,
+) -> anyhow::Result<()> {
+    let act = CreateNewDbEvent {
+        id: idgen.new_id().unwrap().encode(),
+        timestamp,
+        duration_ms,
+        data,
+    };
+    let ins: NewDbEvent = act.try_into()?;
+
+    db.insert_events_if_needed(vec![ins])
+        .await
+        .context("Could not insert captured event")?;
+    Ok(())
 }
 
 pub async fn capture_loop(db: DatyBasy, config: CaptureConfig) -> anyhow::Result<Never> {
-    let CaptureConfig { args, interval: _ } = config;
+    let CaptureConfig {
+        args,
+        interval: _,
+        redact: _,
+        event_driven,
+        input_activity,
+        media_usage: _,
+    } = &config;
     let mut c = args
         .create_capturer()
         .with_context(|| format!("Could not create capturer from {:?}", &args))?;
 
     let idgen = crate::libxid::new_generator();
+    let hash_key = db
+        .get_or_create_secret(redact::HASH_KEY_SECRET)
+        .await
+        .context("Could not get the key of redacted title hashes")?;
+
+    if *input_activity {
+        if let Err(e) = linux::input_types::watch_input() {
+            log::warn!("Could not count input activity: {:?}", e);
+        }
+    }
+
+    let mut changes = if *event_driven {
+        match c.changes() {
+            Ok(Some(changes)) => Some(changes),
+            Ok(None) => {
+                log::warn!("{:?} can't detect changes, only polling", args);
+                None
+            }
+            Err(e) => {
+                log::warn!("Could not watch for changes, only polling: {:?}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // the last capture is stored once the next one happens, since only then its duration
+    // (in event driven mode) and the input activity during it are known
+    let mut pending: Option<(DateTime<Utc>, EventData)> = None;
 
     let mut interval = tokio::time::interval(config.interval);
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
     loop {
-        log::info!("sleeping {}s", config.interval.as_secs());
-        interval.tick().await;
-
-        match c.capture().await {
-            Ok(data) => {
-                let act = CreateNewDbEvent {
-                    id: idgen.new_id().unwrap().encode(),
-                    timestamp: Utc::now(),
-                    duration_ms: config.interval.as_millis() as i64,
-                    data,
+        match &mut changes {
+            None => {
+                log::info!("sleeping {}s", config.interval.as_secs());
+                interval.tick().await;
+            }
+            Some(stream) => {
+                let change = tokio::select! {
+                    _ = interval.tick() => Some(false),
+                    change = stream.next() => change.map(|_| true),
                 };
-                let ins: NewDbEvent = act.try_into()?;
-
-                db.insert_events_if_needed(vec![ins])
-                    .await
-                    .context("Could not insert captured event")?;
+                match change {
+                    Some(true) => {
+                        tokio::time::sleep(CHANGE_DEBOUNCE).await;
+                        while let Some(Some(())) = stream.next().now_or_never() {}
+                        // the next poll is one interval after this capture
+                        interval.reset();
+                    }
+                    Some(false) => {}
+                    None => {
+                        log::warn!("change stream of {:?} ended, only polling from now on", args);
+                        changes = None;
+                    }
+                }
             }
+        }
+
+        let now = Utc::now();
+        let data = match capture_once(&mut c, &config, &hash_key).await {
+            Ok(data) => data,
             Err(e) => {
                 log::error!("Could not capture event: {}", e);
+                continue;
+            }
+        };
+        if let Some((start, mut data)) = pending.replace((now, data)) {
+            if *input_activity {
+                context::merge_input_activity(&mut data);
             }
+            // when polling, and in event driven mode after a suspend or failed captures,
+            // the previous capture can be longer ago, but it is only known to be valid for one interval
+            let duration_ms = (now - start)
+                .num_milliseconds()
+                .min(config.interval.as_millis() as i64);
+            insert_captured(&db, &idgen, start, duration_ms, data).await?;
         }
     }
 }
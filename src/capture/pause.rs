@@ -0,0 +1,125 @@
+// pausing capture, e.g. while someone else uses the computer.
+// while paused, the capture loops store marker events instead of the captured data, so the paused time is visible in the timeline.
+// the pause state is only kept in memory: restarting timetrackrs (or a crash) resumes capturing
+use std::sync::RwLock;
+
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, Default, PartialEq)]
+pub struct PauseState {
+    pub paused: bool,
+    pub since: Option<DateTime<Utc>>,
+    /// capture resumes automatically at this time
+    pub until: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, Default)]
+pub struct PauseRequest {
+    /// automatically resume after this many seconds
+    pub duration_secs: Option<u64>,
+    pub reason: Option<String>,
+}
+
+/// marker event written by the capture loops while paused
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct PausedEntry {
+    pub reason: Option<String>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl ExtractInfo for PausedEntry {
+    fn extract_info(&self) -> Option<Tags> {
+        let mut tags = Tags::new();
+        tags.add("timetrackrs-paused", "true");
+        if let Some(reason) = &self.reason {
+            tags.add("timetrackrs-paused-reason", reason);
+        }
+        Some(tags)
+    }
+
+    /// paused time is not tracked time
+    fn is_background(&self) -> bool {
+        true
+    }
+}
+
+lazy_static! {
+    static ref PAUSE_STATE: RwLock<PauseState> = RwLock::new(PauseState::default());
+}
+
+/// fails if the duration is too long to be represented
+pub fn pause(req: PauseRequest) -> anyhow::Result<PauseState> {
+    let now = Utc::now();
+    let until = req
+        .duration_secs
+        .map(|secs| {
+            i64::try_from(secs)
+                .ok()
+                .and_then(chrono::Duration::try_seconds)
+                .and_then(|d| now.checked_add_signed(d))
+                .with_context(|| format!("pause duration of {secs}s is too long"))
+        })
+        .transpose()?;
+    let state = PauseState {
+        paused: true,
+        since: Some(now),
+        until,
+        reason: req.reason,
+    };
+    log::info!("pausing capture: {:?}", state);
+    *PAUSE_STATE.write().unwrap() = state.clone();
+    Ok(state)
+}
+
+pub fn resume() -> PauseState {
+    log::info!("resuming capture");
+    let mut state = PAUSE_STATE.write().unwrap();
+    *state = PauseState::default();
+    state.clone()
+}
+
+/// the current state, resuming first if the pause has timed out
+pub fn pause_state() -> PauseState {
+    // checked and reset under the same lock, so a pause() in between is not undone
+    let mut state = PAUSE_STATE.write().unwrap();
+    if state.paused && state.until.is_some_and(|until| until <= Utc::now()) {
+        log::info!("pause timed out, resuming capture");
+        *state = PauseState::default();
+    }
+    state.clone()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pause_timeout() -> anyhow::Result<()> {
+        let state = pause(PauseRequest {
+            duration_secs: Some(3600),
+            reason: Some("guest".to_string()),
+        })?;
+        assert!(state.paused);
+        assert_eq!(pause_state(), state);
+
+        pause(PauseRequest {
+            duration_secs: Some(0),
+            reason: None,
+        })?;
+        assert!(!pause_state().paused);
+
+        for secs in [u64::MAX, i64::MAX as u64] {
+            assert!(pause(PauseRequest {
+                duration_secs: Some(secs),
+                reason: None,
+            })
+            .is_err());
+        }
+
+        pause(PauseRequest::default())?;
+        assert!(pause_state().paused);
+        assert!(!resume().paused);
+        Ok(())
+    }
+}
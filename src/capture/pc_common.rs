@@ -63,6 +63,7 @@ pub fn match_software(
         tags.add("software-window-class", format!("{}.{}", cls.0, cls.1));
     }
     if let Some(cwd) = cwd {
+        tags.add("software-cwd", cwd);
         if let Some(cmdline) = cmdline {
             if let Ok(path) = match_cmdline_to_filepath(cwd, cmdline) {
                 tags.add("software-opened-file", path);
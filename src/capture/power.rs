@@ -0,0 +1,171 @@
+// battery charge, charging state, AC power and power profile, stored in every window event.
+// batteries are read with the battery crate on all platforms, AC adapters and the platform profile from /sys on linux
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, Default, PartialEq)]
+pub struct PowerState {
+    /// combined charge of all batteries in percent. None without a battery
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery_percent: Option<f32>,
+    /// charging, discharging, full, empty or unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery_state: Option<String>,
+    /// whether an AC adapter is plugged in. None if unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ac_online: Option<bool>,
+    /// e.g. low-power, balanced or performance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power_profile: Option<String>,
+}
+
+impl PowerState {
+    /// "ac" or "battery". without an AC adapter, a battery that is not discharging is assumed to be plugged in
+    pub fn power_source(&self) -> Option<&'static str> {
+        let on_ac = match (self.ac_online, self.battery_state.as_deref()) {
+            (Some(online), _) => online,
+            (None, Some("discharging" | "empty")) => false,
+            (None, Some("charging" | "full")) => true,
+            // no battery, so it must be plugged in
+            (None, None) => true,
+            (None, Some(_)) => return None,
+        };
+        Some(if on_ac { "ac" } else { "battery" })
+    }
+
+    pub fn add_tags(&self, tags: &mut Tags) {
+        if let Some(source) = self.power_source() {
+            tags.add("device-power-source", source);
+        }
+        if let Some(percent) = self.battery_percent {
+            // in steps of 10, so the values don't change every event
+            tags.add(
+                "device-battery-level",
+                ((percent / 10.0).round() as i32 * 10).to_string(),
+            );
+        }
+        if let Some(state) = &self.battery_state {
+            tags.add("device-battery-state", state);
+        }
+        if let Some(profile) = &self.power_profile {
+            tags.add("device-power-profile", profile);
+        }
+    }
+}
+
+fn battery_state() -> anyhow::Result<(Option<f32>, Option<String>)> {
+    use battery::units::energy::watt_hour;
+    let mut energy = 0.0;
+    let mut energy_full = 0.0;
+    let mut states = Vec::new();
+    for battery in battery::Manager::new()?.batteries()? {
+        let battery = battery?;
+        energy += battery.energy().get::<watt_hour>();
+        energy_full += battery.energy_full().get::<watt_hour>();
+        states.push(battery.state());
+    }
+    if states.is_empty() || energy_full <= 0.0 {
+        return Ok((None, None));
+    }
+    // with multiple batteries, one may be full while the other is being used
+    let state = [
+        battery::State::Discharging,
+        battery::State::Charging,
+        battery::State::Empty,
+        battery::State::Full,
+    ]
+    .into_iter()
+    .find(|s| states.contains(s))
+    .unwrap_or(battery::State::Unknown);
+    Ok((
+        Some((energy / energy_full * 100.0).min(100.0)),
+        Some(state.to_string()),
+    ))
+}
+
+/// whether a mains or usb power supply is online. None if the system has neither, as many desktops
+#[cfg(target_os = "linux")]
+fn ac_online() -> Option<bool> {
+    let mut found = false;
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()? {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let read =
+            |name: &str| std::fs::read_to_string(entry.path().join(name)).unwrap_or_default();
+        if !matches!(read("type").trim(), "Mains" | "USB") {
+            continue;
+        }
+        found = true;
+        if read("online").trim() == "1" {
+            return Some(true);
+        }
+    }
+    found.then_some(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn ac_online() -> Option<bool> {
+    None
+}
+
+/// the ACPI platform profile, which power-profiles-daemon and tuned set
+#[cfg(target_os = "linux")]
+fn power_profile() -> Option<String> {
+    let profile = std::fs::read_to_string("/sys/firmware/acpi/platform_profile").ok()?;
+    Some(profile.trim().to_string()).filter(|p| !p.is_empty())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn power_profile() -> Option<String> {
+    None
+}
+
+pub fn get_power_state() -> PowerState {
+    let (battery_percent, battery_state) = battery_state()
+        .map_err(|e| log::debug!("could not get battery state: {:?}", e))
+        .unwrap_or_default();
+    PowerState {
+        battery_percent,
+        battery_state,
+        ac_online: ac_online(),
+        power_profile: power_profile(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_power_source() {
+        let unplugged = PowerState {
+            battery_percent: Some(64.6),
+            battery_state: Some("discharging".to_string()),
+            ac_online: None,
+            power_profile: Some("low-power".to_string()),
+        };
+        let mut tags = Tags::new();
+        unplugged.add_tags(&mut tags);
+        assert!(tags.has_value("device-power-source", "battery"));
+        assert!(tags.has_value("device-battery-level", "60"));
+        assert!(tags.has_value("device-power-profile", "low-power"));
+
+        let desktop = PowerState {
+            ac_online: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(desktop.power_source(), Some("ac"));
+        let full = PowerState {
+            battery_percent: Some(100.0),
+            battery_state: Some("full".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(full.power_source(), Some("ac"));
+        let unknown = PowerState {
+            battery_percent: Some(80.0),
+            battery_state: Some("unknown".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(unknown.power_source(), None);
+    }
+}
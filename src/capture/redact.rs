@@ -0,0 +1,656 @@
+// remove private data from captured events before they are stored.
+// every change is recorded in the redactions list of the event, so missing data can be told apart from data that was never there
+use std::sync::Arc;
+
+use crate::prelude::*;
+use hmac::{Hmac, Mac};
+use regex::Regex;
+
+/// name of the key of the title hashes in the secrets table of the config db
+pub const HASH_KEY_SECRET: &str = "redaction-hash-key";
+
+/// window properties of X11 windows that contain the title or a variant of it
+const X11_TITLE_PROPERTIES: [&str; 6] = [
+    "_NET_WM_NAME",
+    "WM_NAME",
+    "_NET_WM_VISIBLE_NAME",
+    "_NET_WM_ICON_NAME",
+    "WM_ICON_NAME",
+    "_NET_WM_VISIBLE_ICON_NAME",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TypeScriptify)]
+pub enum TitleRedactionMethod {
+    /// replace the title with a hash, so the same title can still be grouped.
+    /// the hash is keyed with a random secret of this installation, so titles can't be guessed from it
+    /// without access to the database
+    #[default]
+    Hash,
+    Blank,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// only keep the focused window
+    #[serde(default)]
+    pub drop_unfocused_windows: bool,
+    /// redact the titles of windows whose title matches any of these
+    #[serde(default, with = "serde_regex")]
+    pub title_patterns: Vec<Regex>,
+    /// redact the titles of windows of these classes / app ids
+    #[serde(default)]
+    pub window_classes: Vec<String>,
+    #[serde(default)]
+    pub title_method: TitleRedactionMethod,
+    /// remove all command line arguments, keeping only the program
+    #[serde(default)]
+    pub strip_cmd_args: bool,
+    /// wifi networks that are removed from the network info
+    #[serde(default)]
+    pub excluded_ssids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum Redaction {
+    UnfocusedWindowsDropped {
+        count: usize,
+    },
+    WindowTitle {
+        window: String,
+        method: TitleRedactionMethod,
+        /// the pattern or window class that matched
+        reason: String,
+    },
+    CmdArgsStripped {
+        count: usize,
+    },
+    WifiExcluded,
+}
+
+impl RedactionConfig {
+    pub fn is_empty(&self) -> bool {
+        !self.drop_unfocused_windows
+            && self.title_patterns.is_empty()
+            && self.window_classes.is_empty()
+            && !self.strip_cmd_args
+            && self.excluded_ssids.is_empty()
+    }
+}
+
+struct Redactor<'a> {
+    config: &'a RedactionConfig,
+    hash_key: &'a [u8],
+    redactions: Vec<Redaction>,
+    stripped_cmds: usize,
+}
+
+impl<'a> Redactor<'a> {
+    fn new(config: &'a RedactionConfig, hash_key: &'a [u8]) -> Redactor<'a> {
+        Redactor {
+            config,
+            hash_key,
+            redactions: vec![],
+            stripped_cmds: 0,
+        }
+    }
+
+    fn dropped_windows(&mut self, count: usize) {
+        if count > 0 {
+            self.redactions
+                .push(Redaction::UnfocusedWindowsDropped { count });
+        }
+    }
+
+    fn title_reason(&self, titles: &[&mut String], classes: &[&str]) -> Option<String> {
+        if let Some(c) = self
+            .config
+            .window_classes
+            .iter()
+            .find(|c| classes.contains(&c.as_str()))
+        {
+            return Some(format!("class {c}"));
+        }
+        self.config
+            .title_patterns
+            .iter()
+            .find(|p| titles.iter().any(|t| p.is_match(t)))
+            .map(|p| format!("pattern {}", p.as_str()))
+    }
+
+    fn hash(&self, title: &str) -> String {
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(self.hash_key)
+            .expect("hmac accepts keys of any length");
+        mac.update(title.as_bytes());
+        format!(
+            "redacted:{}",
+            hex::encode(&mac.finalize().into_bytes()[..16])
+        )
+    }
+
+    fn title(&mut self, window: impl ToString, title: &mut String, classes: &[&str]) {
+        self.titles(window, vec![title], classes);
+    }
+
+    /// all titles of a window (e.g. the title and the icon title) are redacted if any of them matches.
+    /// classes are matched against each of the given names (e.g. both parts of WM_CLASS)
+    fn titles(&mut self, window: impl ToString, mut titles: Vec<&mut String>, classes: &[&str]) {
+        titles.retain(|t| !t.is_empty());
+        if titles.is_empty() {
+            return;
+        }
+        let Some(reason) = self.title_reason(&titles, classes) else {
+            return;
+        };
+        let method = self.config.title_method;
+        for title in titles {
+            *title = match method {
+                TitleRedactionMethod::Hash => self.hash(title),
+                TitleRedactionMethod::Blank => "".to_string(),
+            };
+        }
+        self.redactions.push(Redaction::WindowTitle {
+            window: window.to_string(),
+            method,
+            reason,
+        });
+    }
+
+    fn cmd(&mut self, cmd: &mut Vec<String>) {
+        if self.config.strip_cmd_args && cmd.len() > 1 {
+            cmd.truncate(1);
+            self.stripped_cmds += 1;
+        }
+    }
+
+    /// returns true if the wifi should be removed
+    fn wifi(&mut self, ssid: &str) -> bool {
+        let excluded = self.config.excluded_ssids.iter().any(|s| s == ssid);
+        if excluded {
+            self.redactions.push(Redaction::WifiExcluded);
+        }
+        excluded
+    }
+
+    fn network(&mut self, network: &mut Option<NetworkInfo>) {
+        if let Some(NetworkInfo { wifi, gateway, .. }) = network {
+            if wifi.as_ref().is_some_and(|w| self.wifi(&w.ssid)) {
+                // the gateway would identify the same network
+                if gateway.as_ref().map(|g| &g.interface) == wifi.as_ref().map(|w| &w.name) {
+                    *gateway = None;
+                }
+                *wifi = None;
+            }
+        }
+    }
+
+    fn terminal(&mut self, terminal: &mut Option<TerminalContext>) {
+        if let Some(terminal) = terminal {
+            for p in &mut terminal.foreground {
+                self.cmd(&mut p.cmd);
+            }
+        }
+    }
+
+    fn context(&mut self, context: &mut CaptureContext) {
+        self.network(&mut context.network);
+        self.terminal(&mut context.terminal);
+    }
+
+    fn finish(mut self) -> Vec<Redaction> {
+        if self.stripped_cmds > 0 {
+            self.redactions.push(Redaction::CmdArgsStripped {
+                count: self.stripped_cmds,
+            });
+        }
+        self.redactions
+    }
+}
+
+fn redact_x11(r: &mut Redactor, d: &mut X11EventData) {
+    if r.config.drop_unfocused_windows {
+        let before = d.windows.len();
+        d.windows.retain(|w| w.window_id == d.focused_window);
+        r.dropped_windows(before - d.windows.len());
+    }
+    for w in &mut d.windows {
+        let classes = match w.window_properties.get("WM_CLASS") {
+            Some(J::String(cls)) => split_zero(cls),
+            _ => vec![],
+        };
+        let classes: Vec<&str> = classes.iter().map(|c| c.as_str()).collect();
+        let titles = w
+            .window_properties
+            .iter_mut()
+            .filter(|(prop, _)| X11_TITLE_PROPERTIES.contains(&prop.as_str()))
+            .filter_map(|(_, value)| match value {
+                J::String(title) => Some(title),
+                _ => None,
+            })
+            .collect();
+        r.titles(w.window_id, titles, &classes);
+        if let Some(p) = &mut w.process {
+            r.cmd(&mut p.cmd);
+        }
+    }
+    r.context(&mut d.context);
+}
+
+/// sway tree nodes: windows are the nodes with a pid
+fn redact_sway_node(r: &mut Redactor, node: &mut J, dropped: &mut usize) {
+    if node.get("pid").is_some() {
+        let focused = node.get("focused").and_then(|f| f.as_bool()) == Some(true);
+        let id = node.get("id").map(|i| i.to_string()).unwrap_or_default();
+        if !focused && r.config.drop_unfocused_windows {
+            if let Some(o) = node.as_object_mut() {
+                o.remove("name");
+                o.remove("window_properties");
+            }
+            *dropped += 1;
+        }
+        let mut classes: Vec<String> = vec![];
+        if let Some(app_id) = node.get("app_id").and_then(|a| a.as_str()) {
+            classes.push(app_id.to_string());
+        }
+        if let Some(props) = node.get("window_properties") {
+            for key in ["class", "instance"] {
+                if let Some(c) = props.get(key).and_then(|c| c.as_str()) {
+                    classes.push(c.to_string());
+                }
+            }
+        }
+        let classes: Vec<&str> = classes.iter().map(|c| c.as_str()).collect();
+        // XWayland windows also have their title in the window properties
+        let titles = node
+            .as_object_mut()
+            .into_iter()
+            .flat_map(|o| o.iter_mut())
+            .filter_map(|(key, value)| match (key.as_str(), value) {
+                ("name", J::String(title)) => Some(title),
+                ("window_properties", props) => match props.get_mut("title") {
+                    Some(J::String(title)) => Some(title),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        r.titles(id, titles, &classes);
+    }
+    for key in ["nodes", "floating_nodes"] {
+        if let Some(J::Array(children)) = node.get_mut(key) {
+            for child in children {
+                redact_sway_node(r, child, dropped);
+            }
+        }
+    }
+}
+
+fn find_pid(node: &J, focused: bool) -> Option<i32> {
+    if node.get("focused").and_then(|f| f.as_bool()) == Some(focused) {
+        if let Some(pid) = node.get("pid").and_then(|p| p.as_i64()) {
+            return Some(pid as i32);
+        }
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|k| node.get(k).and_then(|n| n.as_array()))
+        .flatten()
+        .find_map(|c| find_pid(c, focused))
+}
+
+fn redact_sway(r: &mut Redactor, d: &mut SwayEventData) {
+    let mut dropped = 0;
+    redact_sway_node(r, &mut d.window_tree, &mut dropped);
+    if r.config.drop_unfocused_windows {
+        let focused_pid = find_pid(&d.window_tree, true);
+        d.processes.retain(|p| Some(p.pid) == focused_pid);
+    }
+    r.dropped_windows(dropped);
+    for p in &mut d.processes {
+        r.cmd(&mut p.cmd);
+    }
+    r.context(&mut d.context);
+}
+
+fn redact_hyprland(r: &mut Redactor, d: &mut HyprlandEventData) {
+    if let J::Array(windows) = &mut d.window_tree {
+        let is_focused = |w: &J| w.get("focusHistoryID").and_then(|id| id.as_u64()) == Some(0);
+        if r.config.drop_unfocused_windows {
+            let before = windows.len();
+            windows.retain(is_focused);
+            r.dropped_windows(before - windows.len());
+            let pids: Vec<i64> = windows
+                .iter()
+                .filter_map(|w| w.get("pid").and_then(|p| p.as_i64()))
+                .collect();
+            d.processes.retain(|p| pids.contains(&(p.pid as i64)));
+        }
+        for w in windows.iter_mut() {
+            let id = w.get("address").map(|a| a.to_string()).unwrap_or_default();
+            let classes: Vec<String> = ["class", "initialClass"]
+                .iter()
+                .filter_map(|k| w.get(k).and_then(|c| c.as_str()).map(|c| c.to_string()))
+                .collect();
+            let classes: Vec<&str> = classes.iter().map(|c| c.as_str()).collect();
+            let titles = w
+                .as_object_mut()
+                .into_iter()
+                .flat_map(|o| o.iter_mut())
+                .filter_map(|(key, value)| match (key.as_str(), value) {
+                    ("title" | "initialTitle", J::String(title)) => Some(title),
+                    _ => None,
+                })
+                .collect();
+            r.titles(&id, titles, &classes);
+        }
+    }
+    for p in &mut d.processes {
+        r.cmd(&mut p.cmd);
+    }
+    r.context(&mut d.context);
+}
+
+fn redact_desktop_window(
+    r: &mut Redactor,
+    window: &mut Option<DesktopWindow>,
+    process: &mut Option<ProcessData>,
+    context: &mut CaptureContext,
+) {
+    if let Some(w) = window {
+        let classes: Vec<&str> = [w.wm_class.as_deref(), w.app_id.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        let id = w.pid.map(|p| p.to_string()).unwrap_or_default();
+        let mut title = w.title.clone();
+        r.title(id, &mut title, &classes);
+        w.title = title;
+    }
+    if let Some(p) = process {
+        r.cmd(&mut p.cmd);
+    }
+    r.context(context);
+}
+
+fn redact_wlr(r: &mut Redactor, d: &mut WlrToplevelEventData) {
+    if r.config.drop_unfocused_windows {
+        let before = d.toplevels.len();
+        d.toplevels.retain(|t| t.activated);
+        r.dropped_windows(before - d.toplevels.len());
+    }
+    for t in &mut d.toplevels {
+        let app_id = t.app_id.clone();
+        r.title(&app_id, &mut t.title, &[app_id.as_str()]);
+    }
+    r.context(&mut d.context);
+}
+
+fn redact_windows(r: &mut Redactor, d: &mut WindowsEventData) {
+    if r.config.drop_unfocused_windows {
+        let before = d.windows.len();
+        d.windows.retain(|w| Some(w.window_id) == d.focused_window);
+        r.dropped_windows(before - d.windows.len());
+    }
+    for w in &mut d.windows {
+        let class = w.wclass.clone();
+        r.title(w.window_id, &mut w.title, &[class.as_str()]);
+        if let Some(cmd) = &mut w.command_line {
+            r.cmd(cmd);
+        }
+    }
+    if d.wifi.as_deref().is_some_and(|ssid| r.wifi(ssid)) {
+        d.wifi = None;
+    }
+}
+
+fn redact_macos(r: &mut Redactor, d: &mut MacOSEventData) {
+    if r.config.drop_unfocused_windows {
+        let before = d.windows.len();
+        d.windows.retain(|w| Some(w.window_id) == d.focused_window);
+        r.dropped_windows(before - d.windows.len());
+    }
+    for w in &mut d.windows {
+        let classes: Vec<String> = w
+            .process
+            .iter()
+            .flat_map(|p| [Some(p.name.clone()), p.bundle.clone()])
+            .flatten()
+            .collect();
+        let classes: Vec<&str> = classes.iter().map(|c| c.as_str()).collect();
+        if let Some(title) = &mut w.title {
+            r.title(w.window_id, title, &classes);
+        }
+        if let Some(p) = &mut w.process {
+            let mut cmd = p.cmd.clone();
+            r.cmd(&mut cmd);
+            if cmd.len() != p.cmd.len() {
+                Arc::make_mut(p).cmd = cmd;
+            }
+        }
+    }
+}
+
+/// apply the redaction config to a captured event. events without window data are not changed.
+/// hash_key is the key of the title hashes (see HASH_KEY_SECRET)
+pub fn redact_event(data: &mut EventData, config: &RedactionConfig, hash_key: &[u8]) {
+    if config.is_empty() {
+        return;
+    }
+    let mut r = Redactor::new(config, hash_key);
+    let redactions = match data {
+        EventData::x11_v2(d) => {
+            redact_x11(&mut r, d);
+            &mut d.context.redactions
+        }
+        EventData::sway_v1(d) => {
+            redact_sway(&mut r, d);
+            &mut d.context.redactions
+        }
+        EventData::hyprland_v1(d) => {
+            redact_hyprland(&mut r, d);
+            &mut d.context.redactions
+        }
+        EventData::gnome_v1(d) => {
+            redact_desktop_window(
+                &mut r,
+                &mut d.focused_window,
+                &mut d.process,
+                &mut d.context,
+            );
+            &mut d.context.redactions
+        }
+        EventData::kde_v1(d) => {
+            redact_desktop_window(
+                &mut r,
+                &mut d.focused_window,
+                &mut d.process,
+                &mut d.context,
+            );
+            &mut d.context.redactions
+        }
+        EventData::wlr_toplevel_v1(d) => {
+            redact_wlr(&mut r, d);
+            &mut d.context.redactions
+        }
+        EventData::windows_v1(d) => {
+            redact_windows(&mut r, d);
+            &mut d.redactions
+        }
+        EventData::macos_v1(d) => {
+            redact_macos(&mut r, d);
+            &mut d.redactions
+        }
+        _ => return,
+    };
+    redactions.extend(r.finish());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn window(id: u32, title: &str, class: &str, cmd: &[&str]) -> X11WindowData {
+        let mut window_properties = BTreeMap::new();
+        window_properties.insert("_NET_WM_NAME".to_string(), J::String(title.to_string()));
+        window_properties.insert(
+            "WM_CLASS".to_string(),
+            J::String(format!("{class}\0{class}\0")),
+        );
+        X11WindowData {
+            window_id: id,
+            geometry: X11WindowGeometry {
+                x: 0,
+                y: 0,
+                width: 100,
+                height: 100,
+            },
+            process: Some(ProcessData {
+                pid: id as i32,
+                name: class.to_string(),
+                cmd: cmd.iter().map(|c| c.to_string()).collect(),
+                exe: None,
+                cwd: None,
+                memory_kB: 0,
+                parent: None,
+                status: "Run".to_string(),
+                start_time: Utc::now(),
+                cpu_usage: None,
+            }),
+            window_properties,
+            viewable: None,
+        }
+    }
+
+    fn x11_event(windows: Vec<X11WindowData>, network: Option<NetworkInfo>) -> EventData {
+        EventData::x11_v2(X11EventData {
+            os_info: Default::default(),
+            desktop_names: vec![],
+            current_desktop_id: 0,
+            focused_window: 1,
+            ms_since_user_input: 0,
+            ms_until_screensaver: 0,
+            screensaver_window: 0,
+            windows,
+            context: CaptureContext {
+                network,
+                ..Default::default()
+            },
+            stacking: vec![],
+            monitors: vec![],
+        })
+    }
+
+    #[test]
+    fn test_redact_x11() -> anyhow::Result<()> {
+        let config: RedactionConfig = serde_json::from_value(serde_json::json!({
+            "drop_unfocused_windows": true,
+            "title_patterns": ["(?i)bank"],
+            "strip_cmd_args": true,
+            "excluded_ssids": ["HomeWifi"],
+        }))?;
+        let network = NetworkInfo {
+            wifi: Some(WifiInterface {
+                ssid: "HomeWifi".to_string(),
+                mac: "".to_string(),
+                name: "wlan0".to_string(),
+                power: 0,
+                average_signal: 0,
+                bssid: "".to_string(),
+                connected_time: 0,
+            }),
+            gateway: Some(NetworkGateway {
+                interface: "wlan0".to_string(),
+                ip: "192.168.1.1".to_string(),
+                mac: Some("aa:bb:cc:dd:ee:ff".to_string()),
+            }),
+            interfaces: vec![],
+        };
+        let windows = vec![
+            window(1, "My Bank - Firefox", "firefox", &["firefox", "--private"]),
+            window(2, "Inbox: secret subject", "thunderbird", &["thunderbird"]),
+        ];
+        let mut data = x11_event(windows, Some(network));
+        redact_event(&mut data, &config, b"key");
+        let EventData::x11_v2(d) = &data else {
+            anyhow::bail!("wrong type");
+        };
+        assert_eq!(d.windows.len(), 1);
+        let title = d.windows[0].window_properties["_NET_WM_NAME"]
+            .as_str()
+            .unwrap();
+        assert!(title.starts_with("redacted:"));
+        assert_eq!(d.windows[0].process.as_ref().unwrap().cmd, vec!["firefox"]);
+        assert!(d.context.network.as_ref().unwrap().wifi.is_none());
+        assert!(d.context.network.as_ref().unwrap().gateway.is_none());
+        assert_eq!(
+            d.context.redactions,
+            vec![
+                Redaction::UnfocusedWindowsDropped { count: 1 },
+                Redaction::WindowTitle {
+                    window: "1".to_string(),
+                    method: TitleRedactionMethod::Hash,
+                    reason: "pattern (?i)bank".to_string()
+                },
+                Redaction::WifiExcluded,
+                Redaction::CmdArgsStripped { count: 1 },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_redact_x11_icon_name() -> anyhow::Result<()> {
+        let config: RedactionConfig = serde_json::from_value(serde_json::json!({
+            "title_patterns": ["(?i)bank"],
+        }))?;
+        let mut w = window(1, "Firefox", "firefox", &["firefox"]);
+        w.window_properties.insert(
+            "_NET_WM_ICON_NAME".to_string(),
+            J::String("My Bank".to_string()),
+        );
+        let mut data = x11_event(vec![w], None);
+        redact_event(&mut data, &config, b"key");
+        let EventData::x11_v2(d) = &data else {
+            anyhow::bail!("wrong type");
+        };
+        let props = &d.windows[0].window_properties;
+        for prop in ["_NET_WM_NAME", "_NET_WM_ICON_NAME"] {
+            assert!(props[prop].as_str().unwrap().starts_with("redacted:"));
+        }
+        assert_eq!(d.context.redactions.len(), 1);
+
+        // the hash depends on the key
+        let mut other = x11_event(vec![window(1, "My Bank", "firefox", &[])], None);
+        redact_event(&mut other, &config, b"other key");
+        let EventData::x11_v2(other) = &other else {
+            anyhow::bail!("wrong type");
+        };
+        assert_ne!(
+            props["_NET_WM_ICON_NAME"],
+            other.windows[0].window_properties["_NET_WM_NAME"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_redact_sway_xwayland_title() {
+        let config = RedactionConfig {
+            window_classes: vec!["Steam".to_string()],
+            title_method: TitleRedactionMethod::Blank,
+            ..Default::default()
+        };
+        let mut r = Redactor::new(&config, b"key");
+        let mut node = serde_json::json!({
+            "id": 5,
+            "pid": 100,
+            "focused": true,
+            "name": "Friends List",
+            "window_properties": {"class": "Steam", "title": "Friends List"},
+        });
+        redact_sway_node(&mut r, &mut node, &mut 0);
+        assert_eq!(node["name"], "");
+        assert_eq!(node["window_properties"]["title"], "");
+        assert_eq!(r.finish().len(), 1);
+    }
+}
@@ -0,0 +1,210 @@
+// which windows are on screen and how much of the screen they cover, so a video call or dashboard
+// on another monitor is tracked too, not only the focused window
+use crate::prelude::*;
+
+/// windows covering less of the screens than this are not tagged as visible
+const MIN_VISIBLE_WEIGHT: f64 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    fn right(&self) -> i32 {
+        self.x + self.width.max(0)
+    }
+    fn bottom(&self) -> i32 {
+        self.y + self.height.max(0)
+    }
+    fn area(&self) -> i64 {
+        self.width.max(0) as i64 * self.height.max(0) as i64
+    }
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+}
+
+pub struct Output {
+    pub name: String,
+    pub rect: Rect,
+}
+
+/// a mapped window on the current workspace of some output
+pub struct StackedWindow {
+    /// window class or app id
+    pub software: String,
+    pub rect: Rect,
+    pub fullscreen: bool,
+    pub focused: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct WindowVisibility {
+    pub software: String,
+    /// the output most of the visible part is on
+    pub output: Option<String>,
+    /// area that is on an output and not covered by windows above it
+    pub visible_area: i64,
+    /// visible area as a share of the area of all outputs
+    pub weight: f64,
+    pub fullscreen: bool,
+    pub focused: bool,
+}
+
+/// the visible part of each window. windows are given bottom to top
+pub fn compute_visibility(outputs: &[Output], windows: &[StackedWindow]) -> Vec<WindowVisibility> {
+    let total_area: i64 = outputs.iter().map(|o| o.rect.area()).sum();
+    if total_area == 0 {
+        return vec![];
+    }
+    // split the screen along all edges, then every cell is either fully covered by a window or not at all
+    let mut xs: Vec<i32> = Vec::new();
+    let mut ys: Vec<i32> = Vec::new();
+    for rect in outputs
+        .iter()
+        .map(|o| &o.rect)
+        .chain(windows.iter().map(|w| &w.rect))
+    {
+        xs.extend([rect.x, rect.right()]);
+        ys.extend([rect.y, rect.bottom()]);
+    }
+    xs.sort_unstable();
+    xs.dedup();
+    ys.sort_unstable();
+    ys.dedup();
+
+    // visible area of each window per output
+    let mut areas = vec![vec![0i64; outputs.len()]; windows.len()];
+    for x in xs.windows(2) {
+        for y in ys.windows(2) {
+            let Some(output) = outputs.iter().position(|o| o.rect.contains(x[0], y[0])) else {
+                continue;
+            };
+            if let Some(window) = windows.iter().rposition(|w| w.rect.contains(x[0], y[0])) {
+                areas[window][output] += (x[1] - x[0]) as i64 * (y[1] - y[0]) as i64;
+            }
+        }
+    }
+    windows
+        .iter()
+        .zip(areas)
+        .map(|(window, areas)| {
+            let visible_area: i64 = areas.iter().sum();
+            let output = areas
+                .iter()
+                .enumerate()
+                .filter(|(_, area)| **area > 0)
+                .max_by_key(|(_, area)| **area)
+                .map(|(i, _)| outputs[i].name.clone());
+            WindowVisibility {
+                software: window.software.clone(),
+                output,
+                visible_area,
+                weight: visible_area as f64 / total_area as f64,
+                fullscreen: window.fullscreen,
+                focused: window.focused,
+            }
+        })
+        .collect()
+}
+
+/// visible-software gets the share of the event's duration that the window covers of all screens
+pub fn add_visibility_tags(tags: &mut Tags, visibility: &[WindowVisibility]) {
+    let mut weights: Vec<(&str, f64)> = Vec::new();
+    for window in visibility {
+        // multiple windows of the same software add up
+        match weights.iter_mut().find(|(s, _)| *s == window.software) {
+            Some((_, weight)) => *weight += window.weight,
+            None => weights.push((&window.software, window.weight)),
+        }
+        if window.fullscreen && window.visible_area > 0 {
+            tags.add("fullscreen-software", &window.software);
+        }
+        if window.focused {
+            if let Some(output) = &window.output {
+                tags.add("focused-output", output);
+            }
+        }
+    }
+    for (software, weight) in weights {
+        if weight >= MIN_VISIBLE_WEIGHT {
+            tags.add_weighted("visible-software", software, weight.min(1.0));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: i32, height: i32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn window(software: &str, rect: Rect, focused: bool) -> StackedWindow {
+        StackedWindow {
+            software: software.to_string(),
+            rect,
+            fullscreen: false,
+            focused,
+        }
+    }
+
+    #[test]
+    fn test_visibility() {
+        let outputs = [
+            Output {
+                name: "DP-1".to_string(),
+                rect: rect(0, 0, 100, 100),
+            },
+            Output {
+                name: "DP-2".to_string(),
+                rect: rect(100, 0, 100, 100),
+            },
+        ];
+        let mut call = window("zoom", rect(100, 0, 100, 100), false);
+        call.fullscreen = true;
+        let windows = [
+            // fully covered by the editor
+            window("thunderbird", rect(10, 10, 50, 50), false),
+            window("code", rect(0, 0, 100, 100), true),
+            call,
+            // half off screen, on top of the call
+            window("pavucontrol", rect(150, 50, 100, 100), false),
+        ];
+        let visibility = compute_visibility(&outputs, &windows);
+        assert_eq!(visibility[0].visible_area, 0);
+        assert_eq!(visibility[1].visible_area, 10000);
+        assert_eq!(visibility[1].output.as_deref(), Some("DP-1"));
+        assert_eq!(visibility[2].visible_area, 10000 - 2500);
+        assert_eq!(visibility[2].output.as_deref(), Some("DP-2"));
+        assert_eq!(visibility[3].visible_area, 2500);
+        assert_eq!(visibility[3].weight, 0.125);
+
+        let mut tags = Tags::new();
+        add_visibility_tags(&mut tags, &visibility);
+        assert!(tags.has_value("visible-software", "code"));
+        assert!(tags.has_value("visible-software", "zoom"));
+        assert!(!tags.has_value("visible-software", "thunderbird"));
+        assert!(tags.has_value("fullscreen-software", "zoom"));
+        assert!(tags.has_value("focused-output", "DP-1"));
+        let weights = tags.take_weights();
+        assert_eq!(
+            weights.get(&("visible-software".to_string(), "code".to_string())),
+            Some(&0.5)
+        );
+        assert_eq!(
+            weights.get(&("visible-software".to_string(), "zoom".to_string())),
+            Some(&0.375)
+        );
+    }
+}
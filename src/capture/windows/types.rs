@@ -26,6 +26,8 @@ pub struct WindowsEventData {
     pub windows: Vec<WindowsWindow>,
     pub duration_since_user_input: std::time::Duration,
     pub wifi: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redactions: Vec<Redaction>,
 }
 
 #[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
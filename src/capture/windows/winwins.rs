@@ -56,6 +56,7 @@ impl Capturer for WindowsCapturer {
                     log::warn!("{}", e);
                     return Duration::ZERO;
                 }),
+            redactions: vec![],
         }))
     }
 }
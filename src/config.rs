@@ -6,6 +6,9 @@ use crate::{prelude::*, server::server::ServerConfig};
 pub struct TimetrackrsConfig {
     pub capturers: Vec<CaptureConfig>,
     pub server: Option<ServerConfig>,
+    /// imports that are run periodically, e.g. pulling data from another device
+    #[serde(default)]
+    pub imports: Vec<ImportConfig>,
 }
 
 pub fn default_config() -> TimetrackrsConfig {
@@ -13,9 +16,14 @@ pub fn default_config() -> TimetrackrsConfig {
         capturers: vec![CaptureConfig {
             args: CaptureArgs::NativeDefault(NativeDefaultArgs {}),
             interval: Duration::from_secs(30),
+            redact: Default::default(),
+            event_driven: false,
+            input_activity: false,
+            media_usage: false,
         }],
         server: Some(ServerConfig {
             listen: vec!["127.0.0.1:52714".to_string()],
         }),
+        imports: vec![],
     }
 }
@@ -19,10 +19,15 @@ use std::sync::atomic::Ordering::Relaxed;
 use tokio::sync::RwLock;
 
 pub async fn init_db_pool() -> anyhow::Result<DatyBasy> {
+    init_db_pool_in_dir(&crate::db::get_database_dir_location()).await
+}
+
+/// like init_db_pool, but with the databases in a different directory (e.g. a scratch database)
+pub async fn init_db_pool_in_dir(dir: &std::path::Path) -> anyhow::Result<DatyBasy> {
     static CALLED: AtomicBool = AtomicBool::new(false);
     CALLED.compare_exchange(false, true, Relaxed, Relaxed)
         .map_err(|_| anyhow::anyhow!("DB was already connected. Create new instances of DatyBasy by using .clone(), otherwise stuff like rules invalidation would not apply to all instances of DatyBasy"))?;
-    let db = crate::db::connect(None)
+    let db = crate::db::connect_dir(dir.to_string_lossy().to_string(), None)
         .await
         .context("Could not connect to db")?;
     Ok(DatyBasy {
@@ -80,7 +85,8 @@ pub async fn fetch_tag_rules(db: &SqlitePool) -> anyhow::Result<Vec<TagRule>> {
 struct EventWithTagMap {
     timestamp: Timestamptz,
     duration_ms: i64,
-    tags: Vec<(i64, i64)>,
+    /// (tag, value) and the share of the duration it gets
+    tags: Vec<((i64, i64), f64)>,
 }
 struct SingleExtractedChunkInfo {
     timechunk: TimeChunk,
@@ -104,8 +110,8 @@ impl ExtractedChunks {
             Timestamptz(event.timestamp.0 + chrono::Duration::milliseconds(event.duration_ms)),
         ) {
             let hm = self.data.entry(chunk).or_default();
-            for tag in &event.tags {
-                *hm.entry(*tag).or_insert(0) += duration_ms;
+            for (tag, weight) in &event.tags {
+                *hm.entry(*tag).or_insert(0) += (duration_ms as f64 * weight).round() as i64;
             }
         }
     }
@@ -175,6 +181,58 @@ impl DatyBasy {
         Ok(())
     }
 
+    pub async fn get_import_cursor(&self, source_id: &str) -> anyhow::Result<Option<ImportCursor>> {
+        let cursor = sqlx::query_scalar!(
+            "select cursor from config.import_cursors where source_id = ?",
+            source_id
+        )
+        .fetch_optional(&self.db)
+        .await?;
+
+        Ok(if let Some(cursor) = cursor {
+            Some(serde_json::from_str(&cursor).context("deserializing import cursor")?)
+        } else {
+            None
+        })
+    }
+
+    pub async fn set_import_cursor(
+        &self,
+        source_id: &str,
+        cursor: &ImportCursor,
+    ) -> anyhow::Result<()> {
+        let now = Timestamptz(Utc::now());
+        let cursor = serde_json::to_string(cursor).context("serializing import cursor")?;
+        sqlx::query!(
+            "insert or replace into config.import_cursors (source_id, cursor, updated_timestamp_unix_ms) values (?, ?, ?)",
+            source_id,
+            cursor,
+            now
+        )
+        .execute(&self.db)
+        .await
+        .context("insert into import_cursors db")?;
+
+        Ok(())
+    }
+
+    /// a random secret of this installation, created on first use
+    pub async fn get_or_create_secret(&self, name: &str) -> anyhow::Result<Vec<u8>> {
+        let new_secret = rand::random::<[u8; 32]>().to_vec();
+        sqlx::query!(
+            "insert into config.secrets (name, value) values (?, ?) on conflict (name) do nothing",
+            name,
+            new_secret
+        )
+        .execute(&self.db)
+        .await
+        .context("insert into secrets db")?;
+        let secret = sqlx::query_scalar!("select value from config.secrets where name = ?", name)
+            .fetch_one(&self.db)
+            .await?;
+        Ok(secret)
+    }
+
     pub async fn get_all_tag_rules<'a>(
         &'a self,
     ) -> impl core::ops::Deref<Target = Vec<TagRule>> + 'a {
@@ -461,25 +519,31 @@ impl DatyBasy {
         let now = Instant::now();
         *total_cache_get_dur.write().unwrap() += now.elapsed();
         let _now = Instant::now();
-        let (tags, _iterations) = get_tags(self, r, progress).await;
+        let (mut tags, _iterations) = get_tags(self, r, progress).await;
         //total_extract_dur += now.elapsed();
         //total_extract_iterations += iterations;
+        let weights = tags.take_weights();
+        let weights = &weights;
 
-        let tags: Vec<(i64, i64)> = futures::stream::iter(tags.into_iter())
+        let tags: Vec<((i64, i64), f64)> = futures::stream::iter(tags.into_iter())
             .flat_map(move |(tag, values)| {
                 let t = total_cache_get_dur.clone();
                 async move {
                     let now = Instant::now();
-                    let tag = self.tags_cache.get(&tag).await;
+                    let tag_id = self.tags_cache.get(&tag).await;
                     *t.write().unwrap() += now.elapsed();
 
                     futures::stream::iter(values).then(move |value| {
                         let t = t.clone();
+                        let weight = weights
+                            .get(&(tag.clone(), value.clone()))
+                            .copied()
+                            .unwrap_or(1.0);
                         async move {
                             let now = Instant::now();
                             let value = self.values_cache.get(&value).await;
                             *t.write().unwrap() += now.elapsed();
-                            (tag, value)
+                            ((tag_id, value), weight)
                         }
                     })
                 }
@@ -553,7 +617,11 @@ impl DatyBasy {
                 let ex: Tags = match r {
                     Ok(r) => {
                         let mut tags = r.extract_info()?;
-                        tags.add("timetrackrs-tracked", "true");
+                        if r.is_background() {
+                            tags.add("timetrackrs-background", "true");
+                        } else {
+                            tags.add("timetrackrs-tracked", "true");
+                        }
                         tags.add("timetrackrs-data-source", &a.data_type);
                         tags.add("timetrackrs-raw-id", &a.id);
                         tags
@@ -649,6 +717,32 @@ impl DatyBasy {
         Ok(())
     }
 
+    /// id, type and time of all raw events that intersect the given time range, without their data
+    pub async fn get_raw_event_spans_for_time_range(
+        &self,
+        from: Timestamptz,
+        to: Timestamptz,
+    ) -> anyhow::Result<Vec<RawEventSpan>> {
+        let absolute_lower_bound =
+            Timestamptz(from.0 - chrono::Duration::seconds(MAX_EVENT_LEN_SECS));
+        let raws = sqlx::query_as!(
+            RawEventSpan,
+            r#"select
+                id, timestamp_unix_ms as "timestamp_unix_ms: _", data_type, duration_ms
+            from raw_events.events where
+            timestamp_unix_ms + duration_ms >= ? and timestamp_unix_ms < ?
+                and timestamp_unix_ms >= ?
+            order by timestamp_unix_ms asc"#,
+            from,
+            to,
+            absolute_lower_bound // needed for perf
+        )
+        .fetch_all(&self.db)
+        .await
+        .context("fetching raw events")?;
+        Ok(raws)
+    }
+
     pub async fn insert_events_if_needed(&self, events: Vec<NewDbEvent>) -> anyhow::Result<u64> {
         let mut inserted: u64 = 0;
 
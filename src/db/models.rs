@@ -235,6 +235,14 @@ impl ToSql<Text, Sqlite> for DateUtc {
     }
 }*/
 
+/// a raw event without its data
+pub struct RawEventSpan {
+    pub id: String,
+    pub timestamp_unix_ms: Timestamptz,
+    pub data_type: String,
+    pub duration_ms: i64,
+}
+
 #[derive(sqlx::FromRow)]
 pub struct NewDbEvent {
     pub id: String,
@@ -244,6 +252,18 @@ pub struct NewDbEvent {
     pub data: String,
 }
 
+impl From<DbEvent> for NewDbEvent {
+    fn from(e: DbEvent) -> Self {
+        NewDbEvent {
+            id: e.id,
+            timestamp_unix_ms: e.timestamp_unix_ms,
+            data_type: e.data_type,
+            duration_ms: e.duration_ms,
+            data: e.data,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, sqlx::Type)]
 pub struct TagRuleGroup {
     pub global_id: String,
@@ -12,8 +12,22 @@ pub enum EventData {
     app_usage_v2(AppUsageEntry),
     journald_v1(JournaldEntry),
     sleep_as_android_v1(SleepAsAndroidEntry),
+    sleep_as_android_phase_v1(SleepAsAndroidPhase),
+    sleep_as_android_actigraphy_v1(SleepAsAndroidActigraphy),
     sway_v1(SwayEventData),
     hyprland_v1(HyprlandEventData),
+    gnome_v1(GnomeEventData),
+    kde_v1(KdeEventData),
+    wlr_toplevel_v1(WlrToplevelEventData),
+    manual_tracker_v1(ManualTrackerEntry),
+    generic_v1(GenericEntry),
+    health_v1(HealthEntry),
+    android_usage_stats_v1(AndroidUsageStatsEntry),
+    media_v1(MediaEntry),
+    mpris_v1(MprisEventData),
+    command_v1(CommandEventData),
+    paused_v1(PausedEntry),
+    logind_v1(LogindEventData),
 }
 
 // todo: maybe borrow more here
@@ -37,8 +51,28 @@ impl std::convert::TryFrom<CreateNewDbEvent> for NewDbEvent {
             EventData::app_usage_v2(d) => ("app_usage_v2", serde_json::to_string(d)?),
             EventData::journald_v1(d) => ("journald_v1", serde_json::to_string(d)?),
             EventData::sleep_as_android_v1(d) => ("sleep_as_android_v1", serde_json::to_string(d)?),
+            EventData::sleep_as_android_phase_v1(d) => {
+                ("sleep_as_android_phase_v1", serde_json::to_string(d)?)
+            }
+            EventData::sleep_as_android_actigraphy_v1(d) => {
+                ("sleep_as_android_actigraphy_v1", serde_json::to_string(d)?)
+            }
             EventData::sway_v1(d) => ("sway_v1", serde_json::to_string(d)?),
             EventData::hyprland_v1(d) => ("hyprland_v1", serde_json::to_string(d)?),
+            EventData::gnome_v1(d) => ("gnome_v1", serde_json::to_string(d)?),
+            EventData::kde_v1(d) => ("kde_v1", serde_json::to_string(d)?),
+            EventData::wlr_toplevel_v1(d) => ("wlr_toplevel_v1", serde_json::to_string(d)?),
+            EventData::manual_tracker_v1(d) => ("manual_tracker_v1", serde_json::to_string(d)?),
+            EventData::generic_v1(d) => ("generic_v1", serde_json::to_string(d)?),
+            EventData::health_v1(d) => ("health_v1", serde_json::to_string(d)?),
+            EventData::android_usage_stats_v1(d) => {
+                ("android_usage_stats_v1", serde_json::to_string(d)?)
+            }
+            EventData::media_v1(d) => ("media_v1", serde_json::to_string(d)?),
+            EventData::mpris_v1(d) => ("mpris_v1", serde_json::to_string(d)?),
+            EventData::command_v1(d) => ("command_v1", serde_json::to_string(d)?),
+            EventData::paused_v1(d) => ("paused_v1", serde_json::to_string(d)?),
+            EventData::logind_v1(d) => ("logind_v1", serde_json::to_string(d)?),
         };
         Ok(NewDbEvent {
             id: value.id,
@@ -58,8 +92,24 @@ pub fn deserialize_captured((data_type, data): (&str, &str)) -> anyhow::Result<E
         "app_usage_v2" => serde_json::from_str::<AppUsageEntry>(data)?.into(),
         "journald_v1" => serde_json::from_str::<JournaldEntry>(data)?.into(),
         "sleep_as_android_v1" => serde_json::from_str::<SleepAsAndroidEntry>(data)?.into(),
+        "sleep_as_android_phase_v1" => serde_json::from_str::<SleepAsAndroidPhase>(data)?.into(),
+        "sleep_as_android_actigraphy_v1" => {
+            serde_json::from_str::<SleepAsAndroidActigraphy>(data)?.into()
+        }
         "sway_v1" => serde_json::from_str::<SwayEventData>(data)?.into(),
         "hyprland_v1" => serde_json::from_str::<HyprlandEventData>(data)?.into(),
+        "gnome_v1" => serde_json::from_str::<GnomeEventData>(data)?.into(),
+        "kde_v1" => serde_json::from_str::<KdeEventData>(data)?.into(),
+        "wlr_toplevel_v1" => serde_json::from_str::<WlrToplevelEventData>(data)?.into(),
+        "manual_tracker_v1" => serde_json::from_str::<ManualTrackerEntry>(data)?.into(),
+        "generic_v1" => serde_json::from_str::<GenericEntry>(data)?.into(),
+        "health_v1" => serde_json::from_str::<HealthEntry>(data)?.into(),
+        "android_usage_stats_v1" => serde_json::from_str::<AndroidUsageStatsEntry>(data)?.into(),
+        "media_v1" => serde_json::from_str::<MediaEntry>(data)?.into(),
+        "mpris_v1" => serde_json::from_str::<MprisEventData>(data)?.into(),
+        "command_v1" => serde_json::from_str::<CommandEventData>(data)?.into(),
+        "paused_v1" => serde_json::from_str::<PausedEntry>(data)?.into(),
+        "logind_v1" => serde_json::from_str::<LogindEventData>(data)?.into(),
         _ => anyhow::bail!("unknown data type {}", data_type),
     })
 }
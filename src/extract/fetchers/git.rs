@@ -0,0 +1,294 @@
+use std::path::{Path, PathBuf};
+
+use super::ExternalFetcher;
+use crate::prelude::*;
+use regex::Regex;
+
+// finds the git repository containing an opened file or working directory by reading .git locally.
+// results are stored in the fetcher cache like the results of web fetchers, so extracting old events
+// doesn't read the filesystem again. the checked out branch changes too often to be cached, so it is
+// recorded in the capture context instead (see current_git_heads)
+
+/// looks up the repository of the paths in one tag
+pub struct GitRepoFetcher {
+    id: &'static str,
+    regexes: Vec<TagValueRegex>,
+    /// the tag values are files, so the lookup starts at their directory
+    is_file: bool,
+}
+
+impl GitRepoFetcher {
+    pub fn new(id: &'static str, tag: &str, is_file: bool) -> GitRepoFetcher {
+        GitRepoFetcher {
+            id,
+            regexes: vec![TagValueRegex {
+                tag: tag.to_string(),
+                regex: Regex::new(r#"^(?P<path>/.*)$"#).unwrap(),
+            }],
+            is_file,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct GitRepoInfo {
+    root: String,
+    remote_url: Option<String>,
+}
+
+/// the branch checked out in a repository when an event was captured
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, PartialEq)]
+pub struct GitHead {
+    pub root: String,
+    /// None if HEAD is detached
+    pub branch: Option<String>,
+}
+
+/// the url of origin, or of the first remote if there is no origin
+fn parse_remote_url(config: &str) -> Option<String> {
+    let mut section = "";
+    let mut urls = vec![];
+    for line in config.lines().map(|l| l.trim()) {
+        if line.starts_with('[') {
+            section = line;
+        } else if let Some(remote) = section
+            .strip_prefix("[remote \"")
+            .and_then(|s| s.strip_suffix("\"]"))
+        {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == "url" {
+                    urls.push((remote, value.trim().to_string()));
+                }
+            }
+        }
+    }
+    urls.iter()
+        .find(|(remote, _)| *remote == "origin")
+        .or_else(|| urls.first())
+        .map(|(_, url)| url.clone())
+}
+
+/// host/owner/repo, so the https and ssh urls of a repo give the same project
+/// e.g. git@github.com:phiresky/timetrackrs.git -> github.com/phiresky/timetrackrs
+fn normalize_remote_url(url: &str) -> String {
+    let url = url.trim_end_matches('/').trim_end_matches(".git");
+    let without_scheme = match url.split_once("://") {
+        Some((_, rest)) => rest.to_string(),
+        // scp-like syntax: user@host:path
+        None => url.replacen(':', "/", 1),
+    };
+    let without_user = match without_scheme.split_once('@') {
+        Some((user, rest)) if !user.contains('/') => rest,
+        _ => without_scheme.as_str(),
+    };
+    // drop the port
+    let (host, path) = without_user.split_once('/').unwrap_or((without_user, ""));
+    let host = host.split(':').next().unwrap_or(host);
+    format!("{}/{}", host, path.trim_start_matches('/')).to_lowercase()
+}
+
+/// the git directory of a worktree: .git is either the directory or a file pointing to it
+fn git_dir(root: &Path) -> Option<PathBuf> {
+    let dot_git = root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let content = std::fs::read_to_string(&dot_git).ok()?;
+    let dir = content.trim().strip_prefix("gitdir:")?.trim();
+    Some(root.join(dir))
+}
+
+/// the worktree root and git directory of the repository containing start
+fn find_git_dir(start: &Path) -> Option<(&Path, PathBuf)> {
+    start
+        .ancestors()
+        .find_map(|dir| git_dir(dir).map(|git_dir| (dir, git_dir)))
+}
+
+fn read_branch(git_dir: &Path) -> anyhow::Result<Option<String>> {
+    let head = std::fs::read_to_string(git_dir.join("HEAD"))
+        .with_context(|| format!("reading HEAD of {git_dir:?}"))?;
+    Ok(head
+        .trim()
+        .strip_prefix("ref: refs/heads/")
+        .map(|b| b.to_string()))
+}
+
+fn find_repo(start: &Path) -> Option<GitRepoInfo> {
+    let (root, git_dir) = find_git_dir(start)?;
+    // linked worktrees share the config of the main repository
+    let common_dir = match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => git_dir.join(common.trim()),
+        Err(_) => git_dir,
+    };
+    let remote_url = std::fs::read_to_string(common_dir.join("config"))
+        .ok()
+        .and_then(|config| parse_remote_url(&config));
+    Some(GitRepoInfo {
+        root: root.to_string_lossy().to_string(),
+        remote_url,
+    })
+}
+
+/// the branches checked out in the repositories of the opened files and working directories in the tags of a window event
+pub fn current_git_heads(tags: &Tags) -> Vec<GitHead> {
+    let files = tags
+        .get_all_values_of("software-opened-file")
+        .filter_map(|file| Path::new(file).parent());
+    let dirs = ["software-cwd", "terminal-cwd", "title-match-shell-cwd"]
+        .into_iter()
+        .flat_map(|tag| tags.get_all_values_of(tag))
+        .map(Path::new);
+    let mut heads: Vec<GitHead> = Vec::new();
+    for dir in files.chain(dirs).filter(|dir| dir.is_absolute()) {
+        let Some((root, git_dir)) = find_git_dir(dir) else {
+            continue;
+        };
+        let root = root.to_string_lossy().to_string();
+        if heads.iter().any(|head| head.root == root) {
+            continue;
+        }
+        match read_branch(&git_dir) {
+            Ok(branch) => heads.push(GitHead { root, branch }),
+            Err(e) => log::debug!("{:?}", e),
+        }
+    }
+    heads
+}
+
+#[async_trait]
+impl ExternalFetcher for GitRepoFetcher {
+    fn get_id(&self) -> &'static str {
+        self.id
+    }
+
+    fn get_regexes(&self) -> &[TagValueRegex] {
+        &self.regexes
+    }
+
+    fn get_possible_output_tags(&self) -> &[&str] {
+        &["git-repo-root", "git-remote-url", "project"]
+    }
+
+    fn get_cache_key(&self, found: &[regex::Captures], _tags: &Tags) -> Option<String> {
+        let path = Path::new(get_capture(found, "path")?);
+        let dir = if self.is_file { path.parent()? } else { path };
+        Some(dir.to_string_lossy().to_string())
+    }
+
+    async fn fetch_data(&self, cache_key: &str) -> Result<String, FetchError> {
+        log::debug!("looking for git repository of {}", cache_key);
+        // results are cached forever, so a directory that is not in a repository yet is looked at again later
+        let info = find_repo(Path::new(cache_key)).ok_or_else(|| {
+            FetchError::TemporaryFailure(
+                anyhow::anyhow!("{} is not in a git repository", cache_key),
+                Duration::from_secs(10 * 60),
+            )
+        })?;
+        serde_json::to_string(&info)
+            .context("serializing git repo info")
+            .map_err(temporary(1))
+    }
+
+    async fn process_data(
+        &self,
+        _tags: &Tags,
+        _cache_key: &str,
+        data: &str,
+    ) -> anyhow::Result<Vec<TagValue>> {
+        let mut tags: Vec<TagValue> = Vec::new();
+        // null was stored for directories outside of a repository by earlier versions
+        let Some(info): Option<GitRepoInfo> = serde_json::from_str(data).context("serde")? else {
+            return Ok(tags);
+        };
+        tags.add("git-repo-root", &info.root);
+        match info.remote_url {
+            Some(url) => {
+                tags.add("project", normalize_remote_url(&url));
+                tags.add("git-remote-url", url);
+            }
+            None => {
+                let name = Path::new(&info.root)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                tags.add("project", name);
+            }
+        }
+        Ok(tags)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize_remote_url() {
+        for url in [
+            "git@github.com:phiresky/timetrackrs.git",
+            "https://github.com/phiresky/timetrackrs",
+            "https://github.com/Phiresky/timetrackrs.git/",
+            "ssh://git@github.com:22/phiresky/timetrackrs.git",
+        ] {
+            assert_eq!(normalize_remote_url(url), "github.com/phiresky/timetrackrs");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_git_repo_fetcher() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("timetrackrs-git-test-{}", std::process::id()));
+        let git = dir.join("project/.git");
+        std::fs::create_dir_all(&git)?;
+        std::fs::create_dir_all(dir.join("project/src"))?;
+        std::fs::create_dir_all(dir.join("elsewhere"))?;
+        std::fs::write(git.join("HEAD"), "ref: refs/heads/feature/x\n")?;
+        std::fs::write(
+            git.join("config"),
+            "[core]\n\tbare = false\n[remote \"upstream\"]\n\turl = https://example.com/up/project.git\n[remote \"origin\"]\n\turl = git@github.com:me/project.git\n\tfetch = +refs/heads/*:refs/remotes/origin/*\n",
+        )?;
+
+        let fetcher = GitRepoFetcher::new("git-repo-test", "software-opened-file", true);
+        let file = dir.join("project/src/main.rs");
+        let mut tags = Tags::new();
+        tags.add("software-opened-file", file.to_string_lossy());
+        let caps = fetcher.get_regexes()[0]
+            .regex
+            .captures(tags.get_one_value_of("software-opened-file").unwrap())
+            .unwrap();
+        let key = fetcher.get_cache_key(&[caps], &tags).unwrap();
+        assert_eq!(Path::new(&key), dir.join("project/src"));
+
+        let data = fetcher
+            .fetch_data(&key)
+            .await
+            .map_err(|_| anyhow::anyhow!("fetch failed"))?;
+        let mut new_tags = Tags::new();
+        new_tags.extend(fetcher.process_data(&tags, &key, &data).await?);
+        let root = dir.join("project").to_string_lossy().to_string();
+        assert!(new_tags.has_value("git-repo-root", &root));
+        assert!(new_tags.get_one_value_of("git-branch").is_none());
+        assert!(new_tags.has_value("git-remote-url", "git@github.com:me/project.git"));
+        assert!(new_tags.has_value("project", "github.com/me/project"));
+
+        // the branch is read at capture time
+        let outside = dir.join("elsewhere").to_string_lossy().to_string();
+        tags.add("terminal-cwd", &outside);
+        assert_eq!(
+            current_git_heads(&tags),
+            vec![GitHead {
+                root,
+                branch: Some("feature/x".to_string())
+            }]
+        );
+
+        // not being in a repository is not cached
+        assert!(matches!(
+            fetcher.fetch_data(&outside).await,
+            Err(FetchError::TemporaryFailure(..))
+        ));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -6,10 +6,12 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use std::collections::HashMap;
 use url::Url;
+pub mod git;
 pub mod wikidata;
 pub mod youtube;
 use addr::parser::DomainName;
 
+pub use git::GitRepoFetcher;
 pub use wikidata::*;
 pub use youtube::YoutubeFetcher;
 
@@ -19,6 +21,10 @@ pub fn get_external_fetcher(id: &str) -> Option<&'static dyn ExternalFetcher> {
             Box::new(youtube::YoutubeFetcher) as Box<dyn ExternalFetcher>,
             Box::new(WikidataIdFetcher) as Box<dyn ExternalFetcher>,
             Box::new(WikidataCategoryFetcher) as Box<dyn ExternalFetcher>,
+            Box::new(GitRepoFetcher::new("git-repo-of-file-v1", "software-opened-file", true)) as Box<dyn ExternalFetcher>,
+            Box::new(GitRepoFetcher::new("git-repo-of-software-cwd-v1", "software-cwd", false)) as Box<dyn ExternalFetcher>,
+            Box::new(GitRepoFetcher::new("git-repo-of-terminal-cwd-v1", "terminal-cwd", false)) as Box<dyn ExternalFetcher>,
+            Box::new(GitRepoFetcher::new("git-repo-of-shell-cwd-v1", "title-match-shell-cwd", false)) as Box<dyn ExternalFetcher>,
         ].into_iter().map(|e| (e.get_id(), e)).collect();
     }
     EXT_FETCHERS.get(id).map(|e| e.as_ref())
@@ -11,4 +11,10 @@ pub mod tags;
 pub trait ExtractInfo {
     /// if returns None, event is discarded as (currently) uninteresting
     fn extract_info(&self) -> Option<Tags>;
+
+    /// background events (e.g. listening to music) happen in parallel to what the user is doing.
+    /// they are tagged timetrackrs-background instead of timetrackrs-tracked, so they don't count as tracked time
+    fn is_background(&self) -> bool {
+        false
+    }
 }
@@ -345,7 +345,7 @@ pub async fn apply_tag_rules(db: &DatyBasy, tags: &mut Tags, progress: Progress)
             {
                 Err(e) => log::warn!("{:?}", e),
                 Ok(None) => {}
-                Ok(Some((new_tags, _))) => tags.extend(new_tags),
+                Ok(Some((new_tags, matched_tags))) => tags.extend_derived(new_tags, &matched_tags),
             }
         }
         let new_length = tags.total_value_count();
@@ -422,7 +422,7 @@ pub async fn apply_tag_rules_get_reasons(
                             },
                         );
                     }
-                    tags.extend(new_tags);
+                    tags.extend_derived(new_tags, &matched_tags);
                 }
             }
         }
@@ -11,6 +11,9 @@ use crate::prelude::*;
 pub struct Tags {
     #[ts(ts_type = "{[key in string]?: string[]}")]
     map: HashMap<String, HashSet<String>>,
+    /// share (0-1) of the event's duration of the values added with add_weighted. not visible to rules or the api
+    #[serde(skip)]
+    weights: HashMap<(String, String), f64>,
 }
 #[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, Hash)]
 pub struct TagValue {
@@ -43,6 +46,7 @@ impl Tags {
     pub fn new() -> Tags {
         Tags {
             map: HashMap::new(),
+            weights: HashMap::new(),
         }
     }
     pub fn single(key: impl Into<String>, value: impl Into<String>) -> Tags {
@@ -50,8 +54,13 @@ impl Tags {
         tags.add(key, value);
         tags
     }
+    /// a value added without a weight gets the full duration, even if it was added with a weight before
     pub fn add(&mut self, key: impl Into<String>, value: impl Into<String>) {
-        self.map.entry(key.into()).or_default().insert(value.into());
+        let (key, value) = (key.into(), value.into());
+        if !self.weights.is_empty() {
+            self.weights.remove(&(key.clone(), value.clone()));
+        }
+        self.map.entry(key).or_default().insert(value);
     }
     pub fn has(&self, key: &str) -> bool {
         self.map.contains_key(key)
@@ -76,14 +85,56 @@ impl Tags {
             .map(|e| e.contains(value))
             .unwrap_or(false)
     }
+    /// add a tag value that only gets the given share (0-1) of the event's duration when extracted.
+    /// a value that is already there keeps the larger weight
+    pub fn add_weighted(&mut self, key: impl Into<String>, value: impl Into<String>, weight: f64) {
+        let (key, value) = (key.into(), value.into());
+        if weight >= 1.0 {
+            return self.add(key, value);
+        }
+        let existing = self
+            .has_value(&key, &value)
+            .then(|| self.weight(&key, &value));
+        let weight = existing.map_or(weight, |e| e.max(weight));
+        if weight < 1.0 {
+            self.weights.insert((key.clone(), value.clone()), weight);
+        }
+        self.map.entry(key).or_default().insert(value);
+    }
+    /// the share of the event's duration of a value, 1 for values added without a weight
+    pub fn weight(&self, key: &str, value: &str) -> f64 {
+        if self.weights.is_empty() {
+            return 1.0;
+        }
+        self.weights
+            .get(&(key.to_string(), value.to_string()))
+            .copied()
+            .unwrap_or(1.0)
+    }
+    /// remove the weights added with add_weighted. values without a weight get the full duration
+    pub fn take_weights(&mut self) -> HashMap<(String, String), f64> {
+        std::mem::take(&mut self.weights)
+    }
     pub fn extend(&mut self, e: Vec<TagValue>) {
         for tag in e {
             self.add(tag.tag, tag.value);
         }
     }
+    /// add the values a rule derived from the matched values. they get the smallest weight of the matched values,
+    /// so e.g. the category of a window that is half visible is also only counted half
+    pub fn extend_derived(&mut self, e: Vec<TagValue>, matched: &[TagValue]) {
+        let weight = matched
+            .iter()
+            .map(|m| self.weight(&m.tag, &m.value))
+            .fold(1.0, f64::min);
+        for tag in e {
+            self.add_weighted(tag.tag, tag.value, weight);
+        }
+    }
     pub fn iter(
         &self,
-    ) -> std::collections::hash_map::Iter<'_, std::string::String, HashSet<std::string::String>> {
+    ) -> std::collections::hash_map::Iter<'_, std::string::String, HashSet<std::string::String>>
+    {
         self.map.iter()
     }
     pub fn iter_values(&self) -> impl Iterator<Item = (&str, &str)> {
@@ -139,3 +190,38 @@ impl AddTag for Vec<TagValue> {
         self.push(TagValue::new(key, value));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_weights() {
+        let mut tags = Tags::new();
+        tags.add_weighted("visible-software", "code", 0.5);
+        tags.add("software-name", "firefox");
+        assert_eq!(tags.tag_count(), 2);
+        assert_eq!(tags.weight("visible-software", "code"), 0.5);
+
+        tags.extend_derived(
+            vec![TagValue::new("category", "Programming")],
+            &[TagValue::new("visible-software", "code")],
+        );
+        assert_eq!(tags.weight("category", "Programming"), 0.5);
+        tags.extend_derived(
+            vec![TagValue::new("category", "Browsing")],
+            &[TagValue::new("software-name", "firefox")],
+        );
+        assert_eq!(tags.weight("category", "Browsing"), 1.0);
+
+        // an unweighted source of the same value counts fully
+        tags.extend_derived(
+            vec![TagValue::new("category", "Programming")],
+            &[TagValue::new("software-name", "firefox")],
+        );
+        assert_eq!(tags.weight("category", "Programming"), 1.0);
+        assert!(tags
+            .take_weights()
+            .contains_key(&("visible-software".to_string(), "code".to_string())));
+    }
+}
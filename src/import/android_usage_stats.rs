@@ -0,0 +1,377 @@
+// import app usage from Android's UsageStats, either from the output of `adb shell dumpsys usagestats`
+// or from the XML files in /data/system/usagestats/<user>/daily/ (needs root, Android 8 and older.
+// newer versions store them as protobuf, use dumpsys there).
+// the events are paired from ACTIVITY_RESUMED to ACTIVITY_PAUSED (MOVE_TO_FOREGROUND / MOVE_TO_BACKGROUND on old versions).
+// UsageStats does not contain the app names, they can be given in a separate labels file
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use crate::prelude::*;
+
+use quick_xml::events::Event;
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct AndroidUsageStatsImportArgs {
+    /// output of `adb shell dumpsys usagestats`, a usagestats XML file or a directory of them
+    #[structopt(parse(from_os_str))]
+    filename: PathBuf,
+    device_name: String,
+    #[structopt(long, default_value = "Smartphone")]
+    device_type: SoftwareDeviceType,
+    /// time zone of the device, since dumpsys prints local times. defaults to the local time zone
+    #[structopt(long)]
+    #[serde(default)]
+    timezone: Option<String>,
+    /// file with lines of `<package id>=<app name>`, for the software-name tag. apps without a name use the package id
+    #[structopt(long, parse(from_os_str))]
+    #[serde(default)]
+    labels: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct AndroidUsageStatsEntry {
+    pub device_type: SoftwareDeviceType,
+    pub device_name: String,
+    pub package: String,
+    pub class: Option<String>,
+    /// the app name from the labels file
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+impl ExtractInfo for AndroidUsageStatsEntry {
+    fn extract_info(&self) -> Option<Tags> {
+        // same tags as AppUsageEntry. without a label the software-name is the package id,
+        // which does not match the names from AppUsageEntry
+        let mut tags = Tags::new();
+        tags.add("device-hostname", &self.device_name);
+        tags.add("device-type", format!("{}", self.device_type));
+        tags.add("android-package-id", &self.package);
+        tags.add(
+            "software-name",
+            self.label.as_ref().unwrap_or(&self.package),
+        );
+        tags.add("device-os-type", "Android");
+        Some(tags)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum UsageEventType {
+    Resumed,
+    Paused,
+    /// screen off, keyguard shown or shutdown: nothing is in the foreground any more
+    Inactive,
+}
+
+#[derive(Debug)]
+struct UsageEvent {
+    time: DateTime<Utc>,
+    event_type: UsageEventType,
+    package: Option<String>,
+    class: Option<String>,
+}
+
+impl UsageEventType {
+    fn from_name(name: &str) -> Option<UsageEventType> {
+        Some(match name {
+            "ACTIVITY_RESUMED" | "MOVE_TO_FOREGROUND" => UsageEventType::Resumed,
+            "ACTIVITY_PAUSED" | "MOVE_TO_BACKGROUND" | "ACTIVITY_STOPPED" => UsageEventType::Paused,
+            "SCREEN_NON_INTERACTIVE" | "KEYGUARD_SHOWN" | "DEVICE_SHUTDOWN" => {
+                UsageEventType::Inactive
+            }
+            _ => return None,
+        })
+    }
+    /// https://developer.android.com/reference/android/app/usage/UsageEvents.Event
+    fn from_code(code: &str) -> Option<UsageEventType> {
+        Some(match code {
+            "1" => UsageEventType::Resumed,
+            "2" | "23" => UsageEventType::Paused,
+            "16" | "17" | "26" => UsageEventType::Inactive,
+            _ => return None,
+        })
+    }
+}
+
+lazy_static! {
+    // time="2020-01-14 15:03:52" type=ACTIVITY_RESUMED package=com.android.chrome class=org.chromium.chrome.browser.ChromeTabbedActivity flags=0x0
+    static ref DUMPSYS_EVENT: regex::Regex = regex::Regex::new(
+        r#"^\s*time="(?P<time>[^"]+)" type=(?P<type>\S+)(?: package=(?P<package>\S+))?(?: class=(?P<class>\S+))?"#
+    )
+    .unwrap();
+}
+
+fn parse_dumpsys(
+    input: impl BufRead,
+    to_utc: impl Fn(NaiveDateTime) -> anyhow::Result<DateTime<Utc>>,
+) -> anyhow::Result<Vec<UsageEvent>> {
+    let mut events = vec![];
+    for line in input.lines() {
+        let line = line?;
+        let Some(cap) = DUMPSYS_EVENT.captures(&line) else {
+            continue;
+        };
+        let Some(event_type) = UsageEventType::from_name(&cap["type"]) else {
+            continue;
+        };
+        let time = NaiveDateTime::parse_from_str(&cap["time"], "%Y-%m-%d %H:%M:%S")
+            .with_context(|| format!("invalid time in '{line}'"))?;
+        events.push(UsageEvent {
+            time: to_utc(time)?,
+            event_type,
+            package: cap.name("package").map(|p| p.as_str().to_string()),
+            class: cap.name("class").map(|c| c.as_str().to_string()),
+        });
+    }
+    Ok(events)
+}
+
+/// the event times in the XML files are relative to the begin time in the file name
+fn parse_usagestats_xml(input: impl BufRead, begin_ms: i64) -> anyhow::Result<Vec<UsageEvent>> {
+    let mut reader = quick_xml::Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut events = vec![];
+    loop {
+        match reader
+            .read_event_into(&mut buf)
+            .context("parsing usagestats xml")?
+        {
+            Event::Start(e) | Event::Empty(e) if e.name().as_ref() == b"event" => {
+                let (mut time, mut event_type, mut package, mut class) = (None, None, None, None);
+                for attr in e.attributes() {
+                    let attr = attr?;
+                    let value = attr.unescape_value()?.into_owned();
+                    match attr.key.as_ref() {
+                        b"time" => time = value.parse::<i64>().ok(),
+                        b"type" => event_type = UsageEventType::from_code(&value),
+                        b"package" => package = Some(value),
+                        b"class" => class = Some(value),
+                        _ => {}
+                    }
+                }
+                if let (Some(time), Some(event_type)) = (time, event_type) {
+                    events.push(UsageEvent {
+                        time: util::unix_epoch_millis_to_date(begin_ms + time),
+                        event_type,
+                        package,
+                        class,
+                    });
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(events)
+}
+
+/// lines of `<package id>=<app name>`. empty lines and lines starting with # are skipped
+fn parse_labels(input: &str) -> anyhow::Result<HashMap<String, String>> {
+    let mut labels = HashMap::new();
+    for line in input.lines().map(|l| l.trim()) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (package, label) = line
+            .split_once('=')
+            .with_context(|| format!("expected <package id>=<app name>, got '{line}'"))?;
+        labels.insert(package.trim().to_string(), label.trim().to_string());
+    }
+    Ok(labels)
+}
+
+struct ForegroundApp {
+    package: String,
+    class: Option<String>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+}
+
+/// pair the resumed events with the following paused event of the same app.
+/// only one activity is in the foreground at a time, so a resume also ends the previous one
+fn pair_events(mut events: Vec<UsageEvent>) -> Vec<ForegroundApp> {
+    events.sort_by_key(|e| e.time);
+    let mut out = vec![];
+    let mut current: Option<ForegroundApp> = None;
+    for event in events {
+        let ends_current = match event.event_type {
+            UsageEventType::Resumed | UsageEventType::Inactive => true,
+            UsageEventType::Paused => current
+                .as_ref()
+                .is_some_and(|c| Some(&c.package) == event.package.as_ref()),
+        };
+        if ends_current {
+            if let Some(mut app) = current.take() {
+                if event.time > app.start {
+                    app.end = event.time;
+                    out.push(app);
+                }
+            }
+        }
+        if event.event_type == UsageEventType::Resumed {
+            if let Some(package) = event.package {
+                current = Some(ForegroundApp {
+                    package,
+                    class: event.class,
+                    start: event.time,
+                    end: event.time,
+                });
+            }
+        }
+    }
+    out
+}
+
+impl AndroidUsageStatsImportArgs {
+    fn read_events(&self) -> anyhow::Result<Vec<UsageEvent>> {
+        if self.filename.is_dir() {
+            let mut events = vec![];
+            for entry in std::fs::read_dir(&self.filename)? {
+                events.extend(self.read_file(&entry?.path())?);
+            }
+            Ok(events)
+        } else {
+            self.read_file(&self.filename)
+        }
+    }
+
+    fn read_file(&self, path: &Path) -> anyhow::Result<Vec<UsageEvent>> {
+        let mut input = std::io::BufReader::new(File::open(path)?);
+        let is_xml = input.fill_buf()?.trim_ascii_start().starts_with(b"<");
+        if !is_xml {
+            let tz: Option<chrono_tz::Tz> = match &self.timezone {
+                Some(tz) => Some(tz.parse().map_err(|e| anyhow::anyhow!("{e}"))?),
+                None => None,
+            };
+            return parse_dumpsys(input, |time| {
+                match tz {
+                    Some(tz) => tz
+                        .from_local_datetime(&time)
+                        .earliest()
+                        .map(|t| t.with_timezone(&Utc)),
+                    None => Local
+                        .from_local_datetime(&time)
+                        .earliest()
+                        .map(|t| t.with_timezone(&Utc)),
+                }
+                .with_context(|| format!("invalid local time {time}"))
+            });
+        }
+        // e.g. /data/system/usagestats/0/daily/1578873600000
+        let begin_ms: i64 = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse().ok())
+            .with_context(|| format!("usagestats file name {path:?} is not a timestamp"))?;
+        parse_usagestats_xml(input, begin_ms).with_context(|| format!("parsing {path:?}"))
+    }
+}
+
+#[async_trait]
+impl Importable for AndroidUsageStatsImportArgs {
+    fn source_path(&self) -> Option<PathBuf> {
+        Some(self.filename.clone())
+    }
+
+    async fn import(&self) -> ImportResult {
+        let labels = match &self.labels {
+            Some(path) => parse_labels(
+                &std::fs::read_to_string(path).with_context(|| format!("reading {path:?}"))?,
+            )?,
+            None => HashMap::new(),
+        };
+        let mut outs = vec![];
+        for app in pair_events(self.read_events()?) {
+            outs.push(
+                CreateNewDbEvent {
+                    id: format!(
+                        "android_usage_stats.{}.{}.{}",
+                        self.device_name,
+                        app.package,
+                        app.start.timestamp_millis()
+                    ),
+                    timestamp: app.start,
+                    duration_ms: (app.end - app.start).num_milliseconds(),
+                    data: EventData::android_usage_stats_v1(AndroidUsageStatsEntry {
+                        device_type: self.device_type.clone(),
+                        device_name: self.device_name.clone(),
+                        label: labels.get(&app.package).cloned(),
+                        package: app.package,
+                        class: app.class,
+                    }),
+                }
+                .try_into()?,
+            );
+        }
+        log::info!("got {} app usages", outs.len());
+        Ok(Box::pin(futures::stream::once(futures::future::ok(outs))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pair_usage_events() -> anyhow::Result<()> {
+        let dumpsys = r#"user=0
+  In-memory daily stats
+  timeRange="1/14/2020, 12:00 AM – 1/14/2020, 3:04 PM"
+  events
+    time="2020-01-14 15:00:00" type=SCREEN_INTERACTIVE package=android flags=0x0
+    time="2020-01-14 15:00:05" type=ACTIVITY_RESUMED package=com.android.chrome class=org.chromium.chrome.browser.ChromeTabbedActivity flags=0x0
+    time="2020-01-14 15:01:05" type=ACTIVITY_PAUSED package=com.android.chrome class=org.chromium.chrome.browser.ChromeTabbedActivity flags=0x0
+    time="2020-01-14 15:01:06" type=ACTIVITY_RESUMED package=org.telegram.messenger class=org.telegram.ui.LaunchActivity flags=0x0
+    time="2020-01-14 15:02:06" type=SCREEN_NON_INTERACTIVE package=android flags=0x0
+"#;
+        let events = parse_dumpsys(dumpsys.as_bytes(), |t| Ok(Utc.from_utc_datetime(&t)))?;
+        let xml = r#"<?xml version='1.0' encoding='utf-8' standalone='yes' ?>
+<usagestats version="1" endTime="86400000">
+    <event-log>
+        <event time="10000" package="org.telegram.messenger" class="org.telegram.ui.LaunchActivity" type="1" />
+        <event time="40000" package="org.telegram.messenger" class="org.telegram.ui.LaunchActivity" type="2" />
+    </event-log>
+</usagestats>"#;
+        let xml_events = parse_usagestats_xml(xml.as_bytes(), 1578873600000)?;
+        let paired: Vec<_> = pair_events(events.into_iter().chain(xml_events).collect())
+            .into_iter()
+            .map(|a| {
+                (
+                    a.package,
+                    a.start.timestamp(),
+                    (a.end - a.start).num_seconds(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            paired,
+            vec![
+                ("org.telegram.messenger".to_string(), 1578873610, 30),
+                ("com.android.chrome".to_string(), 1579014005, 60),
+                ("org.telegram.messenger".to_string(), 1579014066, 60),
+            ]
+        );
+
+        let labels = parse_labels("# app names\ncom.android.chrome = Chrome\n\n")?;
+        let mut entry = AndroidUsageStatsEntry {
+            device_type: SoftwareDeviceType::Smartphone,
+            device_name: "phone".to_string(),
+            package: "com.android.chrome".to_string(),
+            class: None,
+            label: labels.get("com.android.chrome").cloned(),
+        };
+        assert!(entry
+            .extract_info()
+            .unwrap()
+            .has_value("software-name", "Chrome"));
+        entry.label = None;
+        assert!(entry
+            .extract_info()
+            .unwrap()
+            .has_value("software-name", "com.android.chrome"));
+        Ok(())
+    }
+}
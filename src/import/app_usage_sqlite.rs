@@ -33,7 +33,7 @@ pub enum SoftwareDeviceType {
     Smartphone,
     Tablet,
 }
-#[derive(StructOpt)]
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
 pub struct AppUsageImportArgs {
     // ~/data/bck/TitaniumBackup/com.a0soft.gphone.uninstaller-20200114-030409.tar.bz2
     filename: String,
@@ -149,6 +149,10 @@ struct ActRow {
 
 #[async_trait]
 impl Importable for AppUsageImportArgs {
+    fn source_path(&self) -> Option<std::path::PathBuf> {
+        Some(std::path::PathBuf::from(&self.filename))
+    }
+
     async fn import(&self) -> ImportResult {
         let mut archive =
             zip::read::ZipArchive::new(File::open(&self.filename).context("opening AUM file")?)
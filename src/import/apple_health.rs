@@ -0,0 +1,215 @@
+// import the export.xml of an Apple Health export (Health app -> profile -> Export All Health Data)
+// the file is usually hundreds of megabytes, so it is parsed as a stream and the events are inserted in chunks
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::PathBuf;
+
+use crate::prelude::*;
+
+use quick_xml::events::{BytesStart, Event};
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct AppleHealthImportArgs {
+    /// path to the extracted export.xml
+    filename: PathBuf,
+}
+
+const CHUNK_SIZE: usize = 1000;
+
+fn attributes(e: &BytesStart) -> anyhow::Result<HashMap<String, String>> {
+    let mut map = HashMap::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        map.insert(
+            String::from_utf8_lossy(attr.key.as_ref()).into_owned(),
+            attr.unescape_value()?.into_owned(),
+        );
+    }
+    Ok(map)
+}
+
+fn parse_date(attrs: &HashMap<String, String>, name: &str) -> anyhow::Result<DateTime<Utc>> {
+    let date = attrs
+        .get(name)
+        .with_context(|| format!("missing attribute {name}"))?;
+    // e.g. 2021-01-01 10:00:00 +0100
+    Ok(DateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S %z")
+        .with_context(|| format!("invalid date {date}"))?
+        .with_timezone(&Utc))
+}
+
+/// HKCategoryValueSleepAnalysis* -> kind. InBed is skipped since it overlaps the asleep samples
+fn sleep_kind(value: &str) -> Option<HealthEventKind> {
+    let phase = match value.strip_prefix("HKCategoryValueSleepAnalysis")? {
+        "Asleep" | "AsleepUnspecified" => None,
+        "AsleepCore" => Some("light"),
+        "AsleepDeep" => Some("deep"),
+        "AsleepREM" => Some("rem"),
+        "Awake" => {
+            return Some(HealthEventKind::SleepPhase {
+                phase: "awake".to_string(),
+            })
+        }
+        _ => return None,
+    };
+    Some(HealthEventKind::Sleep {
+        phase: phase.map(|p| p.to_string()),
+    })
+}
+
+fn parse_element(e: &BytesStart) -> anyhow::Result<Option<NewDbEvent>> {
+    let (kind, attrs) = match e.name().as_ref() {
+        b"Record" => {
+            let attrs = attributes(e)?;
+            let value = attrs.get("value").map(|v| v.as_str()).unwrap_or_default();
+            let kind = match attrs.get("type").map(|t| t.as_str()) {
+                Some("HKQuantityTypeIdentifierStepCount") => HealthEventKind::Steps {
+                    count: value.parse::<f64>().unwrap_or(0.0) as i64,
+                },
+                Some("HKCategoryTypeIdentifierSleepAnalysis") => match sleep_kind(value) {
+                    Some(kind) => kind,
+                    None => return Ok(None),
+                },
+                _ => return Ok(None),
+            };
+            (kind, attrs)
+        }
+        b"Workout" => {
+            let attrs = attributes(e)?;
+            let activity = attrs
+                .get("workoutActivityType")
+                .map(|t| t.trim_start_matches("HKWorkoutActivityType"))
+                .unwrap_or("other");
+            (
+                HealthEventKind::Workout {
+                    activity: normalize_activity(activity),
+                },
+                attrs,
+            )
+        }
+        _ => return Ok(None),
+    };
+    let start = parse_date(&attrs, "startDate")?;
+    let end = parse_date(&attrs, "endDate")?;
+    let device = attrs.get("sourceName").cloned();
+    let kind_name = match &kind {
+        HealthEventKind::Workout { .. } => "workout",
+        HealthEventKind::Steps { .. } => "steps",
+        HealthEventKind::Sleep { .. } | HealthEventKind::SleepPhase { .. } => "sleep",
+    };
+    let id = format!(
+        "apple_health.{}.{}.{}",
+        kind_name,
+        device.as_deref().unwrap_or_default(),
+        start.timestamp_millis()
+    );
+    Ok(Some(health_event(
+        id,
+        HealthSource::AppleHealth,
+        device,
+        kind,
+        start,
+        end,
+    )?))
+}
+
+/// parse export.xml, calling emit with chunks of events
+pub fn parse_apple_health(
+    input: impl BufRead,
+    mut emit: impl FnMut(Vec<NewDbEvent>) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut reader = quick_xml::Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut chunk = Vec::new();
+    loop {
+        match reader
+            .read_event_into(&mut buf)
+            .context("parsing apple health xml")?
+        {
+            Event::Start(e) | Event::Empty(e) => {
+                if let Some(event) = parse_element(&e)? {
+                    chunk.push(event);
+                    if chunk.len() >= CHUNK_SIZE {
+                        emit(std::mem::take(&mut chunk))?;
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    if !chunk.is_empty() {
+        emit(chunk)?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl Importable for AppleHealthImportArgs {
+    fn source_path(&self) -> Option<PathBuf> {
+        Some(self.filename.clone())
+    }
+
+    async fn import(&self) -> ImportResult {
+        let file = File::open(&self.filename).context("opening apple health export")?;
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        tokio::task::spawn_blocking(move || {
+            let res = parse_apple_health(std::io::BufReader::new(file), |chunk| {
+                tx.blocking_send(Ok(chunk))
+                    .map_err(|_| anyhow::anyhow!("import was cancelled"))
+            });
+            if let Err(e) = res {
+                // receiver might be gone already, nothing to do then
+                let _ = tx.blocking_send(Err(e));
+            }
+        });
+        Ok(Box::pin(tokio_stream::wrappers::ReceiverStream::new(rx)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_apple_health() -> anyhow::Result<()> {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE HealthData [
+<!ELEMENT HealthData (ExportDate,Me,(Record|Workout)*)>
+]>
+<HealthData locale="en_DE">
+ <ExportDate value="2021-01-02 10:00:00 +0100"/>
+ <Record type="HKQuantityTypeIdentifierStepCount" sourceName="Phire&apos;s iPhone" unit="count" startDate="2021-01-01 09:50:00 +0100" endDate="2021-01-01 10:00:00 +0100" value="123"/>
+ <Record type="HKQuantityTypeIdentifierHeartRate" sourceName="Watch" unit="count/min" startDate="2021-01-01 09:50:00 +0100" endDate="2021-01-01 09:50:00 +0100" value="60"/>
+ <Record type="HKCategoryTypeIdentifierSleepAnalysis" sourceName="Watch" startDate="2021-01-01 01:00:00 +0100" endDate="2021-01-01 01:30:00 +0100" value="HKCategoryValueSleepAnalysisAsleepDeep">
+  <MetadataEntry key="HKTimeZone" value="Europe/Berlin"/>
+ </Record>
+ <Workout workoutActivityType="HKWorkoutActivityTypeTraditionalStrengthTraining" duration="30" durationUnit="min" sourceName="Watch" startDate="2021-01-01 18:00:00 +0100" endDate="2021-01-01 18:30:00 +0100">
+  <WorkoutStatistics type="HKQuantityTypeIdentifierActiveEnergyBurned" sum="200" unit="kcal"/>
+ </Workout>
+</HealthData>"#;
+        let mut events = vec![];
+        parse_apple_health(xml.as_bytes(), |chunk| {
+            events.extend(chunk);
+            Ok(())
+        })?;
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[0].id,
+            "apple_health.steps.Phire's iPhone.1609491000000"
+        );
+        assert_eq!(events[0].duration_ms, 10 * 60 * 1000);
+        let tags = |e: &NewDbEvent| -> anyhow::Result<Tags> {
+            Ok(deserialize_captured((&e.data_type, &e.data))?
+                .extract_info()
+                .unwrap())
+        };
+        assert!(tags(&events[0])?.has_value("physical-activity", "walking"));
+        let sleep = tags(&events[1])?;
+        assert!(sleep.has_value("physical-activity", "sleeping"));
+        assert!(sleep.has_value("sleep-phase", "deep"));
+        assert!(tags(&events[2])?.has_value("physical-activity", "traditional-strength-training"));
+        Ok(())
+    }
+}
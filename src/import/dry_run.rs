@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use crate::prelude::*;
+
+use futures::stream::StreamExt;
+
+#[derive(Debug, Default)]
+pub struct DataTypeStats {
+    pub count: u64,
+    pub duration_ms: i64,
+}
+
+/// what an import would do, without writing anything to the database
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+    pub by_data_type: BTreeMap<String, DataTypeStats>,
+    /// events with an id that is already in the database
+    pub already_existing: u64,
+    /// total time covered by the imported events (overlaps between imported events counted once)
+    pub covered_ms: i64,
+    /// time covered by both the imported events and existing events of the given data_type
+    pub overlap_by_data_type: BTreeMap<String, i64>,
+}
+
+/// run the given import and compare the events against the existing raw events instead of inserting them.
+/// the import cursor is read but not updated
+pub async fn dry_run_import(
+    db: &DatyBasy,
+    import: &ImportArgs,
+    cursor_id: Option<&str>,
+) -> anyhow::Result<ImportReport> {
+    let mut data = match cursor_id {
+        Some(cursor_id) => {
+            let cursor = db
+                .get_import_cursor(cursor_id)
+                .await
+                .context("loading import cursor")?;
+            import.import_incremental(cursor).await?.0
+        }
+        None => import.import().await?,
+    };
+    let mut events = Vec::new();
+    while let Some(chunk) = data.next().await {
+        events.extend(chunk?);
+    }
+    let mut report = ImportReport::default();
+    for e in &events {
+        let stats = report.by_data_type.entry(e.data_type.clone()).or_default();
+        stats.count += 1;
+        stats.duration_ms += e.duration_ms;
+        let end = e.timestamp_unix_ms.0 + chrono::Duration::milliseconds(e.duration_ms);
+        report.first = Some(
+            report
+                .first
+                .map_or(e.timestamp_unix_ms.0, |f| f.min(e.timestamp_unix_ms.0)),
+        );
+        report.last = Some(report.last.map_or(end, |l| l.max(end)));
+    }
+    let (Some(first), Some(last)) = (report.first, report.last) else {
+        return Ok(report);
+    };
+    let imported = merge_intervals(events.iter().map(event_interval).collect());
+    report.covered_ms = imported.iter().map(|(from, to)| to - from).sum();
+
+    let existing = db
+        .get_raw_event_spans_for_time_range(Timestamptz(first), Timestamptz(last))
+        .await?;
+    let imported_ids: HashSet<&str> = events.iter().map(|e| e.id.as_str()).collect();
+    let mut others: BTreeMap<String, Vec<(i64, i64)>> = BTreeMap::new();
+    for e in existing {
+        if imported_ids.contains(e.id.as_str()) {
+            report.already_existing += 1;
+        } else if !report.by_data_type.contains_key(&e.data_type) {
+            let interval = (
+                e.timestamp_unix_ms.0.timestamp_millis(),
+                e.timestamp_unix_ms.0.timestamp_millis() + e.duration_ms,
+            );
+            others.entry(e.data_type).or_default().push(interval);
+        }
+    }
+    for (data_type, intervals) in others {
+        let overlap = overlap_ms(&imported, &merge_intervals(intervals));
+        if overlap > 0 {
+            report.overlap_by_data_type.insert(data_type, overlap);
+        }
+    }
+    Ok(report)
+}
+
+fn event_interval(e: &NewDbEvent) -> (i64, i64) {
+    let from = e.timestamp_unix_ms.0.timestamp_millis();
+    (from, from + e.duration_ms)
+}
+
+/// sort the intervals and merge the ones that overlap
+fn merge_intervals(mut intervals: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    intervals.sort_unstable();
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(intervals.len());
+    for (from, to) in intervals {
+        match merged.last_mut() {
+            Some(last) if from <= last.1 => last.1 = last.1.max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+    merged
+}
+
+/// total length of the intersection of two lists of merged intervals
+fn overlap_ms(a: &[(i64, i64)], b: &[(i64, i64)]) -> i64 {
+    let (mut i, mut j) = (0, 0);
+    let mut total = 0;
+    while i < a.len() && j < b.len() {
+        let from = a[i].0.max(b[j].0);
+        let to = a[i].1.min(b[j].1);
+        if to > from {
+            total += to - from;
+        }
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    total
+}
+
+fn fmt_duration_ms(ms: i64) -> String {
+    let secs = ms / 1000;
+    format!("{}h {:02}m {:02}s", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (Some(first), Some(last)) = (self.first, self.last) else {
+            return writeln!(f, "the import did not return any events");
+        };
+        writeln!(
+            f,
+            "time range: {} - {}",
+            first.to_rfc3339(),
+            last.to_rfc3339()
+        )?;
+        writeln!(f, "events by data type:")?;
+        for (data_type, stats) in &self.by_data_type {
+            writeln!(
+                f,
+                "  {}: {} events, {}",
+                data_type,
+                stats.count,
+                fmt_duration_ms(stats.duration_ms)
+            )?;
+        }
+        writeln!(
+            f,
+            "already in the database: {} events",
+            self.already_existing
+        )?;
+        writeln!(f, "covered time: {}", fmt_duration_ms(self.covered_ms))?;
+        if self.overlap_by_data_type.is_empty() {
+            writeln!(f, "no overlap with existing events from other sources")?;
+        } else {
+            writeln!(f, "overlap with existing events from other sources:")?;
+            for (data_type, overlap) in &self.overlap_by_data_type {
+                writeln!(
+                    f,
+                    "  {}: {} ({:.1}% of covered time)",
+                    data_type,
+                    fmt_duration_ms(*overlap),
+                    *overlap as f64 / self.covered_ms.max(1) as f64 * 100.0
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_overlap() {
+        let a = merge_intervals(vec![(10, 20), (0, 5), (15, 30)]);
+        assert_eq!(a, vec![(0, 5), (10, 30)]);
+        let b = merge_intervals(vec![(3, 12), (25, 40)]);
+        assert_eq!(overlap_ms(&a, &b), 2 + 2 + 5);
+        assert_eq!(overlap_ms(&a, &[]), 0);
+    }
+}
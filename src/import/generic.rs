@@ -0,0 +1,416 @@
+// import arbitrary CSV / JSONL exports using a declarative mapping file
+// example mapping (json):
+// {
+//   "source": "gym",
+//   "format": { "type": "Csv", "delimiter": ";" },
+//   "id_columns": ["Date", "Exercise"],
+//   "timestamp": { "column": "Date", "format": { "type": "Strftime", "format": "%d.%m.%Y %H:%M" }, "timezone": "Europe/Berlin" },
+//   "duration": { "type": "Column", "column": "Duration", "unit": "Minutes" },
+//   "tags": [
+//     { "tag": "physical-activity", "value": "workout" },
+//     { "tag": "gym-exercise", "value": "${Exercise}" },
+//     { "tag": "gym-muscle-group", "value": "$muscles", "split": "," }
+//   ]
+// }
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::prelude::*;
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct GenericImportArgs {
+    /// path to the mapping file (json) that describes how to interpret the data
+    mapping: PathBuf,
+    /// path to the csv or jsonl file to import
+    filename: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GenericImportMapping {
+    /// short name of the data source, used in event ids and the `generic-source` tag
+    pub source: String,
+    #[serde(default)]
+    pub format: GenericFileFormat,
+    /// columns that together uniquely identify a row. if empty, the full row is hashed
+    #[serde(default)]
+    pub id_columns: Vec<String>,
+    pub timestamp: GenericTimestamp,
+    pub duration: GenericDuration,
+    pub tags: Vec<GenericTagMapping>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum GenericFileFormat {
+    Csv {
+        /// a single ascii character
+        #[serde(default = "default_delimiter", deserialize_with = "ascii_delimiter")]
+        delimiter: char,
+        /// if false, columns are referred to by their index ("0", "1", ...)
+        #[serde(default = "default_true")]
+        has_headers: bool,
+    },
+    /// one json object per line. nested objects are flattened to `parent.child` columns
+    Jsonl,
+}
+fn default_delimiter() -> char {
+    ','
+}
+/// the csv reader splits on bytes, so other characters would be cut to the first byte of their utf-8 encoding
+fn ascii_delimiter<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<char, D::Error> {
+    let delimiter = char::deserialize(deserializer)?;
+    if !delimiter.is_ascii() {
+        return Err(serde::de::Error::custom(format!(
+            "delimiter must be a single ascii character, got {delimiter:?}"
+        )));
+    }
+    Ok(delimiter)
+}
+fn default_true() -> bool {
+    true
+}
+impl Default for GenericFileFormat {
+    fn default() -> Self {
+        GenericFileFormat::Csv {
+            delimiter: default_delimiter(),
+            has_headers: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GenericTimestamp {
+    pub column: String,
+    #[serde(default)]
+    pub format: GenericTimestampFormat,
+    /// IANA time zone name used for formats without offset. defaults to the local time zone
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(tag = "type")]
+pub enum GenericTimestampFormat {
+    #[default]
+    Rfc3339,
+    UnixSeconds,
+    UnixMillis,
+    /// chrono strftime format, e.g. `%Y-%m-%d %H:%M:%S`. date-only formats are interpreted as midnight
+    Strftime {
+        format: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum GenericDuration {
+    Column { column: String, unit: DurationUnit },
+    EndTime(GenericTimestamp),
+    Fixed { ms: i64 },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub enum DurationUnit {
+    Milliseconds,
+    Seconds,
+    Minutes,
+    Hours,
+}
+impl DurationUnit {
+    fn to_ms(self, value: f64) -> i64 {
+        let factor = match self {
+            DurationUnit::Milliseconds => 1.0,
+            DurationUnit::Seconds => 1000.0,
+            DurationUnit::Minutes => 60.0 * 1000.0,
+            DurationUnit::Hours => 60.0 * 60.0 * 1000.0,
+        };
+        (value * factor).round() as i64
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GenericTagMapping {
+    pub tag: String,
+    /// expanded with $column / ${Column Name} => the value of that column in the row.
+    /// tags that expand to an empty string are skipped
+    pub value: String,
+    /// if set, the expanded value is split into multiple tag values
+    pub split: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct GenericEntry {
+    pub source: String,
+    pub tags: Vec<TagValue>,
+    pub row: BTreeMap<String, String>,
+}
+
+impl ExtractInfo for GenericEntry {
+    fn extract_info(&self) -> Option<Tags> {
+        let mut tags = Tags::new();
+        tags.add("generic-source", &self.source);
+        tags.extend(self.tags.clone());
+        Some(tags)
+    }
+}
+
+type Row = BTreeMap<String, String>;
+
+fn get_column<'a>(row: &'a Row, column: &str) -> anyhow::Result<&'a str> {
+    row.get(column)
+        .map(|s| s.as_str())
+        .with_context(|| format!("column '{column}' missing in row {row:?}"))
+}
+
+fn parse_timestamp(mapping: &GenericTimestamp, row: &Row) -> anyhow::Result<DateTime<Utc>> {
+    let value = get_column(row, &mapping.column)?.trim();
+    let to_utc = |naive: NaiveDateTime| -> anyhow::Result<DateTime<Utc>> {
+        let date = match &mapping.timezone {
+            Some(tz) => {
+                let tz: chrono_tz::Tz = tz
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("invalid time zone {tz}: {e}"))?;
+                tz.from_local_datetime(&naive)
+                    .earliest()
+                    .map(|d| d.with_timezone(&Utc))
+            }
+            None => Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|d| d.with_timezone(&Utc)),
+        };
+        date.context("impossible time")
+    };
+    Ok(match &mapping.format {
+        GenericTimestampFormat::Rfc3339 => util::iso_string_to_datetime(value)?,
+        GenericTimestampFormat::UnixSeconds => {
+            util::unix_epoch_millis_to_date((value.parse::<f64>()? * 1000.0).round() as i64)
+        }
+        GenericTimestampFormat::UnixMillis => {
+            util::unix_epoch_millis_to_date(value.parse::<f64>()?.round() as i64)
+        }
+        GenericTimestampFormat::Strftime { format } => {
+            if let Ok(d) = DateTime::parse_from_str(value, format) {
+                d.with_timezone(&Utc)
+            } else if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+                to_utc(naive)?
+            } else {
+                let date = NaiveDate::parse_from_str(value, format)
+                    .with_context(|| format!("could not parse '{value}' as '{format}'"))?;
+                to_utc(date.and_hms_opt(0, 0, 0).unwrap())?
+            }
+        }
+    })
+}
+
+impl GenericImportMapping {
+    pub fn row_to_event(&self, row: Row) -> anyhow::Result<NewDbEvent> {
+        let timestamp = parse_timestamp(&self.timestamp, &row)?;
+        let duration_ms = match &self.duration {
+            GenericDuration::Column { column, unit } => {
+                unit.to_ms(get_column(&row, column)?.trim().parse::<f64>()?)
+            }
+            GenericDuration::EndTime(end) => parse_timestamp(end, &row)?
+                .signed_duration_since(timestamp)
+                .num_milliseconds(),
+            GenericDuration::Fixed { ms } => *ms,
+        };
+        let id = if self.id_columns.is_empty() {
+            format!("{:x}", md5::compute(serde_json::to_string(&row)?))
+        } else {
+            self.id_columns
+                .iter()
+                .map(|c| get_column(&row, c))
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .join("_")
+        };
+        let mut tags: Vec<TagValue> = Vec::new();
+        for mapping in &self.tags {
+            let value = expand_str_ez(&mapping.value, |column| {
+                row.get(column).map(|s| s.as_str()).unwrap_or("")
+            });
+            let values: Vec<&str> = match &mapping.split {
+                Some(sep) => value.split(sep.as_str()).map(|v| v.trim()).collect(),
+                None => vec![value.as_str()],
+            };
+            for value in values {
+                if !value.is_empty() {
+                    tags.add(&mapping.tag, value);
+                }
+            }
+        }
+        CreateNewDbEvent {
+            id: format!("generic.{}.{}", self.source, id),
+            timestamp,
+            duration_ms,
+            data: EventData::generic_v1(GenericEntry {
+                source: self.source.clone(),
+                tags,
+                row,
+            }),
+        }
+        .try_into()
+    }
+}
+
+fn flatten_json(prefix: &str, value: J, row: &mut Row) {
+    match value {
+        J::Null => {}
+        J::String(s) => {
+            row.insert(prefix.to_string(), s);
+        }
+        J::Object(o) => {
+            for (k, v) in o {
+                let key = if prefix.is_empty() {
+                    k
+                } else {
+                    format!("{prefix}.{k}")
+                };
+                flatten_json(&key, v, row);
+            }
+        }
+        other => {
+            row.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+pub fn read_generic_rows(
+    format: &GenericFileFormat,
+    filename: &std::path::Path,
+) -> anyhow::Result<Vec<Row>> {
+    let mut rows = Vec::new();
+    match format {
+        GenericFileFormat::Csv {
+            delimiter,
+            has_headers,
+        } => {
+            if !delimiter.is_ascii() {
+                anyhow::bail!("csv delimiter {:?} is not an ascii character", delimiter);
+            }
+            let mut csv = csv::ReaderBuilder::new()
+                .delimiter(*delimiter as u8)
+                .has_headers(*has_headers)
+                .flexible(true)
+                .from_reader(File::open(filename).context("opening csv")?);
+            let headers: Option<Vec<String>> = if *has_headers {
+                Some(
+                    csv.headers()?
+                        .iter()
+                        .map(|h| h.trim().to_string())
+                        .collect(),
+                )
+            } else {
+                None
+            };
+            for record in csv.records() {
+                let record = record.context("reading csv record")?;
+                let row = record
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        let key = headers
+                            .as_ref()
+                            .and_then(|h| h.get(i).cloned())
+                            .unwrap_or_else(|| i.to_string());
+                        (key, value.to_string())
+                    })
+                    .collect();
+                rows.push(row);
+            }
+        }
+        GenericFileFormat::Jsonl => {
+            use std::io::BufRead;
+            let reader = std::io::BufReader::new(File::open(filename).context("opening jsonl")?);
+            for line in reader.lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let mut row = Row::new();
+                flatten_json("", serde_json::from_str(&line)?, &mut row);
+                rows.push(row);
+            }
+        }
+    }
+    Ok(rows)
+}
+
+#[async_trait]
+impl Importable for GenericImportArgs {
+    fn source_path(&self) -> Option<PathBuf> {
+        Some(self.filename.clone())
+    }
+
+    async fn import(&self) -> ImportResult {
+        let mapping: GenericImportMapping =
+            serde_json::from_reader(File::open(&self.mapping).context("opening mapping file")?)
+                .context("parsing mapping file")?;
+        let rows = read_generic_rows(&mapping.format, &self.filename)?;
+        log::info!("read {} rows", rows.len());
+        let mut outs = Vec::new();
+        for (i, row) in rows.into_iter().enumerate() {
+            outs.push(
+                mapping
+                    .row_to_event(row)
+                    .with_context(|| format!("mapping row {i}"))?,
+            );
+        }
+        Ok(Box::pin(futures::stream::once(futures::future::ok(outs))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generic_mapping() {
+        let mapping: GenericImportMapping = serde_json::from_value(serde_json::json!({
+            "source": "sleep",
+            "id_columns": ["start"],
+            "timestamp": { "column": "start", "format": { "type": "UnixSeconds" } },
+            "duration": { "type": "Column", "column": "Minutes Asleep", "unit": "Minutes" },
+            "tags": [
+                { "tag": "physical-activity", "value": "sleeping" },
+                { "tag": "sleep-tag", "value": "${Tags}", "split": "," },
+                { "tag": "sleep-note", "value": "$note" }
+            ]
+        }))
+        .unwrap();
+        let row: Row = [
+            ("start", "1600000000"),
+            ("Minutes Asleep", "90"),
+            ("Tags", "home, alarm"),
+            ("note", ""),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let event = mapping.row_to_event(row).unwrap();
+        assert_eq!(event.id, "generic.sleep.1600000000");
+        assert_eq!(event.duration_ms, 90 * 60 * 1000);
+        assert_eq!(event.timestamp_unix_ms.0.timestamp(), 1600000000);
+        let tags = deserialize_captured((&event.data_type, &event.data))
+            .unwrap()
+            .extract_info()
+            .unwrap();
+        assert!(tags.has_value("physical-activity", "sleeping"));
+        assert!(tags.has_value("sleep-tag", "home"));
+        assert!(tags.has_value("sleep-tag", "alarm"));
+        assert!(!tags.has("sleep-note"));
+        assert!(tags.has_value("generic-source", "sleep"));
+    }
+
+    #[test]
+    fn test_delimiter_must_be_ascii() {
+        let format: GenericFileFormat =
+            serde_json::from_value(serde_json::json!({ "type": "Csv", "delimiter": ";" })).unwrap();
+        assert!(matches!(
+            format,
+            GenericFileFormat::Csv { delimiter: ';', .. }
+        ));
+        let format = serde_json::from_value::<GenericFileFormat>(
+            serde_json::json!({ "type": "Csv", "delimiter": "§" }),
+        );
+        assert!(format.is_err());
+    }
+}
@@ -0,0 +1,167 @@
+// import the Google Fit data of a Google Takeout export (https://takeout.google.com)
+// Takeout/Fit/All Sessions/*.json contains one file per workout or sleep session,
+// Takeout/Fit/All Data/derived_com.google.step_count.delta_*merge_step_deltas.json the merged step counts
+use std::path::PathBuf;
+
+use crate::prelude::*;
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct GoogleTakeoutFitImportArgs {
+    /// path to the extracted Takeout/Fit directory
+    path: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FitSession {
+    fitness_activity: String,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    #[serde(default)]
+    segment: Vec<FitSegment>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FitSegment {
+    fitness_activity: String,
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FitDataFile {
+    #[serde(rename = "Data Points")]
+    data_points: Vec<FitDataPoint>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FitDataPoint {
+    start_time_nanos: i64,
+    end_time_nanos: i64,
+    fit_value: Vec<FitValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FitValue {
+    value: FitValueInner,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FitValueInner {
+    int_val: Option<i64>,
+}
+
+/// "sleep.deep" -> "deep", "sleep.awake" / "awake" -> "awake"
+fn sleep_phase(activity: &str) -> Option<String> {
+    let phase = activity.strip_prefix("sleep.").unwrap_or(activity);
+    match phase {
+        "light" | "deep" | "rem" | "awake" => Some(phase.to_string()),
+        _ => None,
+    }
+}
+
+fn session_events(session: FitSession) -> anyhow::Result<Vec<NewDbEvent>> {
+    let id = format!(
+        "google_fit.{}.{}",
+        session.start_time.timestamp_millis(),
+        session.fitness_activity
+    );
+    let mut outs = vec![];
+    let kind = if session.fitness_activity == "sleep" {
+        for segment in &session.segment {
+            if let Some(phase) = sleep_phase(&segment.fitness_activity) {
+                outs.push(health_event(
+                    format!("{id}.{}", segment.start_time.timestamp_millis()),
+                    HealthSource::GoogleFit,
+                    None,
+                    HealthEventKind::SleepPhase { phase },
+                    segment.start_time,
+                    segment.end_time,
+                )?);
+            }
+        }
+        HealthEventKind::Sleep { phase: None }
+    } else {
+        HealthEventKind::Workout {
+            activity: normalize_activity(&session.fitness_activity),
+        }
+    };
+    outs.push(health_event(
+        id,
+        HealthSource::GoogleFit,
+        None,
+        kind,
+        session.start_time,
+        session.end_time,
+    )?);
+    Ok(outs)
+}
+
+fn step_events(file: FitDataFile) -> anyhow::Result<Vec<NewDbEvent>> {
+    let mut outs = vec![];
+    for point in file.data_points {
+        let count: i64 = point.fit_value.iter().filter_map(|v| v.value.int_val).sum();
+        if count == 0 {
+            continue;
+        }
+        let start = util::unix_epoch_millis_to_date(point.start_time_nanos / 1_000_000);
+        let end = util::unix_epoch_millis_to_date(point.end_time_nanos / 1_000_000);
+        outs.push(health_event(
+            format!("google_fit.steps.{}", point.start_time_nanos),
+            HealthSource::GoogleFit,
+            None,
+            HealthEventKind::Steps { count },
+            start,
+            end,
+        )?);
+    }
+    Ok(outs)
+}
+
+fn json_files(dir: &std::path::Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir).with_context(|| format!("reading {dir:?}"))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|e| e == "json") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &std::path::Path) -> anyhow::Result<T> {
+    serde_json::from_reader(std::io::BufReader::new(File::open(path)?))
+        .with_context(|| format!("parsing {path:?}"))
+}
+
+#[async_trait]
+impl Importable for GoogleTakeoutFitImportArgs {
+    fn source_path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+
+    async fn import(&self) -> ImportResult {
+        let mut outs = vec![];
+        for file in json_files(&self.path.join("All Sessions"))? {
+            outs.extend(session_events(read_json(&file)?)?);
+        }
+        for file in json_files(&self.path.join("All Data"))? {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            // the other step files contain the raw data of the single devices, which is already in the merged one
+            if name.starts_with("derived_com.google.step_count.delta")
+                && name.contains("merge_step_deltas")
+            {
+                outs.extend(step_events(read_json(&file)?)?);
+            }
+        }
+        log::info!("got {} google fit events", outs.len());
+        Ok(Box::pin(futures::stream::once(futures::future::ok(outs))))
+    }
+}
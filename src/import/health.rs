@@ -0,0 +1,121 @@
+use crate::prelude::*;
+
+/// app the health data was exported from
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone, Copy, PartialEq)]
+pub enum HealthSource {
+    GoogleFit,
+    AppleHealth,
+    SamsungHealth,
+}
+
+impl HealthSource {
+    fn name(&self) -> &'static str {
+        match self {
+            HealthSource::GoogleFit => "Google Fit",
+            HealthSource::AppleHealth => "Apple Health",
+            HealthSource::SamsungHealth => "Samsung Health",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+#[serde(tag = "type")]
+pub enum HealthEventKind {
+    /// activity is normalized to lowercase-with-dashes, e.g. "running" or "traditional-strength-training"
+    Workout {
+        activity: String,
+    },
+    Steps {
+        count: i64,
+    },
+    /// asleep, optionally in a known phase (deep, light, rem)
+    Sleep {
+        phase: Option<String>,
+    },
+    /// phase inside a Sleep event (or awake time in bed), does not count as sleeping on its own
+    SleepPhase {
+        phase: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct HealthEntry {
+    pub source: HealthSource,
+    /// device or app that recorded the data, if known
+    pub device: Option<String>,
+    pub kind: HealthEventKind,
+}
+
+impl ExtractInfo for HealthEntry {
+    fn extract_info(&self) -> Option<Tags> {
+        let mut tags = Tags::new();
+        tags.add("health-source", self.source.name());
+        if let Some(device) = &self.device {
+            tags.add("health-device", device);
+        }
+        match &self.kind {
+            HealthEventKind::Workout { activity } => {
+                tags.add("physical-activity", activity);
+            }
+            HealthEventKind::Steps { .. } => {
+                tags.add("physical-activity", "walking");
+            }
+            HealthEventKind::Sleep { phase } => {
+                tags.add("physical-activity", "sleeping");
+                if let Some(phase) = phase {
+                    tags.add("sleep-phase", phase);
+                }
+            }
+            HealthEventKind::SleepPhase { phase } => {
+                tags.add("sleep-phase", phase);
+            }
+        }
+        Some(tags)
+    }
+}
+
+/// "TraditionalStrengthTraining", "traditional_strength_training" or "Traditional strength training"
+/// -> "traditional-strength-training"
+pub fn normalize_activity(activity: &str) -> String {
+    let mut out = String::new();
+    let mut prev_lower = false;
+    for c in activity.chars() {
+        if c == '_' || c == ' ' || c == '.' || c == '-' {
+            if !out.is_empty() && !out.ends_with('-') {
+                out.push('-');
+            }
+            prev_lower = false;
+        } else if c.is_uppercase() {
+            if prev_lower {
+                out.push('-');
+            }
+            out.extend(c.to_lowercase());
+            prev_lower = false;
+        } else {
+            out.push(c);
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        }
+    }
+    out.trim_end_matches('-').to_string()
+}
+
+pub(crate) fn health_event(
+    id: String,
+    source: HealthSource,
+    device: Option<String>,
+    kind: HealthEventKind,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> anyhow::Result<NewDbEvent> {
+    CreateNewDbEvent {
+        id,
+        timestamp: start,
+        duration_ms: (end - start).num_milliseconds().max(0),
+        data: EventData::health_v1(HealthEntry {
+            source,
+            device,
+            kind,
+        }),
+    }
+    .try_into()
+}
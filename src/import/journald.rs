@@ -4,8 +4,18 @@ use crate::prelude::*;
 
 use structopt::StructOpt;
 
-#[derive(StructOpt)]
-pub struct JournaldImportArgs {}
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::PathBuf;
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct JournaldImportArgs {
+    /// read the output of `journalctl --output=json -t systemd-sleep -t systemd-logind` from this file
+    /// instead of running journalctl. boots are only imported when running journalctl
+    #[structopt(long, parse(from_os_str))]
+    #[serde(default)]
+    json_file: Option<PathBuf>,
+}
 
 #[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
 pub struct JournaldEntry {
@@ -18,29 +28,40 @@ enum JournaldEvent {
     Powerup,
     Shutdown,
     LogEntry(J),
+    /// suspended from the start of the event until it resumed. operation is e.g. "suspend" or "hibernate"
+    Suspended {
+        operation: Option<String>,
+    },
+    /// the session was locked for the duration of the event
+    Locked {
+        session: String,
+    },
+    /// the user was logged in for the duration of the event
+    Session {
+        session: String,
+        user: String,
+    },
 }
 
 impl ExtractInfo for JournaldEntry {
     fn extract_info(&self) -> Option<Tags> {
         let mut tags = Tags::new();
-        tags.add("todo", "journald info");
+        tags.add("device-hostname", &self.os_info.hostname);
+        tags.add("device-os-type", &self.os_info.os_type);
+        match &self.event {
+            JournaldEvent::Powerup => tags.add("device-state-change", "power-on"),
+            JournaldEvent::Shutdown => tags.add("device-state-change", "power-off"),
+            JournaldEvent::LogEntry(_) => return None,
+            JournaldEvent::Suspended { operation } => {
+                tags.add("device-state", "suspended");
+                if let Some(operation) = operation {
+                    tags.add("device-sleep-operation", operation);
+                }
+            }
+            JournaldEvent::Locked { .. } => tags.add("device-state", "locked"),
+            JournaldEvent::Session { user, .. } => tags.add("device-session-user", user),
+        }
         Some(tags)
-        /*
-        let mut general = self.os_info.to_partial_general_software();
-        general.identifier = Identifier("exe:/usr/bin/journalctl".to_string());
-        general.title = "journalctl".to_string();
-        general.unique_name = "systemd".to_string();
-        general.opened_filepath = None;
-        Some(ExtractedInfo::InteractWithDevice {
-            general,
-            specific: SpecificSoftware::DeviceStateChange {
-                change: match self.event {
-                    JournaldEvent::Powerup => DeviceStateChange::PowerOn,
-                    JournaldEvent::Shutdown => DeviceStateChange::PowerOff,
-                    _ => return None,
-                },
-            },
-        })*/
     }
 }
 lazy_static! {
@@ -59,11 +80,140 @@ lazy_static! {
         "#
     )
     .unwrap();
+    // systemd-sleep: "Entering sleep state 'suspend'..." (newer: "Performing sleep operation 'suspend'...", older: "Suspending system...")
+    static ref SLEEP_START: regex::Regex = regex::Regex::new(
+        r"^(?:(?:Entering sleep state|Performing sleep operation) '(?P<operation>[a-z-]+)'|Suspending system)"
+    )
+    .unwrap();
+    // "System returned from sleep state." / "System returned from sleep operation 'suspend'." / "System resumed."
+    static ref SLEEP_END: regex::Regex =
+        regex::Regex::new(r"^System (?:returned from sleep|resumed)").unwrap();
+    static ref SESSION_START: regex::Regex =
+        regex::Regex::new(r"^New session (?P<session>\S+) of user (?P<user>[^\s.]+)").unwrap();
+    static ref SESSION_END: regex::Regex =
+        regex::Regex::new(r"^(?:Session (?P<session>\S+) logged out|Removed session (?P<session2>[^\s.]+))").unwrap();
+    static ref SESSION_LOCK: regex::Regex =
+        regex::Regex::new(r"^Session (?P<session>\S+) (?P<state>locked|unlocked)").unwrap();
 }
-#[async_trait]
-impl Importable for JournaldImportArgs {
-    async fn import(&self) -> ImportResult {
-        use std::io::{BufRead, BufReader};
+
+/// one line of `journalctl --output=json`. all values are strings, MESSAGE may be a byte array if it is not valid utf8
+#[derive(Deserialize)]
+struct JournalJsonEntry {
+    #[serde(rename = "__REALTIME_TIMESTAMP")]
+    realtime_timestamp: String,
+    #[serde(rename = "_BOOT_ID")]
+    boot_id: Option<String>,
+    #[serde(rename = "_HOSTNAME")]
+    hostname: Option<String>,
+    #[serde(rename = "MESSAGE")]
+    message: Option<J>,
+}
+
+enum JournalMarker {
+    Start(String, JournaldEvent),
+    End(String),
+}
+
+fn classify_message(message: &str) -> Option<JournalMarker> {
+    if let Some(cap) = SLEEP_START.captures(message) {
+        let operation = cap.name("operation").map(|o| o.as_str().to_string());
+        return Some(JournalMarker::Start(
+            "suspend".to_string(),
+            JournaldEvent::Suspended { operation },
+        ));
+    }
+    if SLEEP_END.is_match(message) {
+        return Some(JournalMarker::End("suspend".to_string()));
+    }
+    if let Some(cap) = SESSION_START.captures(message) {
+        let session = cap["session"].to_string();
+        return Some(JournalMarker::Start(
+            format!("session.{session}"),
+            JournaldEvent::Session {
+                session,
+                user: cap["user"].to_string(),
+            },
+        ));
+    }
+    if let Some(cap) = SESSION_END.captures(message) {
+        let session = cap
+            .name("session")
+            .or_else(|| cap.name("session2"))
+            .unwrap();
+        return Some(JournalMarker::End(format!("session.{}", session.as_str())));
+    }
+    if let Some(cap) = SESSION_LOCK.captures(message) {
+        let session = cap["session"].to_string();
+        let key = format!("lock.{session}");
+        return Some(if &cap["state"] == "locked" {
+            JournalMarker::Start(key, JournaldEvent::Locked { session })
+        } else {
+            JournalMarker::End(key)
+        });
+    }
+    None
+}
+
+/// parse the output of `journalctl --output=json` and pair the suspend/resume, lock/unlock and login/logout messages
+/// into events that last from the first to the second message. unfinished pairs (e.g. the current session) are skipped
+pub fn parse_journal_json(
+    input: impl BufRead,
+    os_info: &util::OsInfo,
+) -> anyhow::Result<Vec<NewDbEvent>> {
+    let mut outs = vec![];
+    // (boot id, key) -> (start, event, hostname)
+    let mut open: HashMap<(String, String), (DateTime<Utc>, JournaldEvent, Option<String>)> =
+        HashMap::new();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: JournalJsonEntry = serde_json::from_str(&line)
+            .with_context(|| format!("could not parse journal entry '{line}'"))?;
+        let Some(message) = entry.message.as_ref().and_then(|m| m.as_str()) else {
+            continue;
+        };
+        let Some(marker) = classify_message(message) else {
+            continue;
+        };
+        let timestamp_us: i64 = entry
+            .realtime_timestamp
+            .parse()
+            .context("invalid __REALTIME_TIMESTAMP")?;
+        let timestamp = util::unix_epoch_millis_to_date(timestamp_us / 1000);
+        let boot_id = entry.boot_id.unwrap_or_else(|| "unknown".to_string());
+        match marker {
+            JournalMarker::Start(key, event) => {
+                open.insert((boot_id, key), (timestamp, event, entry.hostname));
+            }
+            JournalMarker::End(key) => {
+                let Some((start, event, hostname)) = open.remove(&(boot_id.clone(), key.clone()))
+                else {
+                    continue;
+                };
+                let mut os_info = os_info.clone();
+                if let Some(hostname) = hostname {
+                    os_info.hostname = hostname;
+                }
+                outs.push(
+                    CreateNewDbEvent {
+                        id: format!("{boot_id}.{key}.{}", start.timestamp_millis()),
+                        timestamp: start,
+                        data: EventData::journald_v1(JournaldEntry { os_info, event }),
+                        duration_ms: (timestamp - start).num_milliseconds(),
+                    }
+                    .try_into()?,
+                );
+            }
+        }
+    }
+    Ok(outs)
+}
+
+impl JournaldImportArgs {
+    fn list_boots(&self) -> anyhow::Result<Vec<NewDbEvent>> {
+        use std::io::BufReader;
         use std::process::{Command, Stdio};
         let os_info = util::get_os_info();
 
@@ -117,8 +267,100 @@ impl Importable for JournaldImportArgs {
             }
         }
 
+        Ok(outs)
+    }
+
+    fn read_events(&self) -> anyhow::Result<Vec<NewDbEvent>> {
+        let os_info = util::get_os_info();
+        if let Some(json_file) = &self.json_file {
+            let file = File::open(json_file).with_context(|| format!("opening {json_file:?}"))?;
+            return parse_journal_json(std::io::BufReader::new(file), &os_info);
+        }
+        let output = std::process::Command::new("journalctl")
+            .args([
+                "--output=json",
+                "--all",
+                "-t",
+                "systemd-sleep",
+                "-t",
+                "systemd-logind",
+            ])
+            .output()
+            .context("running journalctl")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "journalctl failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        let mut outs = self.list_boots()?;
+        outs.extend(parse_journal_json(&output.stdout[..], &os_info)?);
+        Ok(outs)
+    }
+}
+
+#[async_trait]
+impl Importable for JournaldImportArgs {
+    async fn import(&self) -> ImportResult {
+        let outs = self.read_events()?;
         Ok(Box::pin(futures::stream::once(futures::future::ok(outs))))
+    }
+
+    async fn import_incremental(&self, cursor: Option<ImportCursor>) -> IncrementalImportResult {
+        // compare the end of the events, since e.g. a session that ended after the last import started before it
+        let end_ms = |e: &NewDbEvent| e.timestamp_unix_ms.0.timestamp_millis() + e.duration_ms;
+        let mut outs = self.read_events()?;
+        if let Some(ImportCursor::Timestamp { last_unix_ms }) = cursor {
+            outs.retain(|e| end_ms(e) > last_unix_ms);
+        }
+        let cursor = outs
+            .iter()
+            .map(end_ms)
+            .max()
+            .map(|last_unix_ms| ImportCursor::Timestamp { last_unix_ms });
+        Ok((
+            Box::pin(futures::stream::once(futures::future::ok(outs))),
+            cursor,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_journal_json() -> anyhow::Result<()> {
+        let fixture = include_str!("../../data/test/journald.json");
+        let events = parse_journal_json(fixture.as_bytes(), &util::OsInfo::default())?;
+        // suspend, lock, session. the hibernate at the end never resumed
+        assert_eq!(events.len(), 3);
+
+        let suspend = &events[0];
+        assert_eq!(
+            suspend.id,
+            "9c4e0c5d6c1d4f0a8e2b7f3a1c5d9e01.suspend.1634544000000"
+        );
+        assert_eq!(suspend.duration_ms, 3_600_500);
+        let tags = deserialize_captured((&suspend.data_type, &suspend.data))?
+            .extract_info()
+            .unwrap();
+        assert!(tags.has_value("device-state", "suspended"));
+        assert!(tags.has_value("device-sleep-operation", "suspend"));
+
+        let lock = &events[1];
+        assert_eq!(lock.duration_ms, 300_000);
+        let tags = deserialize_captured((&lock.data_type, &lock.data))?
+            .extract_info()
+            .unwrap();
+        assert!(tags.has_value("device-state", "locked"));
 
-        // journalctl -t systemd-sleep --output=json --all
+        let session = &events[2];
+        assert_eq!(session.duration_ms, 3 * 3_600_000);
+        let tags = deserialize_captured((&session.data_type, &session.data))?
+            .extract_info()
+            .unwrap();
+        assert!(tags.has_value("device-session-user", "phire"));
+        Ok(())
     }
 }
@@ -0,0 +1,58 @@
+// shared event type for manual time trackers (timewarrior, watson, toggl)
+use crate::prelude::*;
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub enum ManualTrackerSource {
+    Timewarrior,
+    Watson,
+    Toggl,
+}
+
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct ManualTrackerEntry {
+    pub source: ManualTrackerSource,
+    pub project: Option<String>,
+    pub client: Option<String>,
+    pub tags: Vec<String>,
+    pub description: Option<String>,
+}
+
+impl ExtractInfo for ManualTrackerEntry {
+    fn extract_info(&self) -> Option<Tags> {
+        let mut tags = Tags::new();
+        tags.add("manual-tracker-source", format!("{:?}", self.source));
+        if let Some(project) = &self.project {
+            tags.add("project", project);
+        }
+        if let Some(client) = &self.client {
+            tags.add("manual-tracker-client", client);
+        }
+        for tag in &self.tags {
+            tags.add("manual-tracker-tag", tag);
+        }
+        if let Some(description) = &self.description {
+            tags.add("manual-tracker-description", description);
+        }
+        Some(tags)
+    }
+}
+
+/// helper for importers that don't have a separate project field:
+/// tags like `project:foo` or `client:bar` are moved into the respective field
+pub fn split_project_client_tags(
+    tags: Vec<String>,
+) -> (Option<String>, Option<String>, Vec<String>) {
+    let mut project = None;
+    let mut client = None;
+    let mut rest = Vec::new();
+    for tag in tags {
+        if let Some(p) = tag.strip_prefix("project:") {
+            project = Some(p.to_string());
+        } else if let Some(c) = tag.strip_prefix("client:") {
+            client = Some(c.to_string());
+        } else {
+            rest.push(tag);
+        }
+    }
+    (project, client, rest)
+}
@@ -0,0 +1,44 @@
+use crate::prelude::*;
+use derive_more::Display;
+
+#[derive(Debug, Display, Serialize, Deserialize, TypeScriptify, Clone, Copy, PartialEq)]
+pub enum MediaSource {
+    Spotify,
+    LastFm,
+    ListenBrainz,
+}
+
+/// something that was listened to. media is a background activity, it does not count as tracked time
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct MediaEntry {
+    pub source: MediaSource,
+    pub artist: Option<String>,
+    pub track: Option<String>,
+    pub album: Option<String>,
+    /// e.g. "music" or "podcast"
+    pub media_type: Option<String>,
+}
+
+impl ExtractInfo for MediaEntry {
+    fn extract_info(&self) -> Option<Tags> {
+        let mut tags = Tags::new();
+        tags.add("media-source", format!("{}", self.source));
+        if let Some(artist) = &self.artist {
+            tags.add("media-artist", artist);
+        }
+        if let Some(track) = &self.track {
+            tags.add("media-track", track);
+        }
+        if let Some(album) = &self.album {
+            tags.add("media-album", album);
+        }
+        if let Some(media_type) = &self.media_type {
+            tags.add("media-type", media_type);
+        }
+        Some(tags)
+    }
+
+    fn is_background(&self) -> bool {
+        true
+    }
+}
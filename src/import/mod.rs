@@ -1,30 +1,193 @@
+pub mod android_usage_stats;
 pub mod app_usage_sqlite;
-// pub mod google_fitness;
+pub mod apple_health;
+pub mod dry_run;
+pub mod generic;
+pub mod google_takeout_fit;
+pub mod health;
 pub mod journald;
+pub mod manual_tracker;
+pub mod media;
+pub mod samsung_health;
+pub mod scrobbles;
 pub mod sleep_as_android;
+pub mod spotify;
 pub mod timetrackrs_db;
+pub mod timewarrior;
+pub mod toggl;
+pub mod watson;
+
+use std::path::{Path, PathBuf};
 
 use crate::prelude::*;
 
 use enum_dispatch::enum_dispatch;
 
-use futures::stream::BoxStream;
+use futures::never::Never;
+use futures::stream::{BoxStream, StreamExt};
 use structopt::StructOpt;
 
 #[enum_dispatch]
-#[derive(StructOpt)]
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
 #[structopt(about = "Import events from a different program")]
 pub enum ImportArgs {
     AppUsage(app_usage_sqlite::AppUsageImportArgs),
+    AndroidUsageStats(android_usage_stats::AndroidUsageStatsImportArgs),
     Journald(journald::JournaldImportArgs),
     Timetrackrs(timetrackrs_db::TimetrackrsImportArgs),
     SleepAsAndroid(sleep_as_android::SleepAsAndroidImportArgs),
+    Timewarrior(timewarrior::TimewarriorImportArgs),
+    Watson(watson::WatsonImportArgs),
+    Toggl(toggl::TogglImportArgs),
+    Generic(generic::GenericImportArgs),
+    GoogleTakeoutFit(google_takeout_fit::GoogleTakeoutFitImportArgs),
+    AppleHealth(apple_health::AppleHealthImportArgs),
+    SamsungHealth(samsung_health::SamsungHealthImportArgs),
+    Spotify(spotify::SpotifyImportArgs),
+    LastFm(scrobbles::LastFmImportArgs),
+    ListenBrainz(scrobbles::ListenBrainzImportArgs),
 }
 
 #[async_trait]
 #[enum_dispatch(ImportArgs)]
 pub trait Importable {
     async fn import(&self) -> ImportResult;
+
+    /// file or directory the data is read from. if set, incremental imports are skipped while it is unchanged
+    fn source_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// like import, but skip data that is already covered by the cursor of the previous import.
+    /// returns the cursor that should be persisted once all returned events are stored
+    async fn import_incremental(&self, cursor: Option<ImportCursor>) -> IncrementalImportResult {
+        let Some(path) = self.source_path() else {
+            return Ok((self.import().await?, None));
+        };
+        let state = ImportCursor::file_state(&path)?;
+        if cursor.as_ref() == Some(&state) {
+            log::info!("{:?} did not change since the last import", path);
+            return Ok((Box::pin(futures::stream::empty()), Some(state)));
+        }
+        Ok((self.import().await?, Some(state)))
+    }
 }
 
 pub type ImportResult<'a> = anyhow::Result<BoxStream<'a, anyhow::Result<Vec<NewDbEvent>>>>;
+pub type IncrementalImportResult<'a> = anyhow::Result<(
+    BoxStream<'a, anyhow::Result<Vec<NewDbEvent>>>,
+    Option<ImportCursor>,
+)>;
+
+/// how far a previous import of a source got, persisted in config.import_cursors
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum ImportCursor {
+    /// max insertion_sequence of an imported timetrackrs raw_events database
+    InsertionSequence { max: i64 },
+    /// modification time and size of the imported file (or newest file in the imported directory)
+    FileState { mtime_unix_ms: i64, size: u64 },
+    /// timestamp of the newest imported event
+    Timestamp { last_unix_ms: i64 },
+}
+
+impl ImportCursor {
+    pub fn file_state(path: &Path) -> anyhow::Result<ImportCursor> {
+        let mut mtime = std::time::SystemTime::UNIX_EPOCH;
+        let mut size = 0;
+        let meta = std::fs::metadata(path).with_context(|| format!("stat {path:?}"))?;
+        let metas = if meta.is_dir() {
+            std::fs::read_dir(path)?
+                .map(|e| Ok(e?.metadata()?))
+                .collect::<anyhow::Result<Vec<_>>>()?
+        } else {
+            vec![meta]
+        };
+        for meta in metas {
+            mtime = mtime.max(meta.modified()?);
+            size += meta.len();
+        }
+        Ok(ImportCursor::FileState {
+            mtime_unix_ms: mtime
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)?
+                .as_millis() as i64,
+            size,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportStats {
+    pub seen: u64,
+    pub inserted: u64,
+}
+
+/// run the given import and insert the events into the database.
+/// if cursor_id is given, the import continues from the cursor persisted under that id
+pub async fn run_import(
+    db: &DatyBasy,
+    import: &ImportArgs,
+    cursor_id: Option<&str>,
+) -> anyhow::Result<ImportStats> {
+    let (mut data, new_cursor) = match cursor_id {
+        Some(cursor_id) => {
+            let cursor = db
+                .get_import_cursor(cursor_id)
+                .await
+                .context("loading import cursor")?;
+            log::debug!("import cursor for {}: {:?}", cursor_id, cursor);
+            import.import_incremental(cursor).await?
+        }
+        None => (import.import().await?, None),
+    };
+    log::info!("inserting...");
+    let mut stats = ImportStats::default();
+    while let Some(chunk) = data.next().await {
+        let chunk = chunk?;
+        let len = chunk.len() as u64;
+        let updated = db
+            .insert_events_if_needed(chunk)
+            .await
+            .context("inserting events")?;
+        stats.inserted += updated;
+        stats.seen += len;
+        log::info!(
+            "successfully inserted {}/{} entries ({} already existed)",
+            stats.inserted,
+            stats.seen,
+            stats.seen - stats.inserted
+        );
+    }
+    if let (Some(cursor_id), Some(new_cursor)) = (cursor_id, new_cursor) {
+        db.set_import_cursor(cursor_id, &new_cursor)
+            .await
+            .context("saving import cursor")?;
+    }
+    Ok(stats)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportConfig {
+    /// unique name of this import, used to persist the import cursor
+    pub name: String,
+    pub interval: Duration,
+    pub args: ImportArgs,
+}
+
+pub async fn import_loop(db: DatyBasy, config: ImportConfig) -> anyhow::Result<Never> {
+    let mut interval = tokio::time::interval(config.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        log::info!("running scheduled import {}", config.name);
+        match run_import(&db, &config.args, Some(&config.name)).await {
+            Ok(stats) => log::info!(
+                "scheduled import {} inserted {}/{} events",
+                config.name,
+                stats.inserted,
+                stats.seen
+            ),
+            Err(e) => log::error!("scheduled import {} failed: {:?}", config.name, e),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+// import the CSV files of a Samsung Health export (Samsung Health -> settings -> download personal data)
+// every file starts with a line with the data type, then the header line. the column names are usually prefixed with
+// the data type (e.g. com.samsung.health.exercise.start_time), times are in UTC
+use std::path::{Path, PathBuf};
+
+use crate::prelude::*;
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct SamsungHealthImportArgs {
+    /// path to the extracted export directory (containing the com.samsung.shealth.*.csv files)
+    path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SamsungFile {
+    Exercise,
+    Sleep,
+    Steps,
+}
+
+impl SamsungFile {
+    /// e.g. com.samsung.shealth.exercise.20210101123456.csv. other files with the same prefix
+    /// (e.g. com.samsung.shealth.exercise.weather.*.csv) are ignored
+    fn from_filename(name: &str) -> Option<SamsungFile> {
+        let (kind, rest) = if let Some(rest) = name.strip_prefix("com.samsung.shealth.exercise.") {
+            (SamsungFile::Exercise, rest)
+        } else if let Some(rest) = name.strip_prefix("com.samsung.shealth.sleep.") {
+            (SamsungFile::Sleep, rest)
+        } else if let Some(rest) =
+            name.strip_prefix("com.samsung.shealth.tracker.pedometer_step_count.")
+        {
+            (SamsungFile::Steps, rest)
+        } else {
+            return None;
+        };
+        if rest.starts_with(|c: char| c.is_ascii_digit()) && rest.ends_with(".csv") {
+            Some(kind)
+        } else {
+            None
+        }
+    }
+}
+
+/// https://developer.samsung.com/health/android/data/api-reference/EXERCISE_TYPE.html
+fn exercise_name(exercise_type: &str) -> String {
+    match exercise_type {
+        "1001" => "walking",
+        "1002" => "running",
+        "11007" => "biking",
+        "13001" => "hiking",
+        "14001" => "swimming",
+        "10007" => "yoga",
+        "10004" => "strength-training",
+        "15003" => "elliptical",
+        "15005" => "rowing-machine",
+        other => return format!("exercise-{other}"),
+    }
+    .to_string()
+}
+
+struct Columns(Vec<String>);
+
+impl Columns {
+    /// the column called name or *.name
+    fn index(&self, name: &str) -> Option<usize> {
+        let suffix = format!(".{name}");
+        self.0
+            .iter()
+            .position(|c| c == name || c.ends_with(&suffix))
+    }
+}
+
+fn parse_time(s: &str) -> anyhow::Result<DateTime<Utc>> {
+    // e.g. 2021-01-01 10:00:00.000
+    let time = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f")
+        .with_context(|| format!("invalid time {s}"))?;
+    Ok(Utc.from_utc_datetime(&time))
+}
+
+fn parse_samsung_csv(kind: SamsungFile, input: impl Read) -> anyhow::Result<Vec<NewDbEvent>> {
+    let mut csv = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(input);
+    let mut records = csv.records();
+    // first line: data type, version, ...
+    records.next().transpose()?;
+    let Some(header) = records.next().transpose()? else {
+        return Ok(vec![]);
+    };
+    let columns = Columns(header.iter().map(|c| c.to_string()).collect());
+    let col = |name: &str| {
+        columns
+            .index(name)
+            .with_context(|| format!("missing column {name}"))
+    };
+    let start_col = col("start_time")?;
+    let end_col = col("end_time")?;
+    let uuid_col = col("datauuid")?;
+    let device_col = columns.index("deviceuuid");
+    let extra_col = match kind {
+        SamsungFile::Exercise => Some(col("exercise_type")?),
+        SamsungFile::Steps => Some(col("count")?),
+        SamsungFile::Sleep => None,
+    };
+    let mut outs = vec![];
+    for record in records {
+        let record = record?;
+        let get = |i: usize| record.get(i).unwrap_or_default();
+        if get(start_col).is_empty() || get(end_col).is_empty() {
+            continue;
+        }
+        let start = parse_time(get(start_col))?;
+        let end = parse_time(get(end_col))?;
+        let extra = extra_col.map(get).unwrap_or_default();
+        let event_kind = match kind {
+            SamsungFile::Exercise => HealthEventKind::Workout {
+                activity: exercise_name(extra),
+            },
+            SamsungFile::Steps => HealthEventKind::Steps {
+                count: extra.parse().unwrap_or(0),
+            },
+            SamsungFile::Sleep => HealthEventKind::Sleep { phase: None },
+        };
+        outs.push(health_event(
+            format!("samsung_health.{}", get(uuid_col)),
+            HealthSource::SamsungHealth,
+            device_col
+                .map(get)
+                .filter(|d| !d.is_empty())
+                .map(|d| d.to_string()),
+            event_kind,
+            start,
+            end,
+        )?);
+    }
+    Ok(outs)
+}
+
+fn find_files(dir: &Path) -> anyhow::Result<Vec<(SamsungFile, PathBuf)>> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(dir).with_context(|| format!("reading {dir:?}"))? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if let Some(kind) = SamsungFile::from_filename(&name) {
+            files.push((kind, path));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[async_trait]
+impl Importable for SamsungHealthImportArgs {
+    fn source_path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+
+    async fn import(&self) -> ImportResult {
+        let mut outs = vec![];
+        for (kind, file) in find_files(&self.path)? {
+            log::debug!("reading {:?} as {:?}", file, kind);
+            outs.extend(
+                parse_samsung_csv(kind, File::open(&file)?)
+                    .with_context(|| format!("parsing {file:?}"))?,
+            );
+        }
+        log::info!("got {} samsung health events", outs.len());
+        Ok(Box::pin(futures::stream::once(futures::future::ok(outs))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_samsung_csv() -> anyhow::Result<()> {
+        assert_eq!(
+            SamsungFile::from_filename("com.samsung.shealth.exercise.20210102103000.csv"),
+            Some(SamsungFile::Exercise)
+        );
+        assert_eq!(
+            SamsungFile::from_filename("com.samsung.shealth.exercise.weather.20210102103000.csv"),
+            None
+        );
+        let csv = "com.samsung.shealth.exercise,6312005,4
+com.samsung.health.exercise.start_time,com.samsung.health.exercise.exercise_type,com.samsung.health.exercise.end_time,com.samsung.health.exercise.time_offset,com.samsung.health.exercise.deviceuuid,com.samsung.health.exercise.datauuid,
+2021-01-01 17:00:00.000,1002,2021-01-01 17:30:00.000,UTC+0100,UiYRbGJhtq,7f8f6f0e-1c2d-4e5f-8a9b-0c1d2e3f4a5b,
+";
+        let events = parse_samsung_csv(SamsungFile::Exercise, csv.as_bytes())?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].id,
+            "samsung_health.7f8f6f0e-1c2d-4e5f-8a9b-0c1d2e3f4a5b"
+        );
+        assert_eq!(events[0].duration_ms, 30 * 60 * 1000);
+        let tags = deserialize_captured((&events[0].data_type, &events[0].data))?
+            .extract_info()
+            .unwrap();
+        assert!(tags.has_value("physical-activity", "running"));
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+// import scrobbles from Last.fm and ListenBrainz exports
+// scrobbles only contain the time the track started, so the duration is the track length if known,
+// otherwise the time until the next scrobble (at most DEFAULT_MAX_DURATION)
+use std::path::PathBuf;
+
+use crate::prelude::*;
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct LastFmImportArgs {
+    /// csv export of lastfm-to-csv (artist,album,track,date without header)
+    /// or of a lastfm exporter with a header row containing uts,artist,album,track
+    filename: PathBuf,
+}
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct ListenBrainzImportArgs {
+    /// listens json (array) or jsonl file from the ListenBrainz export
+    filename: PathBuf,
+}
+
+const DEFAULT_MAX_DURATION: chrono::Duration = chrono::Duration::minutes(5);
+
+struct Scrobble {
+    start: DateTime<Utc>,
+    duration: Option<chrono::Duration>,
+    entry: MediaEntry,
+}
+
+fn scrobble_events(mut scrobbles: Vec<Scrobble>, prefix: &str) -> anyhow::Result<Vec<NewDbEvent>> {
+    scrobbles.sort_by_key(|s| s.start);
+    let next_starts: Vec<Option<DateTime<Utc>>> = scrobbles
+        .iter()
+        .skip(1)
+        .map(|s| Some(s.start))
+        .chain(std::iter::once(None))
+        .collect();
+    let mut outs = vec![];
+    for (scrobble, next_start) in scrobbles.into_iter().zip(next_starts) {
+        let duration = scrobble.duration.unwrap_or_else(|| {
+            next_start
+                .map(|n| n - scrobble.start)
+                .unwrap_or(DEFAULT_MAX_DURATION)
+                .min(DEFAULT_MAX_DURATION)
+        });
+        outs.push(
+            CreateNewDbEvent {
+                id: format!(
+                    "{prefix}.{}.{}",
+                    scrobble.start.timestamp(),
+                    scrobble.entry.track.as_deref().unwrap_or_default()
+                ),
+                timestamp: scrobble.start,
+                duration_ms: duration.num_milliseconds(),
+                data: EventData::media_v1(scrobble.entry),
+            }
+            .try_into()?,
+        );
+    }
+    Ok(outs)
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s.to_string())
+    }
+}
+
+fn parse_lastfm_csv(input: impl Read) -> anyhow::Result<Vec<Scrobble>> {
+    let mut csv = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(input);
+    let mut records = csv.records().peekable();
+    // (uts, artist, album, track) column indices if there is a header
+    let header = match records.peek() {
+        Some(Ok(first)) if first.iter().any(|c| c == "uts") => {
+            let idx = |name: &str| first.iter().position(|c| c == name);
+            let columns = (idx("uts"), idx("artist"), idx("album"), idx("track"));
+            records.next();
+            Some(columns)
+        }
+        _ => None,
+    };
+    let mut scrobbles = vec![];
+    for record in records {
+        let record = record?;
+        let get = |i: Option<usize>| i.and_then(|i| record.get(i)).unwrap_or_default();
+        let (start, artist, album, track) = match header {
+            Some((uts, artist, album, track)) => {
+                let uts: i64 = get(uts).parse().context("invalid uts")?;
+                (
+                    util::unix_epoch_millis_to_date(uts * 1000),
+                    get(artist),
+                    get(album),
+                    get(track),
+                )
+            }
+            None => {
+                let date = get(Some(3));
+                // e.g. 31 Jan 2021 10:00
+                let start = NaiveDateTime::parse_from_str(date, "%d %b %Y %H:%M")
+                    .with_context(|| format!("invalid date {date}"))?;
+                (
+                    Utc.from_utc_datetime(&start),
+                    get(Some(0)),
+                    get(Some(1)),
+                    get(Some(2)),
+                )
+            }
+        };
+        scrobbles.push(Scrobble {
+            start,
+            duration: None,
+            entry: MediaEntry {
+                source: MediaSource::LastFm,
+                artist: non_empty(artist),
+                track: non_empty(track),
+                album: non_empty(album),
+                media_type: Some("music".to_string()),
+            },
+        });
+    }
+    Ok(scrobbles)
+}
+
+#[derive(Debug, Deserialize)]
+struct Listen {
+    listened_at: i64,
+    track_metadata: ListenTrackMetadata,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListenTrackMetadata {
+    artist_name: Option<String>,
+    track_name: Option<String>,
+    release_name: Option<String>,
+    #[serde(default)]
+    additional_info: ListenAdditionalInfo,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct ListenAdditionalInfo {
+    duration_ms: Option<i64>,
+    duration: Option<i64>,
+}
+
+fn parse_listenbrainz(input: &str) -> anyhow::Result<Vec<Scrobble>> {
+    let listens: Vec<Listen> = if input.trim_start().starts_with('[') {
+        serde_json::from_str(input).context("parsing listens")?
+    } else {
+        input
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| serde_json::from_str(l).context("parsing listen"))
+            .collect::<anyhow::Result<_>>()?
+    };
+    Ok(listens
+        .into_iter()
+        .map(|listen| {
+            let info = &listen.track_metadata.additional_info;
+            let duration_ms = info.duration_ms.or(info.duration.map(|s| s * 1000));
+            Scrobble {
+                start: util::unix_epoch_millis_to_date(listen.listened_at * 1000),
+                duration: duration_ms.map(chrono::Duration::milliseconds),
+                entry: MediaEntry {
+                    source: MediaSource::ListenBrainz,
+                    artist: listen.track_metadata.artist_name,
+                    track: listen.track_metadata.track_name,
+                    album: listen.track_metadata.release_name,
+                    media_type: Some("music".to_string()),
+                },
+            }
+        })
+        .collect())
+}
+
+#[async_trait]
+impl Importable for LastFmImportArgs {
+    fn source_path(&self) -> Option<PathBuf> {
+        Some(self.filename.clone())
+    }
+
+    async fn import(&self) -> ImportResult {
+        let scrobbles = parse_lastfm_csv(File::open(&self.filename)?)
+            .with_context(|| format!("parsing {:?}", self.filename))?;
+        let outs = scrobble_events(scrobbles, "lastfm")?;
+        log::info!("got {} scrobbles", outs.len());
+        Ok(Box::pin(futures::stream::once(futures::future::ok(outs))))
+    }
+}
+
+#[async_trait]
+impl Importable for ListenBrainzImportArgs {
+    fn source_path(&self) -> Option<PathBuf> {
+        Some(self.filename.clone())
+    }
+
+    async fn import(&self) -> ImportResult {
+        let input = std::fs::read_to_string(&self.filename)?;
+        let outs = scrobble_events(parse_listenbrainz(&input)?, "listenbrainz")?;
+        log::info!("got {} listens", outs.len());
+        Ok(Box::pin(futures::stream::once(futures::future::ok(outs))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lastfm_durations() -> anyhow::Result<()> {
+        let csv = "Radiohead,OK Computer,Airbag,31 Jan 2021 10:00
+Radiohead,OK Computer,Paranoid Android,31 Jan 2021 10:04
+Radiohead,OK Computer,Subterranean Homesick Alien,31 Jan 2021 11:00
+";
+        let events = scrobble_events(parse_lastfm_csv(csv.as_bytes())?, "lastfm")?;
+        let durations: Vec<_> = events.iter().map(|e| e.duration_ms / 60_000).collect();
+        assert_eq!(durations, vec![4, 5, 5]);
+        let tags = deserialize_captured((&events[1].data_type, &events[1].data))?;
+        assert!(tags.is_background());
+        let tags = tags.extract_info().unwrap();
+        assert!(tags.has_value("media-track", "Paranoid Android"));
+        assert!(tags.has_value("media-source", "LastFm"));
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use crate::prelude::*;
 
-#[derive(StructOpt)]
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
 pub struct SleepAsAndroidImportArgs {
     filename: String,
 }
@@ -12,22 +12,153 @@ pub struct SleepAsAndroidEntry {
     noise_row: Option<Vec<String>>,
 }
 
+/// part of a night that was spent in one sleep phase (deep, light, rem or awake),
+/// created from the DEEP_START, LIGHT_START, ... events of a SleepAsAndroidEntry
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct SleepAsAndroidPhase {
+    sleep_id: String,
+    phase: String,
+}
+
+/// movement (and noise, if it was recorded) during one interval of a night,
+/// from the time-labelled actigraphy columns of a SleepAsAndroidEntry
+#[derive(Debug, Serialize, Deserialize, TypeScriptify, Clone)]
+pub struct SleepAsAndroidActigraphy {
+    sleep_id: String,
+    movement: f64,
+    noise: Option<f64>,
+}
+
+impl SleepAsAndroidEntry {
+    fn get(&self, column: &str) -> Option<&str> {
+        let idx = self.header_row.iter().position(|h| h == column)?;
+        self.data_row.get(idx).map(|s| s.as_str())
+    }
+
+    /// numeric column, negative values mean unknown
+    fn get_number(&self, column: &str) -> Option<f64> {
+        self.get(column)
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|v| *v >= 0.0)
+    }
+
+    /// the entries of the "Event" columns, e.g. DEEP_START-1508619383000 or HR-1508619383000-63.5
+    fn events(&self) -> impl Iterator<Item = (&str, DateTime<Utc>)> {
+        self.header_row
+            .iter()
+            .zip(self.data_row.iter())
+            .filter(|(h, _)| *h == "Event")
+            .filter_map(|(_, e)| {
+                let mut parts = e.splitn(3, '-');
+                let name = parts.next()?;
+                let timestamp: i64 = parts.next()?.parse().ok()?;
+                Some((name, util::unix_epoch_millis_to_date(timestamp)))
+            })
+    }
+
+    /// the actigraphy intervals between the given times. the header of an actigraphy column is the local time
+    /// the interval starts at (e.g. 23:15), the data row contains the movement and the noise row the noise
+    fn actigraphy(
+        &self,
+        tz: chrono_tz::Tz,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>, f64, Option<f64>)> {
+        let mut previous = from.with_timezone(&tz).naive_local();
+        let mut starts = vec![];
+        for (idx, header) in self.header_row.iter().enumerate() {
+            let Ok(time) = NaiveTime::parse_from_str(header, "%H:%M") else {
+                continue;
+            };
+            let Some(movement) = self.data_row.get(idx).and_then(|v| v.parse::<f64>().ok()) else {
+                continue;
+            };
+            let noise = self
+                .noise_row
+                .as_ref()
+                .and_then(|n| n.get(idx))
+                .and_then(|v| v.parse::<f64>().ok());
+            let mut start = previous.date().and_time(time);
+            // the first column may be a few minutes before the start of the night, later ones wrap at midnight
+            if start < previous - chrono::Duration::hours(1) {
+                start += chrono::Duration::days(1);
+            }
+            previous = start;
+            if let Some(start) = tz.from_local_datetime(&start).earliest() {
+                starts.push((start.with_timezone(&Utc).clamp(from, to), movement, noise));
+            }
+        }
+        let ends: Vec<DateTime<Utc>> = starts
+            .iter()
+            .skip(1)
+            .map(|(start, _, _)| *start)
+            .chain(std::iter::once(to))
+            .collect();
+        starts
+            .into_iter()
+            .zip(ends)
+            .map(|((start, movement, noise), end)| (start, end, movement, noise))
+            .filter(|(start, end, _, _)| end > start)
+            .collect()
+    }
+
+    /// the sleep phases between the given times, from the *_START and *_END events
+    fn phases(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<(String, DateTime<Utc>, DateTime<Utc>)> {
+        let mut events: Vec<_> = self.events().collect();
+        events.sort_by_key(|(_, t)| *t);
+        let mut phases = vec![];
+        let mut current: Option<(&str, DateTime<Utc>)> = None;
+        for (name, time) in events {
+            let time = time.clamp(from, to);
+            let (phase, is_start) = match name {
+                "DEEP_START" => ("deep", true),
+                "DEEP_END" => ("deep", false),
+                "LIGHT_START" => ("light", true),
+                "LIGHT_END" => ("light", false),
+                "REM_START" => ("rem", true),
+                "REM_END" => ("rem", false),
+                "AWAKE_START" => ("awake", true),
+                "AWAKE_END" => ("awake", false),
+                _ => continue,
+            };
+            if is_start || current.map(|(p, _)| p) == Some(phase) {
+                if let Some((p, start)) = current.take() {
+                    phases.push((p.to_string(), start, time));
+                }
+            }
+            if is_start {
+                current = Some((phase, time));
+            }
+        }
+        if let Some((p, start)) = current {
+            phases.push((p.to_string(), start, to));
+        }
+        phases.retain(|(_, start, end)| end > start);
+        phases
+    }
+}
+
 fn parse_saa_entry(
     header_row: &mut Option<Vec<String>>,
     data_row: &mut Option<Vec<String>>,
     noise_row: &mut Option<Vec<String>>,
-) -> anyhow::Result<Option<NewDbEvent>> {
+) -> anyhow::Result<Vec<NewDbEvent>> {
     if let Some(header) = header_row.take() {
         let data = data_row.take().unwrap();
         let noise = noise_row.take();
         let id = format!("sleep_as_android_{}", data[0]); // in theory not globally unique, but very unlikely multiple events started in same millisecond
 
+        let tzz: chrono_tz::Tz = data[1]
+            .parse()
+            .map_err(|e| anyhow::anyhow!("unknown time zone {}: {}", data[1], e))?;
         let (from, to) = {
-            let tz = &data[1];
             let from = &data[2];
             let to = &data[3];
-            log::debug!("tz={}, from={}, to={}", tz, from, to);
-            let tzz: chrono_tz::Tz = tz.parse().unwrap();
+            log::debug!("tz={}, from={}, to={}", tzz, from, to);
 
             let from = NaiveDateTime::parse_from_str(from, "%d. %m. %Y %H:%M")?;
             let to = NaiveDateTime::parse_from_str(to, "%d. %m. %Y %H:%M")?;
@@ -39,26 +170,64 @@ fn parse_saa_entry(
             (from, to)
         };
 
-        Ok(Some(
+        let from = from.with_timezone(&chrono::Utc);
+        let to = to.with_timezone(&chrono::Utc);
+        let entry = SleepAsAndroidEntry {
+            header_row: header,
+            data_row: data,
+            noise_row: noise,
+        };
+        let mut events = vec![];
+        for (phase, start, end) in entry.phases(from, to) {
+            events.push(
+                CreateNewDbEvent {
+                    id: format!("{id}.{phase}.{}", start.timestamp_millis()),
+                    data: EventData::sleep_as_android_phase_v1(SleepAsAndroidPhase {
+                        sleep_id: id.clone(),
+                        phase,
+                    }),
+                    timestamp: start,
+                    duration_ms: (end - start).num_milliseconds(),
+                }
+                .try_into()?,
+            );
+        }
+        for (start, end, movement, noise) in entry.actigraphy(tzz, from, to) {
+            events.push(
+                CreateNewDbEvent {
+                    id: format!("{id}.actigraphy.{}", start.timestamp_millis()),
+                    data: EventData::sleep_as_android_actigraphy_v1(SleepAsAndroidActigraphy {
+                        sleep_id: id.clone(),
+                        movement,
+                        noise,
+                    }),
+                    timestamp: start,
+                    duration_ms: (end - start).num_milliseconds(),
+                }
+                .try_into()?,
+            );
+        }
+        events.push(
             CreateNewDbEvent {
                 id,
-                data: EventData::sleep_as_android_v1(SleepAsAndroidEntry {
-                    header_row: header,
-                    data_row: data,
-                    noise_row: noise,
-                }),
-                timestamp: from.with_timezone(&chrono::Utc),
+                data: EventData::sleep_as_android_v1(entry),
+                timestamp: from,
                 duration_ms: to.signed_duration_since(from).num_milliseconds(),
             }
             .try_into()?,
-        ))
+        );
+        Ok(events)
     } else {
-        Ok(None)
+        Ok(vec![])
     }
 }
 
 #[async_trait]
 impl Importable for SleepAsAndroidImportArgs {
+    fn source_path(&self) -> Option<std::path::PathBuf> {
+        Some(std::path::PathBuf::from(&self.filename))
+    }
+
     async fn import(&self) -> ImportResult {
         let mut entries = Vec::new();
         let mut csv = csv::ReaderBuilder::new()
@@ -74,9 +243,11 @@ impl Importable for SleepAsAndroidImportArgs {
             let record = result?;
             if record.get(0) == Some("Id") {
                 offset_from_id_row = 0;
-                if let Some(e) = parse_saa_entry(&mut header_row, &mut data_row, &mut noise_row)? {
-                    entries.push(e)
-                }
+                entries.extend(parse_saa_entry(
+                    &mut header_row,
+                    &mut data_row,
+                    &mut noise_row,
+                )?);
             }
             match offset_from_id_row {
                 0 => header_row = Some(record.iter().map(|e| e.to_string()).collect()),
@@ -88,9 +259,11 @@ impl Importable for SleepAsAndroidImportArgs {
         }
 
         // last row
-        if let Some(e) = parse_saa_entry(&mut header_row, &mut data_row, &mut noise_row)? {
-            entries.push(e)
-        }
+        entries.extend(parse_saa_entry(
+            &mut header_row,
+            &mut data_row,
+            &mut noise_row,
+        )?);
 
         Ok(Box::pin(futures::stream::once(futures::future::ok(
             entries,
@@ -102,6 +275,142 @@ impl ExtractInfo for SleepAsAndroidEntry {
     fn extract_info(&self) -> Option<Tags> {
         let mut tags = Tags::new();
         tags.add("physical-activity", "sleeping");
+        if let Some(rating) = self.get_number("Rating").filter(|r| *r > 0.0) {
+            tags.add("sleep-rating", format!("{rating}"));
+        }
+        if let Some(deep) = self.get_number("DeepSleep") {
+            tags.add("sleep-deep-percent", format!("{:.0}", deep * 100.0));
+        }
+        if let Some(snore) = self.get_number("Snore") {
+            tags.add("sleep-snore-minutes", format!("{:.0}", snore / 60.0));
+        }
+        if let Some(noise) = self.get_number("Noise") {
+            tags.add("sleep-noise-percent", format!("{:.0}", noise * 100.0));
+        }
+        if let Some(cycles) = self.get_number("Cycles") {
+            tags.add("sleep-cycles", format!("{cycles}"));
+        }
+        Some(tags)
+    }
+}
+
+impl ExtractInfo for SleepAsAndroidActigraphy {
+    fn extract_info(&self) -> Option<Tags> {
+        let mut tags = Tags::new();
+        tags.add("sleep-movement", format!("{:.1}", self.movement));
+        if let Some(noise) = self.noise {
+            tags.add("sleep-noise", format!("{noise:.1}"));
+        }
+        Some(tags)
+    }
+}
+
+impl ExtractInfo for SleepAsAndroidPhase {
+    fn extract_info(&self) -> Option<Tags> {
+        // physical-activity is set by the SleepAsAndroidEntry covering the whole night
+        let mut tags = Tags::new();
+        tags.add("sleep-phase", &self.phase);
         Some(tags)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sleep_phases() {
+        let row = |r: &[&str]| r.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let entry = SleepAsAndroidEntry {
+            header_row: row(&[
+                "Id",
+                "Tz",
+                "From",
+                "To",
+                "Rating",
+                "DeepSleep",
+                "Snore",
+                "Event",
+                "Event",
+                "Event",
+                "Event",
+                "Event",
+            ]),
+            data_row: row(&[
+                "1508619000000",
+                "Europe/Berlin",
+                "21. 10. 2017 22:50",
+                "22. 10. 2017 6:50",
+                "3.75",
+                "0.42",
+                "-1",
+                "LIGHT_START-1508619000000",
+                "DEEP_START-1508620800000",
+                "HR-1508621000000-63.5",
+                "AWAKE_START-1508623200000",
+                "AWAKE_END-1508623800000",
+            ]),
+            noise_row: None,
+        };
+        let from = util::unix_epoch_millis_to_date(1508619000000);
+        let to = util::unix_epoch_millis_to_date(1508647800000);
+        let phases: Vec<_> = entry
+            .phases(from, to)
+            .into_iter()
+            .map(|(p, start, end)| (p, (end - start).num_minutes()))
+            .collect();
+        assert_eq!(
+            phases,
+            vec![
+                ("light".to_string(), 30),
+                ("deep".to_string(), 40),
+                ("awake".to_string(), 10)
+            ]
+        );
+        let tags = entry.extract_info().unwrap();
+        assert!(tags.has_value("sleep-rating", "3.75"));
+        assert!(tags.has_value("sleep-deep-percent", "42"));
+        assert!(!tags.has("sleep-snore-minutes"));
+    }
+
+    #[test]
+    fn test_sleep_actigraphy() {
+        let row = |r: &[&str]| r.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let entry = SleepAsAndroidEntry {
+            header_row: row(&["Id", "Tz", "From", "To", "23:50", "0:10", "0:30", "Event"]),
+            data_row: row(&[
+                "1508622600000",
+                "Europe/Berlin",
+                "21. 10. 2017 23:50",
+                "22. 10. 2017 0:40",
+                "1.5",
+                "0.25",
+                "3.0",
+                "LIGHT_START-1508622600000",
+            ]),
+            noise_row: Some(row(&["", "", "", "", "0.1", "0.05", "0.2"])),
+        };
+        let from = util::unix_epoch_millis_to_date(1508622600000);
+        let to = util::unix_epoch_millis_to_date(1508625600000);
+        let intervals: Vec<_> = entry
+            .actigraphy(chrono_tz::Europe::Berlin, from, to)
+            .into_iter()
+            .map(|(start, end, movement, noise)| {
+                (
+                    (start - from).num_minutes(),
+                    (end - start).num_minutes(),
+                    movement,
+                    noise,
+                )
+            })
+            .collect();
+        assert_eq!(
+            intervals,
+            vec![
+                (0, 20, 1.5, Some(0.1)),
+                (20, 20, 0.25, Some(0.05)),
+                (40, 10, 3.0, Some(0.2))
+            ]
+        );
+    }
+}
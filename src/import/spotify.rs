@@ -0,0 +1,159 @@
+// import the streaming history of a Spotify data export (https://www.spotify.com/account/privacy/)
+// the extended streaming history (Streaming_History_Audio_*.json) contains the end time and the played duration of every stream,
+// the account data export (StreamingHistory*.json) only the end time with minute precision
+use std::path::{Path, PathBuf};
+
+use crate::prelude::*;
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct SpotifyImportArgs {
+    /// a streaming history json file or the directory containing them
+    path: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SpotifyStream {
+    Extended {
+        /// end of the stream
+        ts: DateTime<Utc>,
+        ms_played: i64,
+        master_metadata_track_name: Option<String>,
+        master_metadata_album_artist_name: Option<String>,
+        master_metadata_album_album_name: Option<String>,
+        spotify_track_uri: Option<String>,
+        episode_name: Option<String>,
+        episode_show_name: Option<String>,
+        spotify_episode_uri: Option<String>,
+    },
+    Basic {
+        /// e.g. 2021-01-01 10:03 (UTC)
+        #[serde(rename = "endTime")]
+        end_time: String,
+        #[serde(rename = "artistName")]
+        artist_name: String,
+        #[serde(rename = "trackName")]
+        track_name: String,
+        #[serde(rename = "msPlayed")]
+        ms_played: i64,
+    },
+}
+
+fn stream_event(stream: SpotifyStream) -> anyhow::Result<Option<NewDbEvent>> {
+    let (id, end, ms_played, entry) = match stream {
+        SpotifyStream::Extended {
+            ts,
+            ms_played,
+            master_metadata_track_name,
+            master_metadata_album_artist_name,
+            master_metadata_album_album_name,
+            spotify_track_uri,
+            episode_name,
+            episode_show_name,
+            spotify_episode_uri,
+        } => {
+            let is_episode = spotify_episode_uri.is_some();
+            let uri = spotify_track_uri
+                .or(spotify_episode_uri)
+                .unwrap_or_default();
+            let entry = if is_episode {
+                MediaEntry {
+                    source: MediaSource::Spotify,
+                    artist: episode_show_name,
+                    track: episode_name,
+                    album: None,
+                    media_type: Some("podcast".to_string()),
+                }
+            } else {
+                MediaEntry {
+                    source: MediaSource::Spotify,
+                    artist: master_metadata_album_artist_name,
+                    track: master_metadata_track_name,
+                    album: master_metadata_album_album_name,
+                    media_type: Some("music".to_string()),
+                }
+            };
+            (
+                format!("spotify.{}.{}", ts.timestamp_millis(), uri),
+                ts,
+                ms_played,
+                entry,
+            )
+        }
+        SpotifyStream::Basic {
+            end_time,
+            artist_name,
+            track_name,
+            ms_played,
+        } => {
+            let end = Utc.from_utc_datetime(
+                &NaiveDateTime::parse_from_str(&end_time, "%Y-%m-%d %H:%M")
+                    .with_context(|| format!("invalid endTime {end_time}"))?,
+            );
+            (
+                format!("spotify.{}.{}", end.timestamp_millis(), track_name),
+                end,
+                ms_played,
+                MediaEntry {
+                    source: MediaSource::Spotify,
+                    artist: Some(artist_name),
+                    track: Some(track_name),
+                    album: None,
+                    media_type: None,
+                },
+            )
+        }
+    };
+    if ms_played <= 0 {
+        return Ok(None);
+    }
+    Ok(Some(
+        CreateNewDbEvent {
+            id,
+            timestamp: end - chrono::Duration::milliseconds(ms_played),
+            duration_ms: ms_played,
+            data: EventData::media_v1(entry),
+        }
+        .try_into()?,
+    ))
+}
+
+fn history_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = vec![];
+    for entry in std::fs::read_dir(path).with_context(|| format!("reading {path:?}"))? {
+        let file = entry?.path();
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        // Streaming_History_Video_*.json is skipped, video is not a background activity
+        if (name.starts_with("Streaming_History_Audio") || name.starts_with("StreamingHistory"))
+            && name.ends_with(".json")
+        {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[async_trait]
+impl Importable for SpotifyImportArgs {
+    fn source_path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+
+    async fn import(&self) -> ImportResult {
+        let mut outs = vec![];
+        for file in history_files(&self.path)? {
+            let streams: Vec<SpotifyStream> =
+                serde_json::from_reader(std::io::BufReader::new(File::open(&file)?))
+                    .with_context(|| format!("parsing {file:?}"))?;
+            for stream in streams {
+                outs.extend(stream_event(stream)?);
+            }
+        }
+        log::info!("got {} spotify streams", outs.len());
+        Ok(Box::pin(futures::stream::once(futures::future::ok(outs))))
+    }
+}
@@ -2,18 +2,41 @@ use std::str::FromStr;
 
 use crate::prelude::*;
 use futures::StreamExt;
-use sqlx::{sqlite::SqliteConnectOptions, ConnectOptions};
+use sqlx::{sqlite::SqliteConnectOptions, ConnectOptions, SqliteConnection};
 
-#[derive(StructOpt)]
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
 pub struct TimetrackrsImportArgs {
     /// path to import raw_events.sqlite3
     filename: String,
     /// the previously seen max sequence. skips importing older events defaults to 0
     last_id: Option<i64>,
 }
-#[async_trait]
-impl Importable for TimetrackrsImportArgs {
-    async fn import(&self) -> ImportResult {
+type Page = Option<(Vec<NewDbEvent>, (SqliteConnection, i64))>;
+
+async fn fetch_page(mut db: SqliteConnection, last_id: i64, max_id: i64) -> anyhow::Result<Page> {
+    let events = sqlx::query_as!(
+        DbEvent,
+        r#"select
+            insertion_sequence, id, timestamp_unix_ms as "timestamp_unix_ms: _",
+            data_type, duration_ms, data
+        from raw_events.events where insertion_sequence > ? and insertion_sequence <= ?
+        order by insertion_sequence asc limit 1000"#,
+        last_id,
+        max_id
+    )
+    .fetch_all(&mut db)
+    .await
+    .context("chunk")?;
+    let Some(next_id) = events.last().map(|e| e.insertion_sequence) else {
+        return Ok(None);
+    };
+    let events = events.into_iter().map(NewDbEvent::from).collect();
+    Ok(Some((events, (db, next_id))))
+}
+
+impl TimetrackrsImportArgs {
+    /// returns the events with insertion_sequence > last_id and the new max insertion_sequence
+    async fn import_since(&self, last_id: i64) -> IncrementalImportResult {
         let mut db = SqliteConnectOptions::from_str(&self.filename)?
             .connect()
             .await?;
@@ -29,26 +52,29 @@ impl Importable for TimetrackrsImportArgs {
             .await?;
         println!("have {x}");
 
-        let db = Box::leak(Box::new(db)); // shh bby is ok
-        let last_id = Box::leak(self.last_id.unwrap_or(0).into());
         let is_new_db: i32 = sqlx::query_scalar!(
             "select count(*) from pragma_table_info('events') where name = 'timestamp_unix_ms'"
         )
-        .fetch_one(&mut *db)
+        .fetch_one(&mut db)
         .await?;
-        let raws = {
-            if is_new_db == 1 {
-                sqlx::query_as!(
-                    NewDbEvent,
-                    r#"select
-                id, timestamp_unix_ms as "timestamp_unix_ms: _",
-                data_type, duration_ms, data
-            from raw_events.events where insertion_sequence > ?"#,
-                    *last_id
-                ).fetch(db)
-            } else {
-                println!("detected legacy db format");
-                sqlx::query_as(r#"
+        if is_new_db == 1 {
+            let max_id: i64 = sqlx::query_scalar!(
+                r#"select coalesce(max(insertion_sequence), 0) as "m!: i64" from raw_events.events"#
+            )
+            .fetch_one(&mut db)
+            .await?;
+            // fetch in pages so the connection can be owned by the stream
+            let raws = futures::stream::try_unfold((db, last_id), move |(db, last_id)| {
+                fetch_page(db, last_id, max_id)
+            });
+            Ok((
+                Box::pin(raws),
+                Some(ImportCursor::InsertionSequence { max: max_id }),
+            ))
+        } else {
+            println!("detected legacy db format");
+            let db = Box::leak(Box::new(db)); // shh bby is ok
+            let raws = sqlx::query_as(r#"
                 SELECT
                     id,
                     cast(round((julianday (timestamp) - 2440587.5) * 86400.0 * 1000) AS int) AS timestamp_unix_ms,
@@ -57,15 +83,30 @@ impl Importable for TimetrackrsImportArgs {
                     data
                 FROM
                     raw_events.events"#).fetch(db)
-            }
+            .chunks(1000)
+            .map(|e| {
+                e.into_iter()
+                    .collect::<sqlx::Result<Vec<NewDbEvent>>>()
+                    .context("chunk")
+            });
+            Ok((Box::pin(raws), None))
         }
-        .chunks(1000)
-        .map(|e| {
-            e.into_iter()
-                .collect::<sqlx::Result<Vec<NewDbEvent>>>()
-                .context("chunk")
-        });
-        Ok(Box::pin(raws))
+    }
+}
+
+#[async_trait]
+impl Importable for TimetrackrsImportArgs {
+    async fn import(&self) -> ImportResult {
+        let (raws, _) = self.import_since(self.last_id.unwrap_or(0)).await?;
+        Ok(raws)
+    }
+
+    async fn import_incremental(&self, cursor: Option<ImportCursor>) -> IncrementalImportResult {
+        let last_id = match cursor {
+            Some(ImportCursor::InsertionSequence { max }) => max,
+            _ => self.last_id.unwrap_or(0),
+        };
+        self.import_since(last_id).await
     }
 }
 /* INSERT INTO raw_events.events
@@ -0,0 +1,208 @@
+// import intervals from timewarrior data files (~/.timewarrior/data/*.data)
+// format: https://timewarrior.net/docs/api/
+// inc 20210101T100000Z - 20210101T110000Z # tag1 "tag with space" # "annotation"
+use std::path::{Path, PathBuf};
+
+use crate::prelude::*;
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct TimewarriorImportArgs {
+    /// path to the timewarrior data directory (e.g. ~/.timewarrior/data) or a single .data file
+    path: PathBuf,
+    /// use the first tag of each interval as the project (unless a `project:` tag exists)
+    #[structopt(long)]
+    #[serde(default)]
+    first_tag_is_project: bool,
+}
+
+#[derive(Debug, PartialEq)]
+struct TimewarriorInterval {
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+    tags: Vec<String>,
+    annotation: Option<String>,
+}
+
+fn parse_timewarrior_date(s: &str) -> anyhow::Result<DateTime<Utc>> {
+    Ok(Utc.from_utc_datetime(
+        &NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%SZ")
+            .with_context(|| format!("invalid timewarrior date '{s}'"))?,
+    ))
+}
+
+// split on spaces, keeping "quoted strings" (with \" escapes) together
+fn tokenize(s: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c == ' ' {
+            chars.next();
+            continue;
+        }
+        let mut token = String::new();
+        if c == '"' {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some(escaped) = chars.next() {
+                            token.push(escaped);
+                        }
+                    }
+                    '"' => break,
+                    c => token.push(c),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c == ' ' {
+                    break;
+                }
+                token.push(c);
+                chars.next();
+            }
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+fn parse_timewarrior_line(line: &str) -> anyhow::Result<Option<TimewarriorInterval>> {
+    let line = line.trim();
+    let Some(rest) = line.strip_prefix("inc ") else {
+        return Ok(None);
+    };
+    let (range, extra) = match rest.split_once(" # ") {
+        Some((range, extra)) => (range, Some(extra)),
+        None => (rest.trim_end_matches(" #"), None),
+    };
+    let (start, end) = match range.split_once(" - ") {
+        Some((start, end)) => (start.trim(), Some(end.trim())),
+        None => (range.trim(), None),
+    };
+    let start = parse_timewarrior_date(start)?;
+    let end = end.map(parse_timewarrior_date).transpose()?;
+
+    let mut tags = Vec::new();
+    let mut annotation = None;
+    if let Some(extra) = extra {
+        let tokens = tokenize(extra);
+        let mut tokens = tokens.into_iter();
+        for token in tokens.by_ref() {
+            if token == "#" {
+                break;
+            }
+            tags.push(token);
+        }
+        let rest: Vec<String> = tokens.collect();
+        if !rest.is_empty() {
+            annotation = Some(rest.join(" "));
+        }
+    }
+    Ok(Some(TimewarriorInterval {
+        start,
+        end,
+        tags,
+        annotation,
+    }))
+}
+
+fn list_data_files(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = vec![];
+    for entry in std::fs::read_dir(path).with_context(|| format!("reading dir {path:?}"))? {
+        let path = entry?.path();
+        if path.extension().map(|e| e == "data").unwrap_or(false) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[async_trait]
+impl Importable for TimewarriorImportArgs {
+    fn source_path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+
+    async fn import(&self) -> ImportResult {
+        let mut outs: Vec<NewDbEvent> = Vec::new();
+        for file in list_data_files(&self.path)? {
+            log::info!("reading {:?}", file);
+            let content =
+                std::fs::read_to_string(&file).with_context(|| format!("reading {file:?}"))?;
+            for line in content.lines() {
+                let interval = parse_timewarrior_line(line)
+                    .with_context(|| format!("parsing timewarrior line '{line}'"))?;
+                let Some(interval) = interval else {
+                    continue;
+                };
+                let Some(end) = interval.end else {
+                    // still running, will be imported once it is stopped
+                    log::info!("skipping open interval starting at {}", interval.start);
+                    continue;
+                };
+                let (mut project, client, mut tags) = split_project_client_tags(interval.tags);
+                if project.is_none() && self.first_tag_is_project && !tags.is_empty() {
+                    project = Some(tags.remove(0));
+                }
+                outs.push(
+                    CreateNewDbEvent {
+                        // timewarrior does not allow overlapping intervals, so the start is unique
+                        id: format!("timewarrior.{}", interval.start.timestamp()),
+                        timestamp: interval.start,
+                        duration_ms: end.signed_duration_since(interval.start).num_milliseconds(),
+                        data: EventData::manual_tracker_v1(ManualTrackerEntry {
+                            source: ManualTrackerSource::Timewarrior,
+                            project,
+                            client,
+                            tags,
+                            description: interval.annotation,
+                        }),
+                    }
+                    .try_into()?,
+                );
+            }
+        }
+        log::info!("got {} intervals", outs.len());
+        Ok(Box::pin(futures::stream::once(futures::future::ok(outs))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timewarrior_line() {
+        let parsed = parse_timewarrior_line(
+            r#"inc 20210101T100000Z - 20210101T113000Z # project:foo "tag with space" bar # "did \"things\"""#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            parsed,
+            TimewarriorInterval {
+                start: Utc.with_ymd_and_hms(2021, 1, 1, 10, 0, 0).unwrap(),
+                end: Some(Utc.with_ymd_and_hms(2021, 1, 1, 11, 30, 0).unwrap()),
+                tags: vec![
+                    "project:foo".to_string(),
+                    "tag with space".to_string(),
+                    "bar".to_string()
+                ],
+                annotation: Some(r#"did "things""#.to_string()),
+            }
+        );
+
+        let open = parse_timewarrior_line("inc 20210101T100000Z")
+            .unwrap()
+            .unwrap();
+        assert_eq!(open.end, None);
+        assert!(open.tags.is_empty());
+
+        assert!(parse_timewarrior_line("").unwrap().is_none());
+    }
+}
@@ -0,0 +1,187 @@
+// import time entries from Toggl Track exports
+// supports the detailed report CSV export as well as JSON exports (reports api / time entries api)
+use std::path::PathBuf;
+
+use crate::prelude::*;
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct TogglImportArgs {
+    /// path to the exported .csv or .json file
+    filename: PathBuf,
+    /// time zone of the dates in the CSV export (the CSV does not contain time zone info).
+    /// defaults to the local time zone
+    #[structopt(long)]
+    timezone: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TogglCsvRow {
+    #[serde(rename = "Email")]
+    email: Option<String>,
+    #[serde(rename = "Client")]
+    client: Option<String>,
+    #[serde(rename = "Project")]
+    project: Option<String>,
+    #[serde(rename = "Description")]
+    description: Option<String>,
+    #[serde(rename = "Start date")]
+    start_date: String,
+    #[serde(rename = "Start time")]
+    start_time: String,
+    #[serde(rename = "End date")]
+    end_date: String,
+    #[serde(rename = "End time")]
+    end_time: String,
+    #[serde(rename = "Tags")]
+    tags: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TogglJsonEntry {
+    id: i64,
+    description: Option<String>,
+    #[serde(alias = "project_name")]
+    project: Option<String>,
+    #[serde(alias = "client_name")]
+    client: Option<String>,
+    start: String,
+    #[serde(alias = "end")]
+    stop: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TogglJsonExport {
+    /// reports api v2 `{"data": [...]}`
+    Wrapped { data: Vec<TogglJsonEntry> },
+    /// time entries api / data export `[...]`
+    List(Vec<TogglJsonEntry>),
+}
+
+fn non_empty(s: Option<String>) -> Option<String> {
+    s.filter(|s| !s.is_empty())
+}
+
+fn entry_to_event(
+    id: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    project: Option<String>,
+    client: Option<String>,
+    tags: Vec<String>,
+    description: Option<String>,
+) -> anyhow::Result<NewDbEvent> {
+    CreateNewDbEvent {
+        id,
+        timestamp: start,
+        duration_ms: end.signed_duration_since(start).num_milliseconds(),
+        data: EventData::manual_tracker_v1(ManualTrackerEntry {
+            source: ManualTrackerSource::Toggl,
+            project: non_empty(project),
+            client: non_empty(client),
+            tags,
+            description: non_empty(description),
+        }),
+    }
+    .try_into()
+}
+
+impl TogglImportArgs {
+    fn parse_local(&self, date: &str, time: &str) -> anyhow::Result<DateTime<Utc>> {
+        let naive = NaiveDateTime::parse_from_str(&format!("{date} {time}"), "%Y-%m-%d %H:%M:%S")
+            .with_context(|| format!("invalid toggl date '{date} {time}'"))?;
+        let date = match &self.timezone {
+            Some(tz) => tz
+                .parse::<chrono_tz::Tz>()
+                .map_err(|e| anyhow::anyhow!("invalid time zone {tz}: {e}"))?
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|d| d.with_timezone(&Utc)),
+            None => Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|d| d.with_timezone(&Utc)),
+        };
+        date.context("impossible time")
+    }
+
+    fn import_csv(&self) -> anyhow::Result<Vec<NewDbEvent>> {
+        let mut outs = Vec::new();
+        let mut csv = csv::ReaderBuilder::new().from_reader(File::open(&self.filename)?);
+        for row in csv.deserialize::<TogglCsvRow>() {
+            let row = row.context("deser toggl csv row")?;
+            let start = self.parse_local(&row.start_date, &row.start_time)?;
+            let end = self.parse_local(&row.end_date, &row.end_time)?;
+            let tags = row
+                .tags
+                .as_deref()
+                .unwrap_or("")
+                .split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect();
+            // the csv export has no entry ids. a single user can't have two entries starting at the same time
+            let id = format!(
+                "toggl.csv.{}.{}",
+                start.timestamp(),
+                row.email.as_deref().unwrap_or("")
+            );
+            outs.push(entry_to_event(
+                id,
+                start,
+                end,
+                row.project,
+                row.client,
+                tags,
+                row.description,
+            )?);
+        }
+        Ok(outs)
+    }
+
+    fn import_json(&self) -> anyhow::Result<Vec<NewDbEvent>> {
+        let export: TogglJsonExport =
+            serde_json::from_reader(std::io::BufReader::new(File::open(&self.filename)?))
+                .context("parsing toggl json")?;
+        let entries = match export {
+            TogglJsonExport::Wrapped { data } => data,
+            TogglJsonExport::List(l) => l,
+        };
+        let mut outs = Vec::new();
+        for entry in entries {
+            let Some(stop) = entry.stop else {
+                log::info!("skipping running entry {}", entry.id);
+                continue;
+            };
+            outs.push(entry_to_event(
+                format!("toggl.{}", entry.id),
+                util::iso_string_to_datetime(&entry.start)?,
+                util::iso_string_to_datetime(&stop)?,
+                entry.project,
+                entry.client,
+                entry.tags,
+                entry.description,
+            )?);
+        }
+        Ok(outs)
+    }
+}
+
+#[async_trait]
+impl Importable for TogglImportArgs {
+    fn source_path(&self) -> Option<PathBuf> {
+        Some(self.filename.clone())
+    }
+
+    async fn import(&self) -> ImportResult {
+        let outs = match self.filename.extension().and_then(|e| e.to_str()) {
+            Some("csv") => self.import_csv()?,
+            Some("json") => self.import_json()?,
+            _ => anyhow::bail!("unknown toggl export format {:?}", self.filename),
+        };
+        log::info!("got {} time entries", outs.len());
+        Ok(Box::pin(futures::stream::once(futures::future::ok(outs))))
+    }
+}
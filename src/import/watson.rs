@@ -0,0 +1,58 @@
+// import frames from watson (https://github.com/TailorDev/Watson)
+// the frames file (~/.config/watson/frames) is a json array of
+// [start, stop, project, id, tags, updated_at]
+use std::path::PathBuf;
+
+use crate::prelude::*;
+
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+pub struct WatsonImportArgs {
+    /// path to the watson frames file (e.g. ~/.config/watson/frames)
+    filename: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct WatsonFrame(
+    i64,                             // start (unix seconds)
+    i64,                             // stop (unix seconds)
+    String,                          // project
+    String,                          // id
+    Vec<String>,                     // tags
+    #[allow(dead_code)] Option<i64>, // updated_at
+);
+
+#[async_trait]
+impl Importable for WatsonImportArgs {
+    fn source_path(&self) -> Option<PathBuf> {
+        Some(self.filename.clone())
+    }
+
+    async fn import(&self) -> ImportResult {
+        let frames: Vec<WatsonFrame> = serde_json::from_reader(std::io::BufReader::new(
+            File::open(&self.filename).context("opening watson frames file")?,
+        ))
+        .context("parsing watson frames")?;
+
+        let mut outs: Vec<NewDbEvent> = Vec::new();
+        for WatsonFrame(start, stop, project, id, tags, _) in frames {
+            let (_, client, tags) = split_project_client_tags(tags);
+            outs.push(
+                CreateNewDbEvent {
+                    id: format!("watson.{id}"),
+                    timestamp: util::unix_epoch_millis_to_date(start * 1000),
+                    duration_ms: (stop - start) * 1000,
+                    data: EventData::manual_tracker_v1(ManualTrackerEntry {
+                        source: ManualTrackerSource::Watson,
+                        project: Some(project),
+                        client,
+                        tags,
+                        description: None,
+                    }),
+                }
+                .try_into()?,
+            );
+        }
+        log::info!("got {} frames", outs.len());
+        Ok(Box::pin(futures::stream::once(futures::future::ok(outs))))
+    }
+}
@@ -1,8 +1,19 @@
 // just lots of imports cause i'm lazy
 pub use crate::api_types;
+pub use crate::capture::command::*;
+pub use crate::capture::context::CaptureContext;
+pub use crate::capture::linux::container_types::*;
+pub use crate::capture::linux::input_types::InputActivity;
+pub use crate::capture::linux::logind_types::*;
+pub use crate::capture::linux::media_types::MediaUsage;
+pub use crate::capture::linux::mpris_types::*;
+pub use crate::capture::linux::terminal_types::*;
 pub use crate::capture::linux::wayland_types::*;
 pub use crate::capture::linux::x11_types::*;
 pub use crate::capture::macos::types::*;
+pub use crate::capture::pause::{PauseRequest, PauseState, PausedEntry};
+pub use crate::capture::power::PowerState;
+pub use crate::capture::redact::*;
 pub use crate::capture::windows::types::*;
 pub use crate::capture::*;
 pub use crate::db::datybasy::*;
@@ -17,8 +28,13 @@ pub use crate::extract::fetchers::*;
 pub use crate::extract::tag_rules::*;
 pub use crate::extract::tags::*;
 pub use crate::extract::*;
+pub use crate::import::android_usage_stats::*;
 pub use crate::import::app_usage_sqlite::*;
+pub use crate::import::generic::*;
+pub use crate::import::health::*;
 pub use crate::import::journald::*;
+pub use crate::import::manual_tracker::*;
+pub use crate::import::media::*;
 pub use crate::import::sleep_as_android::*;
 pub use crate::import::*;
 pub use tracing as log;
@@ -115,6 +115,29 @@ async fn timestamp_search(
     }
 }
 
+/// battery level over time, from the power state in captured window events.
+/// gaps show when nothing was captured, e.g. after the battery died
+async fn battery_levels(
+    db: DatyBasy,
+    req: Api::battery_levels::request,
+) -> Api::battery_levels::response {
+    let levels = sqlx::query_as!(
+        BatteryLevel,
+        r#"select timestamp_unix_ms as "timestamp: _",
+    json_extract(data, '$.power.battery_percent') as "percent!: f64",
+    json_extract(data, '$.power.battery_state') as "state?: String",
+    json_extract(data, '$.power.ac_online') as "ac_online?: bool"
+    from raw_events.events
+    where timestamp_unix_ms >= ? and timestamp_unix_ms < ? and json_extract(data, '$.power.battery_percent') is not null
+    order by timestamp_unix_ms"#,
+        req.after,
+        req.before
+    )
+    .fetch_all(&db.db)
+    .await?;
+    Ok(ApiResponse { data: levels })
+}
+
 async fn time_range(db: DatyBasy, req: Api::time_range::request) -> Api::time_range::response {
     let progress = progress_events::new_progress("Extracting time range");
 
@@ -282,6 +305,17 @@ pub fn api_routes(
         })
         .boxed();
 
+    let battery_levels = with_db(db.clone())
+        .and(warp::path("battery-levels"))
+        .and(warp::query::<Api::battery_levels::request>())
+        .and_then(|db, query| async move {
+            battery_levels(db, query)
+                .await
+                .map(|e| json(&e))
+                .map_err(map_error)
+        })
+        .boxed();
+
     let get_known_tags = with_db(db.clone())
         .and(warp::path("get-known-tags"))
         .and(warp::query::<Api::get_known_tags::request>())
@@ -360,15 +394,47 @@ pub fn api_routes(
         })
         .boxed();
 
+    let pause_state = warp::path("pause-state")
+        .map(|| {
+            json(&ApiResponse {
+                data: crate::capture::pause::pause_state(),
+            })
+        })
+        .boxed();
+    let pause = warp::post()
+        .and(warp::path("pause"))
+        .and(warp::body::json())
+        .map(|req: Api::pause::request| match crate::capture::pause::pause(req) {
+            Ok(state) => warp::reply::with_status(
+                json(&ApiResponse { data: state }),
+                warp::http::StatusCode::OK,
+            ),
+            Err(err) => warp::reply::with_status(
+                ErrAsJson { err }.to_json(),
+                warp::http::StatusCode::BAD_REQUEST,
+            ),
+        })
+        .boxed();
+    let resume = warp::post()
+        .and(warp::path("resume"))
+        .map(|| {
+            json(&ApiResponse {
+                data: crate::capture::pause::resume(),
+            })
+        })
+        .boxed();
+
     let get_reqs = warp::get().and(balanced_or_tree!(
         time_range,
         get_known_tags,
         single_event,
         rule_groups,
         timestamp_search,
-        progress_events
+        battery_levels,
+        progress_events,
+        pause_state
     ));
-    let post_reqs = balanced_or_tree!(update_rule_groups, invalidate_extractions);
+    let post_reqs = balanced_or_tree!(update_rule_groups, invalidate_extractions, pause, resume);
 
     get_reqs.or(post_reqs)
 }